@@ -1,9 +1,10 @@
 use std::collections::BTreeMap;
 
-use hardware::Hardware;
+use hardware::{Hardware, SensorKind, file_sensor::FileSensor};
 
 use crate::config::Config;
-use crate::config::{control::Control, fan::Fan, temp::Temp};
+use crate::config::{control::Control, fan::Fan, sensor::Sensor, temp::Temp};
+use crate::failsafe::Failsafe;
 
 use crate::id::{Id, IdGenerator};
 use crate::node::{self, Node, NodeType, NodeTypeLight, ToNode};
@@ -17,6 +18,9 @@ pub struct AppGraph {
     pub nodes: Nodes,
     pub id_generator: IdGenerator,
     pub root_nodes: RootNodes,
+    pub failsafe: Failsafe,
+    /// kept for the config, the bridge only reads them when the app starts
+    pub file_sensors: Vec<FileSensor>,
 }
 
 impl Default for AppGraph {
@@ -31,6 +35,8 @@ impl AppGraph {
             nodes: Nodes::new(),
             id_generator: IdGenerator::new(),
             root_nodes: Vec::new(),
+            failsafe: Failsafe::default(),
+            file_sensors: Vec::new(),
         }
     }
 
@@ -79,6 +85,7 @@ impl AppGraph {
                 name: fan_h.name.clone(),
                 hardware_id: Some(fan_h.hardware_id.clone()),
                 fan_h: Some(fan_h.clone()),
+                ..Default::default()
             };
 
             let node = Node::new(NodeType::Fan(fan), &mut app_graph);
@@ -90,20 +97,37 @@ impl AppGraph {
                 name: temp_h.name.clone(),
                 hardware_id: Some(temp_h.hardware_id.clone()),
                 temp_h: Some(temp_h.clone()),
+                ..Default::default()
             };
 
             let node = Node::new(NodeType::Temp(temp), &mut app_graph);
             app_graph.insert_node(node);
         }
 
+        for kind in SensorKind::VALUES {
+            for sensor_h in hardware.sensors(kind) {
+                let sensor = Sensor {
+                    name: sensor_h.name.clone(),
+                    hardware_id: Some(sensor_h.hardware_id.clone()),
+                    kind,
+                    sensor_h: Some(sensor_h.clone()),
+                };
+
+                let node = Node::new(NodeType::Sensor(sensor), &mut app_graph);
+                app_graph.insert_node(node);
+            }
+        }
+
         app_graph
     }
 
     pub fn apply_config(&mut self, config: Config, hardware: &Hardware) {
         self.nodes.clear();
         self.root_nodes.clear();
+        self.failsafe = config.failsafe;
+        self.file_sensors = config.file_sensors;
 
-        // order: fan -> temp -> custom_temp -> behavior -> control
+        // order: fan -> temp -> sensor -> custom_temp -> smoothing -> hysteresis -> behavior -> hysteresis -> control
 
         for fan in config.fans {
             let node = fan.to_node(self, hardware);
@@ -115,11 +139,35 @@ impl AppGraph {
             self.insert_node(node);
         }
 
+        for sensor in config.sensors {
+            let node = sensor.to_node(self, hardware);
+            self.insert_node(node);
+        }
+
         for custom_temp in config.custom_temps {
             let node = custom_temp.to_node(self, hardware);
             self.insert_node(node);
         }
 
+        for smoothing in config.smoothings {
+            let node = smoothing.to_node(self, hardware);
+            self.insert_node(node);
+        }
+
+        // a hysteresis is either between a temp and a behavior, or after a behavior
+        let (before_behaviors, after_behaviors): (Vec<_>, Vec<_>) =
+            config.hystereses.into_iter().partition(|hysteresis| {
+                hysteresis
+                    .input
+                    .as_ref()
+                    .is_some_and(|input| self.is_name_taken(input))
+            });
+
+        for hysteresis in before_behaviors {
+            let node = hysteresis.to_node(self, hardware);
+            self.insert_node(node);
+        }
+
         for flat in config.flats {
             let node = flat.to_node(self, hardware);
             self.insert_node(node);
@@ -140,6 +188,21 @@ impl AppGraph {
             self.insert_node(node);
         }
 
+        for pid in config.pids {
+            let node = pid.to_node(self, hardware);
+            self.insert_node(node);
+        }
+
+        for hysteresis in after_behaviors {
+            let node = hysteresis.to_node(self, hardware);
+            self.insert_node(node);
+        }
+
+        for rpm_target in config.rpm_targets {
+            let node = rpm_target.to_node(self, hardware);
+            self.insert_node(node);
+        }
+
         for control in config.controls {
             let node = control.to_node(self, hardware);
             self.insert_node(node);
@@ -160,11 +223,16 @@ impl AppGraph {
             NodeTypeLight::Control => fl!("default_control"),
             NodeTypeLight::Fan => fl!("default_fan"),
             NodeTypeLight::Temp => fl!("default_temp"),
+            NodeTypeLight::Sensor => fl!("default_sensor"),
             NodeTypeLight::CustomTemp => fl!("default_custom_temp"),
             NodeTypeLight::Graph => fl!("default_graph"),
             NodeTypeLight::Flat => fl!("default_flat"),
             NodeTypeLight::Linear => fl!("default_linear"),
             NodeTypeLight::Target => fl!("default_target"),
+            NodeTypeLight::Hysteresis => fl!("default_hysteresis"),
+            NodeTypeLight::Smoothing => fl!("default_smoothing"),
+            NodeTypeLight::Pid => fl!("default_pid"),
+            NodeTypeLight::RpmTarget => fl!("default_rpm_target"),
         };
 
         Self::find_unused_name(&self.nodes, &default_name, 1)
@@ -188,11 +256,16 @@ impl AppGraph {
             NodeTypeLight::Control => NodeType::Control(Default::default()),
             NodeTypeLight::Fan => NodeType::Fan(Default::default()),
             NodeTypeLight::Temp => NodeType::Temp(Default::default()),
+            NodeTypeLight::Sensor => NodeType::Sensor(Default::default()),
             NodeTypeLight::CustomTemp => NodeType::CustomTemp(Default::default()),
             NodeTypeLight::Graph => NodeType::Graph(Default::default()),
             NodeTypeLight::Flat => NodeType::Flat(Default::default()),
             NodeTypeLight::Linear => NodeType::Linear(Default::default()),
             NodeTypeLight::Target => NodeType::Target(Default::default()),
+            NodeTypeLight::Hysteresis => NodeType::Hysteresis(Default::default()),
+            NodeTypeLight::Smoothing => NodeType::Smoothing(Default::default()),
+            NodeTypeLight::Pid => NodeType::Pid(Default::default()),
+            NodeTypeLight::RpmTarget => NodeType::RpmTarget(Default::default()),
         };
 
         let new_name = self.generate_default_name(node_type_light);
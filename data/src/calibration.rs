@@ -0,0 +1,363 @@
+//! Find the real operating range of a fan, by driving its control
+//! through all duty cycles and watching the speed of the fan.
+//!
+//! The routine is a state machine, advanced by [`Calibrator::poll`], so it can be
+//! driven by the update loop of the ui, or by a simple loop in the cli.
+
+use std::{
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use hardware::{HControl, HSensor, Hardware, HardwareBridge, HardwareError, Mode, Value};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CalibrationError {
+    #[error(transparent)]
+    Hardware(#[from] HardwareError),
+    #[error("The fan {0} doesn't spin at full speed")]
+    NoRpm(String),
+    #[error("The fan {0} doesn't start again, even at 100%")]
+    NoStart(String),
+    #[error("The calibration was interrupted")]
+    Interrupted,
+}
+
+type Result<T> = std::result::Result<T, CalibrationError>;
+
+/// Result of a calibration, stored per control hardware id.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Calibration {
+    /// hardware id of the control
+    #[serde(rename = "id")]
+    pub hardware_id: String,
+    /// hardware id of the fan driven by the control
+    pub fan: String,
+    /// %, a spinning fan stops at or under this duty cycle.
+    /// None when the fan never stops.
+    pub stop_pwm: Option<Value>,
+    /// %, a stopped fan starts at or above this duty cycle.
+    /// None when the fan never stops.
+    pub start_pwm: Option<Value>,
+    /// RPM, at 100%
+    pub max_rpm: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Calibrations {
+    #[serde(default, rename = "Calibration")]
+    pub data: Vec<Calibration>,
+}
+
+impl Calibrations {
+    pub fn get(&self, hardware_id: &str) -> Option<&Calibration> {
+        self.data.iter().find(|c| c.hardware_id == hardware_id)
+    }
+
+    /// Replace the previous calibration of the same control.
+    pub fn insert(&mut self, calibration: Calibration) {
+        match self
+            .data
+            .iter_mut()
+            .find(|c| c.hardware_id == calibration.hardware_id)
+        {
+            Some(previous) => *previous = calibration,
+            None => self.data.push(calibration),
+        }
+    }
+
+    /// See [`crate::config::Config::migrate_hardware_ids`]
+    pub fn migrate_hardware_ids(&mut self, hardware: &Hardware) -> bool {
+        let mut changed = false;
+
+        for hardware_id in self
+            .data
+            .iter_mut()
+            .flat_map(|c| [&mut c.hardware_id, &mut c.fan])
+        {
+            if let Some(new_id) = hardware.migrate_id(hardware_id) {
+                *hardware_id = new_id.clone();
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CalibrationParams {
+    /// %, between two measured duty cycles
+    pub step: Value,
+    /// time to wait after a change of duty cycle, before the first sample
+    pub settle_delay: Duration,
+    pub sample_interval: Duration,
+    /// maximum time spent on one duty cycle, if the speed never settles
+    pub max_settle: Duration,
+    /// RPM, under this speed the fan is considered stopped
+    pub min_rpm: Value,
+    /// RPM, maximum difference between two samples of a settled fan
+    pub tolerance: Value,
+    /// the control has an inverted PWM logic, see [`crate::config::control::Control::invert`]
+    pub invert: bool,
+}
+
+impl Default for CalibrationParams {
+    fn default() -> Self {
+        Self {
+            step: 5.0,
+            settle_delay: Duration::from_secs(3),
+            sample_interval: Duration::from_secs(1),
+            max_settle: Duration::from_secs(20),
+            min_rpm: 50.0,
+            tolerance: 30.0,
+            invert: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationPhase {
+    /// measure the speed at 100%
+    MaxRpm,
+    /// lower the duty cycle until the fan stops
+    Stop,
+    /// raise the duty cycle until the fan starts again
+    Start,
+}
+
+#[derive(Debug)]
+pub struct Calibrator {
+    control: Rc<HControl>,
+    fan: Rc<HSensor>,
+    params: CalibrationParams,
+    /// mode and value to set back, taken once restored
+    restore: Option<(Mode, Option<Value>)>,
+    phase: CalibrationPhase,
+    duty: Value,
+    changed_at: Instant,
+    last_sample: Option<(Instant, Value)>,
+    max_rpm: Value,
+    stop_pwm: Option<Value>,
+}
+
+impl Calibrator {
+    /// Take the control in manual mode and set it to 100%.
+    /// `mode` will be restored at the end of the calibration.
+    pub fn start<H: HardwareBridge>(
+        bridge: &mut H,
+        control: Rc<HControl>,
+        fan: Rc<HSensor>,
+        params: CalibrationParams,
+        mode: Mode,
+        now: Instant,
+    ) -> Result<Self> {
+        let value = match bridge.get_control_value(&control) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                warn!(
+                    "Can't read the value of {} before calibration: {e}.",
+                    control.name
+                );
+                None
+            }
+        };
+
+        let mut calibrator = Self {
+            control,
+            fan,
+            params,
+            restore: Some((mode, value)),
+            phase: CalibrationPhase::MaxRpm,
+            duty: 100.0,
+            changed_at: now,
+            last_sample: None,
+            max_rpm: 0.0,
+            stop_pwm: None,
+        };
+
+        if let Err(e) = bridge
+            .set_mode(&calibrator.control, &Mode::Manual)
+            .and_then(|_| bridge.set_value(&calibrator.control, calibrator.hardware_duty()))
+        {
+            calibrator.cancel(bridge);
+            return Err(e.into());
+        }
+
+        info!("Calibration of {} started.", calibrator.control.name);
+        Ok(calibrator)
+    }
+
+    pub fn control(&self) -> &Rc<HControl> {
+        &self.control
+    }
+
+    pub fn phase(&self) -> CalibrationPhase {
+        self.phase
+    }
+
+    /// %, duty cycle currently measured
+    pub fn duty(&self) -> Value {
+        self.duty
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.restore.is_some()
+    }
+
+    /// Advance the calibration. Should be called at least every `sample_interval`.
+    /// The original mode is restored when a result or an error is returned.
+    pub fn poll<H: HardwareBridge>(
+        &mut self,
+        bridge: &mut H,
+        now: Instant,
+    ) -> Result<Option<Calibration>> {
+        if !self.is_running() {
+            return Err(CalibrationError::Interrupted);
+        }
+
+        match self.advance(bridge, now) {
+            Ok(Some(calibration)) => {
+                self.cancel(bridge);
+                info!(
+                    "Calibration of {} done: {:?}.",
+                    self.control.name, calibration
+                );
+                Ok(Some(calibration))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => {
+                self.cancel(bridge);
+                Err(e)
+            }
+        }
+    }
+
+    /// Stop the calibration and restore the original mode.
+    pub fn cancel<H: HardwareBridge>(&mut self, bridge: &mut H) {
+        let Some((mode, value)) = self.restore.take() else {
+            return;
+        };
+
+        if let Err(e) = bridge.set_mode(&self.control, &mode) {
+            error!(
+                "Can't restore the mode of {} after calibration: {e}.",
+                self.control.name
+            );
+            return;
+        }
+
+        if mode == Mode::Manual {
+            if let Some(value) = value {
+                if let Err(e) = bridge.set_value(&self.control, value) {
+                    error!(
+                        "Can't restore the value of {} after calibration: {e}.",
+                        self.control.name
+                    );
+                }
+            }
+        }
+    }
+
+    fn advance<H: HardwareBridge>(
+        &mut self,
+        bridge: &mut H,
+        now: Instant,
+    ) -> Result<Option<Calibration>> {
+        if now.duration_since(self.changed_at) < self.params.settle_delay {
+            return Ok(None);
+        }
+
+        if let Some((sampled_at, _)) = self.last_sample {
+            if now.duration_since(sampled_at) < self.params.sample_interval {
+                return Ok(None);
+            }
+        }
+
+        let rpm = bridge.get_sensor_value(&self.fan)?;
+
+        let settled = match self.last_sample {
+            Some((_, last_rpm)) => (rpm - last_rpm).abs() <= self.params.tolerance,
+            None => false,
+        } || now.duration_since(self.changed_at) >= self.params.max_settle;
+
+        self.last_sample = Some((now, rpm));
+
+        if !settled {
+            return Ok(None);
+        }
+
+        debug!(
+            "Calibration of {}: {} RPM at {}%.",
+            self.control.name, rpm, self.duty
+        );
+
+        let spinning = rpm >= self.params.min_rpm;
+
+        match self.phase {
+            CalibrationPhase::MaxRpm => {
+                if !spinning {
+                    return Err(CalibrationError::NoRpm(self.fan.name.clone()));
+                }
+                self.max_rpm = rpm;
+                self.phase = CalibrationPhase::Stop;
+                self.set_duty(bridge, self.duty - self.params.step, now)?;
+            }
+            CalibrationPhase::Stop => {
+                if !spinning {
+                    self.stop_pwm = Some(self.duty);
+                    self.phase = CalibrationPhase::Start;
+                    self.set_duty(bridge, self.duty + self.params.step, now)?;
+                } else if self.duty == 0.0 {
+                    return Ok(Some(self.calibration(None)));
+                } else {
+                    self.set_duty(bridge, self.duty - self.params.step, now)?;
+                }
+            }
+            CalibrationPhase::Start => {
+                if spinning {
+                    return Ok(Some(self.calibration(Some(self.duty))));
+                }
+                if self.duty == 100.0 {
+                    return Err(CalibrationError::NoStart(self.fan.name.clone()));
+                }
+                self.set_duty(bridge, self.duty + self.params.step, now)?;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn set_duty<H: HardwareBridge>(
+        &mut self,
+        bridge: &mut H,
+        duty: Value,
+        now: Instant,
+    ) -> Result<()> {
+        self.duty = duty.clamp(0.0, 100.0);
+        self.changed_at = now;
+        self.last_sample = None;
+        bridge.set_value(&self.control, self.hardware_duty())?;
+        Ok(())
+    }
+
+    fn hardware_duty(&self) -> Value {
+        if self.params.invert {
+            100.0 - self.duty
+        } else {
+            self.duty
+        }
+    }
+
+    fn calibration(&self, start_pwm: Option<Value>) -> Calibration {
+        Calibration {
+            hardware_id: self.control.hardware_id.clone(),
+            fan: self.fan.hardware_id.clone(),
+            stop_pwm: self.stop_pwm,
+            start_pwm,
+            max_rpm: self.max_rpm,
+        }
+    }
+}
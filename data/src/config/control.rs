@@ -1,6 +1,9 @@
-use std::rc::Rc;
+use std::{
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
-use hardware::{HControl, Hardware, HardwareBridge, Mode, Value};
+use hardware::{HControl, HSensor, Hardware, HardwareBridge, Mode, OnExit, Value};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -9,7 +12,7 @@ use crate::{
     update::UpdateError,
 };
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Control {
     // unique
     pub name: String,
@@ -19,20 +22,111 @@ pub struct Control {
     pub input: Option<String>,
     pub active: bool,
 
+    /// %, the value written to the hardware is never under this limit
+    #[serde(default)]
+    pub min: Option<u8>,
+    /// %, the value written to the hardware is never above this limit
+    #[serde(default)]
+    pub max: Option<u8>,
+    /// write `100 - value` to the hardware, for inverted PWM logic
+    #[serde(default)]
+    pub invert: bool,
+    /// %, the value is rounded to a multiple of this step
+    #[serde(default)]
+    pub step: Option<u8>,
+    /// %, the fan is stopped when the value is under this limit
+    #[serde(default, rename = "zeroRpm", alias = "zero_rpm")]
+    pub zero_rpm: Option<u8>,
+    /// %, applied for `kick_duration` when a stopped fan must start at a lower value
+    #[serde(default)]
+    pub kick: Option<u8>,
+    /// ms
+    #[serde(default, rename = "kickDuration", alias = "kick_duration")]
+    pub kick_duration: Option<u64>,
+    /// %/s, the value increases at most this fast, 0 is no limit
+    #[serde(default, rename = "rampUp", alias = "ramp_up")]
+    pub ramp_up: Option<Value>,
+    /// %/s, the value decreases at most this fast, 0 is no limit
+    #[serde(default, rename = "rampDown", alias = "ramp_down")]
+    pub ramp_down: Option<Value>,
+    /// name of the fan node driven by this control,
+    /// used to confirm that the fan started after a kick
+    #[serde(default)]
+    pub fan: Option<String>,
+    /// other fan nodes driven by this control, only watched by the stall detection
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fans: Vec<String>,
+    /// applied when fan-control stops, a fixed value is in %
+    #[serde(default, rename = "onExit", alias = "on_exit")]
+    pub on_exit: OnExit,
+
     // E hardware.controls, only one ref in all controls
     #[serde(skip)]
     pub control_h: Option<Rc<HControl>>,
 
     #[serde(skip)]
     pub mode_set: Option<Mode>,
+
+    #[serde(skip)]
+    pub spin: SpinState,
+
+    /// last value given by [`Control::ramp`], and when
+    #[serde(skip)]
+    pub ramped: Option<(Value, Instant)>,
+
+    /// hardware of the `fan` node, refreshed by the update
+    #[serde(skip)]
+    pub fan_h: Option<Rc<HSensor>>,
+
+    /// last value written to the hardware, checked by [`Control::verify`]
+    #[serde(skip)]
+    pub written: Option<Value>,
+
+    /// first value read back after the last write: drivers round the written value
+    /// to their own resolution, so later readings are compared with this one
+    #[serde(skip)]
+    pub read_back: Option<Value>,
+
+    /// number of times the firmware took the control back
+    #[serde(skip)]
+    pub overrides: u32,
 }
 
+/// State of the fan, used by the zero RPM mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpinState {
+    /// nothing was written yet
+    #[default]
+    Unknown,
+    Stopped,
+    /// the kick value is applied until this instant
+    Kick(Instant),
+    Spinning,
+}
+
+static DEFAULT_KICK_DURATION: u64 = 2000;
+
+/// %, two readings of the same written value may still differ a little
+static READ_BACK_TOLERANCE: Value = 2.0;
+
 impl PartialEq for Control {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name
             && self.hardware_id == other.hardware_id
             && self.input == other.input
             && self.active == other.active
+            && self.min == other.min
+            && self.max == other.max
+            && self.invert == other.invert
+            && self.step == other.step
+            && self.zero_rpm == other.zero_rpm
+            && self.kick == other.kick
+            && self.kick_duration == other.kick_duration
+            && self.ramp_up == other.ramp_up
+            && self.ramp_down == other.ramp_down
+            && self.fan == other.fan
+            && self.fans == other.fans
+            && self.on_exit == other.on_exit
     }
 }
 
@@ -49,11 +143,166 @@ impl Control {
             hardware_id,
             input,
             active,
+            min: None,
+            max: None,
+            invert: false,
+            step: None,
+            zero_rpm: None,
+            kick: None,
+            kick_duration: None,
+            ramp_up: None,
+            ramp_down: None,
+            fan: None,
+            fans: Vec::new(),
+            on_exit: OnExit::default(),
             control_h,
             mode_set: None,
+            spin: SpinState::Unknown,
+            ramped: None,
+            fan_h: None,
+            written: None,
+            read_back: None,
+            overrides: 0,
         }
     }
 
+    pub fn min(&self) -> u8 {
+        self.min.unwrap_or(0)
+    }
+
+    pub fn max(&self) -> u8 {
+        self.max.unwrap_or(100)
+    }
+
+    pub fn step(&self) -> u8 {
+        self.step.unwrap_or(1)
+    }
+
+    /// Apply the step, then the min and max limits, to a value coming from a behavior.
+    pub fn limit(&self, value: Value) -> Value {
+        let step = Value::from(self.step().max(1));
+        let value = (value / step).round() * step;
+
+        let min = Value::from(self.min());
+        let max = Value::from(self.max());
+
+        value.clamp(min, max.max(min))
+    }
+
+    pub fn zero_rpm(&self) -> u8 {
+        self.zero_rpm.unwrap_or(0)
+    }
+
+    pub fn kick(&self) -> u8 {
+        self.kick.unwrap_or(0)
+    }
+
+    pub fn kick_duration(&self) -> Duration {
+        Duration::from_millis(self.kick_duration.unwrap_or(DEFAULT_KICK_DURATION))
+    }
+
+    pub fn ramp_up(&self) -> Value {
+        self.ramp_up.unwrap_or(0.0)
+    }
+
+    pub fn ramp_down(&self) -> Value {
+        self.ramp_down.unwrap_or(0.0)
+    }
+
+    /// Limit the change of a value coming from a behavior to the ramp rates,
+    /// using the real time since the last call, since the update delay can change.
+    /// `current`: value of the control, where the first ramp starts.
+    pub fn ramp(&mut self, value: Value, current: Option<Value>, now: Instant) -> Value {
+        let Some((last, at)) = self.ramped.or(current.map(|current| (current, now))) else {
+            self.ramped = Some((value, now));
+            return value;
+        };
+
+        let seconds = now.saturating_duration_since(at).as_secs_f64();
+
+        let value = if value > last && self.ramp_up() > 0.0 {
+            value.min(last + self.ramp_up() * seconds)
+        } else if value < last && self.ramp_down() > 0.0 {
+            value.max(last - self.ramp_down() * seconds)
+        } else {
+            value
+        };
+
+        self.ramped = Some((value, now));
+        value
+    }
+
+    /// Value to write, taking the zero RPM mode and the kick into account.
+    /// `rpm` is read only at startup, to not kick a fan which already spins,
+    /// and when a kick ends, to confirm that the fan started.
+    fn spin_value(
+        &mut self,
+        value: Value,
+        now: Instant,
+        mut rpm: impl FnMut() -> Option<Value>,
+    ) -> Value {
+        let value = if value < Value::from(self.zero_rpm()) {
+            self.limit(0.0)
+        } else {
+            self.limit(value)
+        };
+
+        let kick = Value::from(self.kick());
+
+        if value == 0.0 {
+            self.spin = SpinState::Stopped;
+            return value;
+        }
+
+        match self.spin {
+            SpinState::Unknown if value < kick && rpm().is_some_and(|rpm| rpm > 0.0) => {
+                self.spin = SpinState::Spinning;
+                value
+            }
+            SpinState::Unknown | SpinState::Stopped if value < kick => {
+                debug!("Kick {} at {}%.", self.name, kick);
+                self.spin = SpinState::Kick(now + self.kick_duration());
+                kick
+            }
+            SpinState::Kick(until) if value < kick => {
+                if now < until {
+                    return kick;
+                }
+
+                if rpm() == Some(0.0) {
+                    warn!(
+                        "The fan of {} didn't start after a kick, retrying.",
+                        self.name
+                    );
+                    self.spin = SpinState::Kick(now + self.kick_duration());
+                    return kick;
+                }
+
+                self.spin = SpinState::Spinning;
+                value
+            }
+            _ => {
+                self.spin = SpinState::Spinning;
+                value
+            }
+        }
+    }
+
+    /// Convert between the value of the control and the value of the hardware.
+    fn to_hardware(&self, value: Value) -> Value {
+        if self.invert { 100.0 - value } else { value }
+    }
+
+    /// Fans watched by the stall detection.
+    pub fn fans(&self) -> impl Iterator<Item = &String> {
+        self.fan.iter().chain(&self.fans)
+    }
+
+    /// %, last value written, None if the firmware may have changed it since.
+    pub fn duty(&self) -> Option<Value> {
+        self.written.map(|written| self.to_hardware(written))
+    }
+
     pub fn set_value<H: HardwareBridge>(
         &mut self,
         value: Value,
@@ -63,13 +312,96 @@ impl Control {
             self.set_mode(Mode::Manual, bridge)?;
         }
 
-        match &self.control_h {
-            Some(control_h) => {
-                bridge.set_value(control_h, value)?;
-                Ok(value)
+        let Some(control_h) = self.control_h.clone() else {
+            return Err(UpdateError::NodeIsInvalid(self.name.clone()));
+        };
+
+        let fan_h = self.fan_h.clone();
+        let now = bridge.now();
+        let value = self.spin_value(value, now, || {
+            fan_h
+                .as_ref()
+                .and_then(|fan_h| bridge.get_sensor_value(fan_h).ok())
+        });
+
+        let hardware_value = self.to_hardware(value);
+        bridge.set_value(&control_h, hardware_value)?;
+        self.set_written(Some(hardware_value));
+        Ok(value)
+    }
+
+    /// Write 100%, ignoring the limits and the zero RPM mode.
+    /// Used when the safety of the hardware comes first.
+    pub fn set_full_speed<H: HardwareBridge>(&mut self, bridge: &mut H) -> Result<(), UpdateError> {
+        if self.mode_set != Some(Mode::Manual) {
+            self.set_mode(Mode::Manual, bridge)?;
+        }
+
+        let Some(control_h) = self.control_h.clone() else {
+            return Err(UpdateError::NodeIsInvalid(self.name.clone()));
+        };
+
+        let hardware_value = self.to_hardware(100.0);
+        bridge.set_value(&control_h, hardware_value)?;
+        self.set_written(Some(hardware_value));
+        self.spin = SpinState::Spinning;
+        // ramp down from here when the behavior takes over again
+        self.ramped = Some((100.0, bridge.now()));
+        Ok(())
+    }
+
+    /// Some boards quietly reset the mode or the value a few seconds after
+    /// we set them. Read them back, and return true if the firmware took
+    /// the control back, in which case the manual mode must be set again.
+    pub fn verify<H: HardwareBridge>(&mut self, bridge: &mut H) -> bool {
+        if self.mode_set != Some(Mode::Manual) {
+            return false;
+        }
+
+        let Some(control_h) = &self.control_h else {
+            return false;
+        };
+
+        let overridden = match bridge.get_mode(control_h) {
+            Ok(Some(mode)) if mode != Mode::Manual => {
+                warn!(
+                    "The mode of {} was changed to {} by the firmware.",
+                    self.name, mode
+                );
+                true
             }
-            None => Err(UpdateError::NodeIsInvalid(self.name.clone())),
+            Ok(_) if self.written.is_none() => false,
+            Ok(_) => match (self.read_back, bridge.get_control_value(control_h)) {
+                (None, Ok(value)) => {
+                    self.read_back = Some(value);
+                    false
+                }
+                (Some(read_back), Ok(value)) if (value - read_back).abs() > READ_BACK_TOLERANCE => {
+                    warn!(
+                        "The value of {} was changed from {} to {} by the firmware.",
+                        self.name, read_back, value
+                    );
+                    true
+                }
+                (_, Err(e)) => {
+                    debug!("Can't read back the value of {}: {e}.", self.name);
+                    false
+                }
+                _ => false,
+            },
+            Err(e) => {
+                debug!("Can't read back the mode of {}: {e}.", self.name);
+                false
+            }
+        };
+
+        if overridden {
+            self.overrides += 1;
+            self.mode_set = None;
+            self.set_written(None);
         }
+
+        overridden
     }
 
     pub fn set_mode<H: HardwareBridge>(
@@ -90,7 +422,34 @@ impl Control {
         };
 
         info!("Mode {} succefuly set for {}.", mode, self.name);
+        if mode != Mode::Manual {
+            self.ramped = None;
+        }
         self.mode_set = Some(mode);
+        self.set_written(None);
+        Ok(())
+    }
+
+    /// The reference of [`Control::verify`] is read again when the value changes.
+    fn set_written(&mut self, written: Option<Value>) {
+        if self.written != written {
+            self.read_back = None;
+        }
+        self.written = written;
+    }
+
+    /// Pass the exit policy to the bridge, which applies it when dropped.
+    pub fn set_on_exit<H: HardwareBridge>(&self, bridge: &mut H) -> Result<(), UpdateError> {
+        let Some(control_h) = &self.control_h else {
+            return Ok(());
+        };
+
+        let on_exit = match self.on_exit {
+            OnExit::Fixed(value) => OnExit::Fixed(self.to_hardware(value.clamp(0.0, 100.0))),
+            on_exit => on_exit,
+        };
+
+        bridge.set_on_exit(control_h, on_exit)?;
         Ok(())
     }
 
@@ -98,6 +457,7 @@ impl Control {
         match &self.control_h {
             Some(control_h) => bridge
                 .get_control_value(control_h)
+                .map(|value| self.to_hardware(value))
                 .map_err(UpdateError::Hardware),
             None => Err(UpdateError::NodeIsInvalid(self.name.clone())),
         }
@@ -158,3 +518,114 @@ impl ToNode for Control {
         Node::new(NodeType::Control(self), app_graph)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use hardware::OnExit;
+
+    use super::{Control, SpinState};
+
+    #[test]
+    fn test_keys() {
+        let control: Control = toml::from_str(
+            r#"
+            name = "Control"
+            active = true
+            zero_rpm = 20
+            kick_duration = 500
+            ramp_up = 10
+            ramp_down = 5
+            on_exit = "Keep"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(control.zero_rpm, Some(20));
+        assert_eq!(control.kick_duration, Some(500));
+        assert_eq!(control.ramp_up, Some(10.0));
+        assert_eq!(control.ramp_down, Some(5.0));
+        assert_eq!(control.on_exit, OnExit::Keep);
+
+        let serialized = toml::to_string(&control).unwrap();
+        assert!(serialized.contains("zeroRpm = 20"), "{serialized}");
+        assert!(serialized.contains("kickDuration = 500"), "{serialized}");
+        assert!(serialized.contains("rampUp = 10"), "{serialized}");
+        assert!(serialized.contains("rampDown = 5"), "{serialized}");
+        assert!(serialized.contains("onExit = \"Keep\""), "{serialized}");
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut control = Control::new("Control".into(), None, None, true, None);
+
+        assert!(control.limit(37.0) == 37.0);
+
+        control.min = Some(40);
+        control.max = Some(90);
+        control.step = Some(10);
+
+        assert!(control.limit(0.0) == 40.0);
+        assert!(control.limit(56.0) == 60.0);
+        assert!(control.limit(100.0) == 90.0);
+
+        control.invert = true;
+        assert!(control.to_hardware(control.limit(56.0)) == 40.0);
+    }
+
+    #[test]
+    fn test_kick() {
+        let mut control = Control::new("Control".into(), None, None, true, None);
+        control.zero_rpm = Some(25);
+        control.kick = Some(60);
+
+        let now = Instant::now();
+        let later = |ms| now + Duration::from_millis(ms);
+
+        assert!(control.spin_value(20.0, now, || unreachable!()) == 0.0);
+        assert!(control.spin == SpinState::Stopped);
+
+        // start at a low value
+        assert!(control.spin_value(30.0, later(100), || unreachable!()) == 60.0);
+        assert!(control.spin_value(30.0, later(1000), || unreachable!()) == 60.0);
+
+        // the fan didn't start: kick again
+        assert!(control.spin_value(30.0, later(2200), || Some(0.0)) == 60.0);
+        assert!(control.spin_value(30.0, later(4300), || Some(800.0)) == 30.0);
+        assert!(control.spin == SpinState::Spinning);
+
+        assert!(control.spin_value(40.0, later(5000), || unreachable!()) == 40.0);
+
+        // at startup, a fan which already spins isn't kicked
+        control.spin = SpinState::Unknown;
+        assert!(control.spin_value(30.0, later(6000), || Some(700.0)) == 30.0);
+        assert!(control.spin == SpinState::Spinning);
+
+        control.spin = SpinState::Unknown;
+        assert!(control.spin_value(30.0, later(7000), || None) == 60.0);
+    }
+
+    #[test]
+    fn test_ramp() {
+        let mut control = Control::new("Control".into(), None, None, true, None);
+        control.ramp_up = Some(10.0);
+        control.ramp_down = Some(2.0);
+
+        let now = Instant::now();
+        let later = |ms| now + Duration::from_millis(ms);
+
+        // start from the current value of the control
+        assert!(control.ramp(100.0, Some(30.0), now) == 30.0);
+        assert!(control.ramp(100.0, Some(30.0), later(1000)) == 40.0);
+        // the elapsed time is used, not the number of calls
+        assert!(control.ramp(100.0, Some(40.0), later(3500)) == 65.0);
+        assert!(control.ramp(70.0, Some(65.0), later(4000)) == 70.0);
+
+        assert!(control.ramp(0.0, Some(70.0), later(6000)) == 66.0);
+
+        // no limit
+        control.ramp_down = Some(0.0);
+        assert!(control.ramp(0.0, Some(66.0), later(6100)) == 0.0);
+    }
+}
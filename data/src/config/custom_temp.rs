@@ -31,12 +31,12 @@ impl CustomTemp {
 
     pub fn get_value(&self, values: &[Value]) -> Result<Value, UpdateError> {
         let value = match self.kind {
-            CustomTempKind::Min => match values.iter().min() {
-                Some(min) => *min,
+            CustomTempKind::Min => match values.iter().copied().reduce(Value::min) {
+                Some(min) => min,
                 None => return Err(UpdateError::NoInputData),
             },
-            CustomTempKind::Max => match values.iter().max() {
-                Some(max) => *max,
+            CustomTempKind::Max => match values.iter().copied().reduce(Value::max) {
+                Some(max) => max,
                 None => return Err(UpdateError::NoInputData),
             },
             CustomTempKind::Average => {
@@ -44,7 +44,7 @@ impl CustomTemp {
                     return Err(UpdateError::NoInputData);
                 }
 
-                values.iter().sum::<i32>() / (values.len() as i32)
+                values.iter().sum::<Value>() / values.len() as Value
             }
         };
 
@@ -2,13 +2,14 @@ use std::rc::Rc;
 
 use crate::{
     app_graph::AppGraph,
+    filter::{Filter, FilterState},
     node::{IsValid, Node, NodeType, ToNode},
     update::UpdateError,
 };
 use hardware::{HSensor, Hardware, HardwareBridge, Value};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Fan {
     // unique
     pub name: String,
@@ -19,22 +20,32 @@ pub struct Fan {
     // E hardware.fans
     #[serde(skip)]
     pub fan_h: Option<Rc<HSensor>>,
+
+    #[serde(default, skip_serializing_if = "Filter::is_default")]
+    pub filter: Filter,
+    #[serde(skip)]
+    pub filter_state: FilterState,
 }
 
 impl PartialEq for Fan {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name && self.hardware_id == other.hardware_id
+        self.name == other.name
+            && self.hardware_id == other.hardware_id
+            && self.filter == other.filter
     }
 }
 
 impl Fan {
-    pub fn get_value<H: HardwareBridge>(&self, bridge: &mut H) -> Result<Value, UpdateError> {
-        match &self.fan_h {
+    pub fn get_value<H: HardwareBridge>(&mut self, bridge: &mut H) -> Result<Value, UpdateError> {
+        let reading = match &self.fan_h {
             Some(fan_h) => bridge
                 .get_sensor_value(fan_h)
                 .map_err(UpdateError::Hardware),
-            None => Err(UpdateError::NodeIsInvalid(self.name.clone())),
-        }
+            None => return Err(UpdateError::NodeIsInvalid(self.name.clone())),
+        };
+
+        self.filter
+            .apply(&mut self.filter_state, &self.name, reading, bridge.now())
     }
 }
 
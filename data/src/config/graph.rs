@@ -11,24 +11,26 @@ use crate::{
 
 use super::utils::affine::Affine;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Eq, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct Coord {
-    pub temp: u8,
+    pub temp: Value,
     pub percent: u8,
 }
 
 impl Hash for Coord {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.temp.hash(state);
+        self.temp.to_bits().hash(state);
     }
 }
 
 impl PartialEq for Coord {
     fn eq(&self, other: &Self) -> bool {
-        self.temp == other.temp
+        self.cmp(other).is_eq()
     }
 }
 
+impl Eq for Coord {}
+
 impl PartialOrd for Coord {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -37,7 +39,7 @@ impl PartialOrd for Coord {
 
 impl Ord for Coord {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.temp.cmp(&other.temp)
+        self.temp.total_cmp(&other.temp)
     }
 }
 
@@ -78,11 +80,11 @@ impl Default for Graph {
             name: Default::default(),
             coords: vec![
                 Coord {
-                    temp: 10,
+                    temp: 10.0,
                     percent: 10,
                 },
                 Coord {
-                    temp: 70,
+                    temp: 70.0,
                     percent: 100,
                 },
             ]
@@ -125,7 +127,11 @@ impl Graph {
         temp: &str,
         percent: &str,
     ) -> Result<Coord, Box<dyn std::error::Error>> {
-        let temp = temp.parse::<u8>()?;
+        let temp = temp.parse::<Value>()?;
+
+        if !temp.is_finite() {
+            return Err("Temp is not a number".into());
+        }
 
         let percent = percent.parse::<u8>()?;
 
@@ -148,25 +154,25 @@ impl Graph {
 
     pub fn get_value(&self, value: Value) -> Result<Value, UpdateError> {
         let dummy_coord = Coord {
-            temp: value as u8,
+            temp: value,
             percent: 0,
         };
 
         let res = match self.coords.get(&dummy_coord) {
-            Some(c) => c.percent as Value,
+            Some(c) => c.percent.into(),
             None => {
                 let lower_bound = self.coords.range(..=dummy_coord).next_back();
                 let upper_bound = self.coords.range(dummy_coord..).next();
 
                 match (lower_bound, upper_bound) {
-                    (Some(coord), None) | (None, Some(coord)) => coord.percent as Value,
+                    (Some(coord), None) | (None, Some(coord)) => coord.percent.into(),
                     (Some(coord1), Some(coord2)) => Affine {
-                        xa: coord1.temp.into(),
+                        xa: coord1.temp,
                         ya: coord1.percent.into(),
-                        xb: coord2.temp.into(),
+                        xb: coord2.temp,
                         yb: coord2.percent.into(),
                     }
-                    .calcule(value) as Value,
+                    .calcule(value),
 
                     _ => panic!("internal error: no value for graph"),
                 }
@@ -200,23 +206,23 @@ mod test {
             name: "name".into(),
             coords: vec![
                 Coord {
-                    temp: 10,
+                    temp: 10.0,
                     percent: 10,
                 },
                 Coord {
-                    temp: 20,
+                    temp: 20.0,
                     percent: 30,
                 },
                 Coord {
-                    temp: 25,
+                    temp: 25.0,
                     percent: 20,
                 },
                 Coord {
-                    temp: 30,
+                    temp: 30.0,
                     percent: 25,
                 },
                 Coord {
-                    temp: 40,
+                    temp: 40.0,
                     percent: 5,
                 },
             ]
@@ -227,11 +233,31 @@ mod test {
 
         graph.is_valid();
 
-        assert_eq!(graph.get_value(9).unwrap(), 10);
-        assert_eq!(graph.get_value(50).unwrap(), 5);
+        assert_eq!(graph.get_value(9.0).unwrap(), 10.0);
+        assert_eq!(graph.get_value(50.0).unwrap(), 5.0);
+
+        assert_eq!(graph.get_value(22.0).unwrap(), 26.0);
+        assert_eq!(graph.get_value(27.0).unwrap(), 22.0);
+        assert_eq!(graph.get_value(35.0).unwrap(), 15.0);
 
-        assert_eq!(graph.get_value(22).unwrap(), 26);
-        assert_eq!(graph.get_value(27).unwrap(), 22);
-        assert_eq!(graph.get_value(35).unwrap(), 15);
+        // sub-degree and out of range temps
+        assert_eq!(graph.get_value(22.5).unwrap(), 25.0);
+        assert_eq!(graph.get_value(-128.0).unwrap(), 10.0);
+        assert_eq!(graph.get_value(300.0).unwrap(), 5.0);
+    }
+
+    #[test]
+    fn test_integer_temps() {
+        // configs written when the values were integers
+        let graph: Graph = toml::from_str(
+            r#"
+            name = "graph"
+            coord = [{ temp = 30, percent = 10 }, { temp = 45.5, percent = 50 }]
+            "#,
+        )
+        .unwrap();
+
+        let temps = graph.coords.iter().map(|c| c.temp).collect::<Vec<_>>();
+        assert_eq!(temps, vec![30.0, 45.5]);
     }
 }
@@ -0,0 +1,224 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    app_graph::AppGraph,
+    node::{IsValid, Node, NodeType, ToNode},
+    update::UpdateError,
+};
+use hardware::{Hardware, Value};
+use serde::{Deserialize, Serialize};
+
+/// Keep the last value of its input until it moved enough,
+/// so a temp oscillating around a point of a curve doesn't make the fans hunt.
+/// The input can be a temp, or the output of a behavior.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Hysteresis {
+    pub name: String,
+    /// increase of the input needed to follow it
+    #[serde(rename = "upDelta", alias = "up_delta")]
+    pub up_delta: Value,
+    /// decrease of the input needed to follow it
+    #[serde(rename = "downDelta", alias = "down_delta")]
+    pub down_delta: Value,
+    /// seconds between two changes of the output
+    #[serde(rename = "minInterval", alias = "min_interval")]
+    pub min_interval: u32,
+    pub input: Option<String>,
+
+    /// last output, and when it changed
+    #[serde(skip)]
+    pub last: Option<(Value, Instant)>,
+}
+
+impl PartialEq for Hysteresis {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.up_delta == other.up_delta
+            && self.down_delta == other.down_delta
+            && self.min_interval == other.min_interval
+            && self.input == other.input
+    }
+}
+
+impl Hysteresis {
+    pub fn get_value(&mut self, value: Value, now: Instant) -> Result<Value, UpdateError> {
+        let Some((last, changed_at)) = self.last else {
+            self.last = Some((value, now));
+            return Ok(value);
+        };
+
+        let moved_enough = (value > last && value - last >= self.up_delta)
+            || (value < last && last - value >= self.down_delta);
+
+        let waited_enough = now.saturating_duration_since(changed_at)
+            >= Duration::from_secs(self.min_interval.into());
+
+        if moved_enough && waited_enough {
+            self.last = Some((value, now));
+            return Ok(value);
+        }
+
+        Ok(last)
+    }
+
+    /// Forget the last value.
+    pub fn reset(&mut self) {
+        self.last.take();
+    }
+}
+
+impl IsValid for Hysteresis {
+    fn is_valid(&self) -> bool {
+        self.input.is_some()
+    }
+}
+
+impl ToNode for Hysteresis {
+    fn to_node(mut self, app_graph: &mut AppGraph, _hardware: &Hardware) -> Node {
+        let default = Self::default();
+
+        if self.up_delta.is_nan() || self.up_delta < 0.0 {
+            self.up_delta = default.up_delta;
+        }
+        if self.down_delta.is_nan() || self.down_delta < 0.0 {
+            self.down_delta = default.down_delta;
+        }
+
+        Node::new(NodeType::Hysteresis(self), app_graph)
+    }
+}
+
+impl Default for Hysteresis {
+    fn default() -> Self {
+        Self {
+            name: Default::default(),
+            up_delta: 2.0,
+            down_delta: 4.0,
+            min_interval: 5,
+            input: Default::default(),
+            last: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use hardware::Hardware;
+
+    use crate::{
+        app_graph::AppGraph,
+        config::{Config, control::Control, graph::Graph, linear::Linear, temp::Temp},
+        node::{self, Node},
+        utils::init_test_logging,
+    };
+
+    use super::Hysteresis;
+
+    #[test]
+    fn test_update() {
+        init_test_logging();
+
+        let mut hysteresis = Hysteresis {
+            name: "hysteresis".into(),
+            up_delta: 2.0,
+            down_delta: 4.0,
+            min_interval: 5,
+            input: Some("temp1".into()),
+            last: None,
+        };
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(hysteresis.get_value(60.0, at(0)).unwrap() == 60.0);
+
+        // too early
+        assert!(hysteresis.get_value(70.0, at(1)).unwrap() == 60.0);
+
+        // oscillating in the deadbands
+        assert!(hysteresis.get_value(61.0, at(10)).unwrap() == 60.0);
+        assert!(hysteresis.get_value(59.0, at(11)).unwrap() == 60.0);
+        assert!(hysteresis.get_value(56.5, at(12)).unwrap() == 60.0);
+
+        assert!(hysteresis.get_value(62.0, at(13)).unwrap() == 62.0);
+        assert!(hysteresis.get_value(58.0, at(14)).unwrap() == 62.0);
+        assert!(hysteresis.get_value(58.0, at(18)).unwrap() == 58.0);
+    }
+
+    /// A hysteresis after a behavior gives a percentage, which can't be used as a temp.
+    #[test]
+    fn test_mixed_units() {
+        init_test_logging();
+
+        let hysteresis = |name: &str, input: &str| Hysteresis {
+            name: name.into(),
+            input: Some(input.into()),
+            ..Default::default()
+        };
+        let config = Config {
+            temps: vec![Temp {
+                name: "temp1".into(),
+                ..Default::default()
+            }],
+            linears: vec![Linear {
+                name: "linear1".into(),
+                input: Some("temp1".into()),
+                ..Default::default()
+            }],
+            hystereses: vec![
+                hysteresis("temp_side", "temp1"),
+                hysteresis("behavior_side", "linear1"),
+            ],
+            graphs: vec![
+                Graph {
+                    name: "graph1".into(),
+                    input: Some("temp_side".into()),
+                    ..Default::default()
+                },
+                Graph {
+                    name: "graph2".into(),
+                    input: Some("behavior_side".into()),
+                    ..Default::default()
+                },
+            ],
+            controls: vec![Control {
+                name: "control1".into(),
+                input: Some("behavior_side".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let app_graph = AppGraph::from_config(config, &Hardware::default());
+        let find = |name: &str| -> &Node {
+            app_graph
+                .nodes
+                .values()
+                .find(|node| node.name() == name)
+                .unwrap()
+        };
+
+        assert_eq!(find("graph1").inputs.len(), 1);
+        assert!(find("graph2").inputs.is_empty());
+        assert_eq!(find("control1").inputs.len(), 1);
+
+        // a temp can't be given to a hysteresis used by a control
+        let nodes = &app_graph.nodes;
+        assert!(!node::is_allowed_input(
+            nodes,
+            find("behavior_side"),
+            find("temp1")
+        ));
+        assert!(node::is_allowed_input(
+            nodes,
+            find("temp_side"),
+            find("temp1")
+        ));
+        assert!(!node::is_allowed_input(
+            nodes,
+            find("graph1"),
+            find("behavior_side")
+        ));
+    }
+}
@@ -8,15 +8,15 @@ use serde::{Deserialize, Serialize};
 
 use super::utils::affine::Affine;
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Linear {
     pub name: String,
     #[serde(rename = "minTemp", alias = "min_temp")]
-    pub min_temp: u8,
+    pub min_temp: Value,
     #[serde(rename = "minSpeed", alias = "min_speed")]
     pub min_speed: u8,
     #[serde(rename = "maxTemp", alias = "max_temp")]
-    pub max_temp: u8,
+    pub max_temp: Value,
     #[serde(rename = "maxSpeed", alias = "max_speed")]
     pub max_speed: u8,
     pub input: Option<String>,
@@ -30,21 +30,21 @@ impl IsValid for Linear {
 
 impl Linear {
     pub fn get_value(&self, value: Value) -> Result<Value, UpdateError> {
-        if value <= self.min_temp.into() {
+        if value <= self.min_temp {
             return Ok(self.min_speed.into());
         }
 
-        if value >= self.max_temp.into() {
+        if value >= self.max_temp {
             return Ok(self.max_speed.into());
         }
 
         let res = Affine {
-            xa: self.min_temp.into(),
+            xa: self.min_temp,
             ya: self.min_speed.into(),
-            xb: self.max_temp.into(),
+            xb: self.max_temp,
             yb: self.max_speed.into(),
         }
-        .calcule(value) as Value;
+        .calcule(value);
 
         Ok(res)
     }
@@ -64,13 +64,13 @@ impl ToNode for Linear {
             self.max_speed = default.max_speed;
         }
 
-        if self.min_temp > 100 {
+        if self.min_temp > 100.0 {
             self.min_temp = default.min_temp;
         }
         if self.min_speed > 100 {
             self.min_speed = default.min_speed;
         }
-        if self.max_temp > 100 {
+        if self.max_temp > 100.0 {
             self.max_temp = default.max_temp;
         }
         if self.max_speed > 100 {
@@ -85,9 +85,9 @@ impl Default for Linear {
     fn default() -> Self {
         Self {
             name: Default::default(),
-            min_temp: 10,
+            min_temp: 10.0,
             min_speed: 10,
-            max_temp: 70,
+            max_temp: 70.0,
             max_speed: 100,
             input: Default::default(),
         }
@@ -106,15 +106,16 @@ mod test {
 
         let linear = Linear {
             name: "Linear".into(),
-            min_temp: 10,
+            min_temp: 10.0,
             min_speed: 10,
-            max_temp: 70,
+            max_temp: 70.0,
             max_speed: 100,
             input: Some("temp1".into()),
         };
 
-        assert!(linear.get_value(9).unwrap() == 10);
-        assert!(linear.get_value(70).unwrap() == 100);
-        assert!(linear.get_value(40).unwrap() == 55);
+        assert!(linear.get_value(9.0).unwrap() == 10.0);
+        assert!(linear.get_value(70.0).unwrap() == 100.0);
+        assert!(linear.get_value(40.0).unwrap() == 55.0);
+        assert!(linear.get_value(40.5).unwrap() == 55.75);
     }
 }
@@ -3,7 +3,12 @@ pub mod custom_temp;
 pub mod fan;
 pub mod flat;
 pub mod graph;
+pub mod hysteresis;
 pub mod linear;
+pub mod pid;
+pub mod rpm_target;
+pub mod sensor;
+pub mod smoothing;
 pub mod target;
 pub mod temp;
 
@@ -16,14 +21,18 @@ use crate::{
     app_graph::AppGraph,
     config::{
         control::Control, custom_temp::CustomTemp, fan::Fan, flat::Flat, graph::Graph,
-        linear::Linear, target::Target, temp::Temp,
+        hysteresis::Hysteresis, linear::Linear, pid::Pid, rpm_target::RpmTarget, sensor::Sensor,
+        smoothing::Smoothing, target::Target, temp::Temp,
     },
+    failsafe::Failsafe,
     node::{self},
+    remap::HardwareKind,
 };
 
+use hardware::{Hardware, file_sensor::FileSensor};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct Config {
     #[serde(default, rename = "Control")]
     pub controls: Vec<Control>,
@@ -31,8 +40,12 @@ pub struct Config {
     pub fans: Vec<Fan>,
     #[serde(default, rename = "Temp")]
     pub temps: Vec<Temp>,
+    #[serde(default, rename = "Sensor")]
+    pub sensors: Vec<Sensor>,
     #[serde(default, rename = "CustomTemp")]
     pub custom_temps: Vec<CustomTemp>,
+    #[serde(default, rename = "Smoothing")]
+    pub smoothings: Vec<Smoothing>,
     #[serde(default, rename = "Graph")]
     pub graphs: Vec<Graph>,
     #[serde(default, rename = "Flat")]
@@ -41,16 +54,36 @@ pub struct Config {
     pub linears: Vec<Linear>,
     #[serde(default, rename = "Target")]
     pub targets: Vec<Target>,
+    #[serde(default, rename = "Pid")]
+    pub pids: Vec<Pid>,
+    #[serde(default, rename = "RpmTarget")]
+    pub rpm_targets: Vec<RpmTarget>,
+    #[serde(default, rename = "Hysteresis")]
+    pub hystereses: Vec<Hysteresis>,
+    #[serde(
+        default,
+        rename = "Failsafe",
+        skip_serializing_if = "Failsafe::is_default"
+    )]
+    pub failsafe: Failsafe,
+    /// Temps read from a file, added to the hardware when the app starts
+    #[serde(default, rename = "FileSensor", skip_serializing_if = "Vec::is_empty")]
+    pub file_sensors: Vec<FileSensor>,
 }
 
 impl Config {
     pub fn from_app_graph(app_graph: &AppGraph) -> Self {
-        let mut config = Config::default();
+        let mut config = Config {
+            failsafe: app_graph.failsafe.clone(),
+            file_sensors: app_graph.file_sensors.clone(),
+            ..Default::default()
+        };
         for node in app_graph.nodes.values() {
             match &node.node_type {
                 node::NodeType::Control(control) => config.controls.push(control.clone()),
                 node::NodeType::Fan(fan) => config.fans.push(fan.clone()),
                 node::NodeType::Temp(temp) => config.temps.push(temp.clone()),
+                node::NodeType::Sensor(sensor) => config.sensors.push(sensor.clone()),
                 node::NodeType::CustomTemp(custom_temp) => {
                     config.custom_temps.push(custom_temp.clone())
                 }
@@ -58,8 +91,127 @@ impl Config {
                 node::NodeType::Flat(flat) => config.flats.push(flat.clone()),
                 node::NodeType::Linear(linear, ..) => config.linears.push(linear.clone()),
                 node::NodeType::Target(target, ..) => config.targets.push(target.clone()),
+                node::NodeType::Hysteresis(hysteresis) => {
+                    config.hystereses.push(hysteresis.clone())
+                }
+                node::NodeType::Smoothing(smoothing) => config.smoothings.push(smoothing.clone()),
+                node::NodeType::Pid(pid) => config.pids.push(pid.clone()),
+                node::NodeType::RpmTarget(rpm_target) => {
+                    config.rpm_targets.push(rpm_target.clone())
+                }
             }
         }
         config
     }
+
+    pub(crate) fn hardware_ids_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (HardwareKind, &mut Option<String>)> {
+        self.controls
+            .iter_mut()
+            .map(|control| (HardwareKind::Control, &mut control.hardware_id))
+            .chain(
+                self.fans
+                    .iter_mut()
+                    .map(|fan| (HardwareKind::Fan, &mut fan.hardware_id)),
+            )
+            .chain(
+                self.temps
+                    .iter_mut()
+                    .map(|temp| (HardwareKind::Temp, &mut temp.hardware_id)),
+            )
+            .chain(
+                self.sensors
+                    .iter_mut()
+                    .map(|sensor| (HardwareKind::Sensor(sensor.kind), &mut sensor.hardware_id)),
+            )
+    }
+
+    pub(crate) fn hardware_ids(&self) -> Vec<(HardwareKind, &String, &Option<String>)> {
+        let mut ids = Vec::new();
+
+        for control in &self.controls {
+            ids.push((HardwareKind::Control, &control.name, &control.hardware_id));
+        }
+        for fan in &self.fans {
+            ids.push((HardwareKind::Fan, &fan.name, &fan.hardware_id));
+        }
+        for temp in &self.temps {
+            ids.push((HardwareKind::Temp, &temp.name, &temp.hardware_id));
+        }
+        for sensor in &self.sensors {
+            ids.push((
+                HardwareKind::Sensor(sensor.kind),
+                &sensor.name,
+                &sensor.hardware_id,
+            ));
+        }
+
+        ids
+    }
+
+    /// Replace the hardware ids generated by older versions by the current ones.
+    /// Return true if the config changed.
+    pub fn migrate_hardware_ids(&mut self, hardware: &Hardware) -> bool {
+        let mut changed = false;
+
+        for (_, hardware_id) in self.hardware_ids_mut() {
+            let Some(hardware_id) = hardware_id else {
+                continue;
+            };
+
+            if let Some(new_id) = hardware.migrate_id(hardware_id) {
+                info!("Migrate hardware id {} to {}.", hardware_id, new_id);
+                *hardware_id = new_id.clone();
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hardware::Hardware;
+
+    use super::{Config, control::Control, temp::Temp};
+
+    #[test]
+    fn test_migrate_hardware_ids() {
+        let mut hardware = Hardware::default();
+        hardware.add_legacy_id(
+            "pwm1-nct6775-isa-0290-pwm1".into(),
+            "nct6775-isa-0290@platform/nct6775.656/pwm1",
+        );
+
+        let mut config = Config {
+            controls: vec![Control::new(
+                "Control".into(),
+                Some("pwm1-nct6775-isa-0290-pwm1".into()),
+                None,
+                true,
+                None,
+            )],
+            temps: vec![Temp {
+                name: "Temp".into(),
+                hardware_id: Some("file-/sys/class/thermal/thermal_zone0/temp".into()),
+                temp_h: None,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert!(config.migrate_hardware_ids(&hardware));
+        assert_eq!(
+            config.controls[0].hardware_id.as_deref(),
+            Some("nct6775-isa-0290@platform/nct6775.656/pwm1")
+        );
+        assert_eq!(
+            config.temps[0].hardware_id.as_deref(),
+            Some("file-/sys/class/thermal/thermal_zone0/temp")
+        );
+
+        assert!(!config.migrate_hardware_ids(&hardware));
+    }
 }
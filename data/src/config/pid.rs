@@ -0,0 +1,243 @@
+use std::time::Instant;
+
+use crate::{
+    app_graph::AppGraph,
+    node::{IsValid, Node, NodeType, ToNode},
+    update::UpdateError,
+};
+use hardware::{Hardware, Value};
+use serde::{Deserialize, Serialize};
+
+/// Hold a temp at a setpoint, instead of following a curve.
+/// The output is a percentage, higher when the temp is above the setpoint.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Pid {
+    pub name: String,
+    /// °C
+    pub setpoint: Value,
+    /// % per °C
+    pub kp: Value,
+    /// % per °C and per second
+    pub ki: Value,
+    /// % per °C/s
+    pub kd: Value,
+    #[serde(rename = "minSpeed", alias = "min_speed")]
+    pub min_speed: u8,
+    #[serde(rename = "maxSpeed", alias = "max_speed")]
+    pub max_speed: u8,
+    /// seconds, time constant of the low-pass filter of the derivative, 0 to disable it
+    #[serde(rename = "derivativeFilter", alias = "derivative_filter")]
+    pub derivative_filter: Value,
+    pub input: Option<String>,
+
+    #[serde(skip)]
+    pub state: PidState,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PidState {
+    /// %, part of the output given by the integral term
+    integral: Value,
+    /// °C/s, filtered
+    derivative: Value,
+    /// last temp, and when it was read
+    last: Option<(Value, Instant)>,
+}
+
+impl PartialEq for Pid {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.setpoint == other.setpoint
+            && self.kp == other.kp
+            && self.ki == other.ki
+            && self.kd == other.kd
+            && self.min_speed == other.min_speed
+            && self.max_speed == other.max_speed
+            && self.derivative_filter == other.derivative_filter
+            && self.input == other.input
+    }
+}
+
+impl Pid {
+    pub fn get_value(&mut self, value: Value, now: Instant) -> Result<Value, UpdateError> {
+        let min = Value::from(self.min_speed);
+        let max = Value::from(self.max_speed.max(self.min_speed));
+        let error = value - self.setpoint;
+
+        let seconds = self
+            .state
+            .last
+            .map(|(_, at)| now.saturating_duration_since(at).as_secs_f64())
+            .filter(|seconds| *seconds > 0.0);
+
+        if let (Some(seconds), Some((last, _))) = (seconds, self.state.last) {
+            // on the temp rather than on the error, so changing the setpoint doesn't make a spike
+            let derivative = (value - last) / seconds;
+            let alpha = if self.derivative_filter > 0.0 {
+                seconds / (self.derivative_filter + seconds)
+            } else {
+                1.0
+            };
+            self.state.derivative += alpha * (derivative - self.state.derivative);
+        }
+
+        let proportional = self.kp * error;
+        let derivative = self.kd * self.state.derivative;
+
+        if let Some(seconds) = seconds {
+            let integral = self.state.integral + self.ki * error * seconds;
+            let output = proportional + integral + derivative;
+
+            // anti-windup: don't integrate further while the output is saturated
+            let saturated = (output > max && error > 0.0) || (output < min && error < 0.0);
+            if !saturated {
+                self.state.integral = integral.clamp(min, max);
+            }
+        }
+
+        self.state.last = Some((value, now));
+
+        Ok((proportional + self.state.integral + derivative).clamp(min, max))
+    }
+
+    /// Forget the integral and the previous temp.
+    pub fn reset(&mut self) {
+        self.state = PidState::default();
+    }
+}
+
+impl IsValid for Pid {
+    fn is_valid(&self) -> bool {
+        self.input.is_some()
+    }
+}
+
+impl ToNode for Pid {
+    fn to_node(mut self, app_graph: &mut AppGraph, _hardware: &Hardware) -> Node {
+        let default = Self::default();
+
+        if self.max_speed < self.min_speed {
+            self.min_speed = default.min_speed;
+            self.max_speed = default.max_speed;
+        }
+        if self.max_speed > 100 {
+            self.max_speed = default.max_speed;
+        }
+        if self.derivative_filter.is_nan() || self.derivative_filter < 0.0 {
+            self.derivative_filter = default.derivative_filter;
+        }
+
+        Node::new(NodeType::Pid(self), app_graph)
+    }
+}
+
+impl Default for Pid {
+    fn default() -> Self {
+        Self {
+            name: Default::default(),
+            setpoint: 50.0,
+            kp: 5.0,
+            ki: 0.1,
+            kd: 0.0,
+            min_speed: 0,
+            max_speed: 100,
+            derivative_filter: 2.0,
+            input: Default::default(),
+            state: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use hardware::Value;
+
+    use crate::utils::init_test_logging;
+
+    use super::{Pid, PidState};
+
+    /// Water loop heated by a constant load, cooled proportionally to the speed of its fans.
+    struct Plant {
+        temp: Value,
+    }
+
+    impl Plant {
+        /// `seconds`: of simulated time
+        fn step(&mut self, speed: Value, seconds: Value) {
+            let heating = 0.3;
+            let cooling = (0.1 + 0.9 * speed / 100.0) * (self.temp - 25.0) * 0.05;
+            self.temp += (heating - cooling) * seconds;
+        }
+    }
+
+    fn run(pid: &mut Pid, plant: &mut Plant, start: Instant, from: u64, to: u64) -> Value {
+        let mut speed = 0.0;
+        for secs in from..to {
+            speed = pid
+                .get_value(plant.temp, start + Duration::from_secs(secs))
+                .unwrap();
+            plant.step(speed, 1.0);
+        }
+        speed
+    }
+
+    #[test]
+    fn test_update() {
+        init_test_logging();
+
+        let mut pid = Pid {
+            name: "pid".into(),
+            setpoint: 35.0,
+            kp: 10.0,
+            ki: 0.5,
+            kd: 5.0,
+            input: Some("temp1".into()),
+            ..Default::default()
+        };
+        let mut plant = Plant { temp: 25.0 };
+        let start = Instant::now();
+
+        // the loop needs (0.3 / (0.05 * 10) - 0.1) / 0.9 = 55.6% at 35 °C
+        let speed = run(&mut pid, &mut plant, start, 0, 600);
+        assert!((plant.temp - 35.0).abs() < 0.1, "{}", plant.temp);
+        assert!((speed - 55.6).abs() < 1.0, "{speed}");
+
+        // and (0.3 / (0.05 * 15) - 0.1) / 0.9 = 33.3% at 40 °C
+        pid.setpoint = 40.0;
+        let speed = run(&mut pid, &mut plant, start, 600, 1200);
+        assert!((plant.temp - 40.0).abs() < 0.1, "{}", plant.temp);
+        assert!((speed - 33.3).abs() < 1.0, "{speed}");
+    }
+
+    #[test]
+    fn test_anti_windup() {
+        init_test_logging();
+
+        let mut pid = Pid {
+            name: "pid".into(),
+            setpoint: 35.0,
+            kp: 10.0,
+            ki: 1.0,
+            kd: 0.0,
+            max_speed: 80,
+            input: Some("temp1".into()),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // far above the setpoint for a long time
+        for secs in 0..1000 {
+            assert!(pid.get_value(60.0, at(secs)).unwrap() == 80.0);
+        }
+
+        // nothing was integrated while saturated,
+        // so the output drops as soon as the temp is under the setpoint
+        assert!(pid.get_value(34.0, at(1000)).unwrap() == 0.0);
+
+        pid.reset();
+        assert_eq!(pid.state, PidState::default());
+    }
+}
@@ -0,0 +1,311 @@
+use std::time::Instant;
+
+use crate::{
+    app_graph::AppGraph,
+    node::{IsValid, Node, NodeType, ToNode},
+    update::UpdateError,
+};
+use hardware::{Hardware, Value};
+use serde::{Deserialize, Serialize};
+
+/// Keep a fan at a speed in RPM, whatever the voltage or the wear of the fan,
+/// by adjusting the value of the control while reading the fan.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RpmTarget {
+    pub name: String,
+    /// RPM to reach, when there is no target input
+    pub rpm: u32,
+    /// RPM of the fan at 100%: the output of the target input is mapped on it,
+    /// and it gives the first value before any correction
+    #[serde(rename = "maxRpm", alias = "max_rpm")]
+    pub max_rpm: u32,
+    /// % per 100 RPM of error, and per second
+    pub gain: Value,
+    /// RPM, error under which the value is kept, so the fan doesn't hunt
+    pub tolerance: u32,
+    #[serde(rename = "minSpeed", alias = "min_speed")]
+    pub min_speed: u8,
+    #[serde(rename = "maxSpeed", alias = "max_speed")]
+    pub max_speed: u8,
+    /// measured speed
+    pub fan: Option<String>,
+    /// behavior giving the target, in percent of `max_rpm`
+    pub target: Option<String>,
+
+    #[serde(skip)]
+    pub state: RpmTargetState,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RpmTargetState {
+    /// last output, and when it was computed
+    last: Option<(Value, Instant)>,
+    /// the output is at a limit, and the fan still isn't at the target
+    unreachable: bool,
+}
+
+impl PartialEq for RpmTarget {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.rpm == other.rpm
+            && self.max_rpm == other.max_rpm
+            && self.gain == other.gain
+            && self.tolerance == other.tolerance
+            && self.min_speed == other.min_speed
+            && self.max_speed == other.max_speed
+            && self.fan == other.fan
+            && self.target == other.target
+    }
+}
+
+impl RpmTarget {
+    /// `rpm`: measured on the fan, `target`: output of the target input, if any
+    pub fn get_value(
+        &mut self,
+        rpm: Value,
+        target: Option<Value>,
+        now: Instant,
+    ) -> Result<Value, UpdateError> {
+        let min = Value::from(self.min_speed);
+        let max = Value::from(self.max_speed.max(self.min_speed));
+        let max_rpm = Value::from(self.max_rpm.max(1));
+
+        let target_rpm = match target {
+            Some(target) => target.clamp(0.0, 100.0) * max_rpm / 100.0,
+            None => Value::from(self.rpm),
+        };
+
+        let Some((last, at)) = self.state.last else {
+            // start from the value the fan should need, then correct it
+            let value = (target_rpm * 100.0 / max_rpm).clamp(min, max);
+            self.state.last = Some((value, now));
+            return Ok(value);
+        };
+
+        let error = target_rpm - rpm;
+        let seconds = now.saturating_duration_since(at).as_secs_f64();
+
+        let value = if error.abs() > Value::from(self.tolerance) {
+            (last + self.gain * error / 100.0 * seconds).clamp(min, max)
+        } else {
+            last
+        };
+
+        let unreachable = (value >= max && error > 0.0 || value <= min && error < 0.0)
+            && error.abs() > Value::from(self.tolerance);
+        if unreachable && !self.state.unreachable {
+            warn!(
+                "{}: can't reach {} RPM, the fan is at {} RPM with a value of {}%",
+                self.name,
+                target_rpm.round(),
+                rpm.round(),
+                value
+            );
+        }
+        self.state.unreachable = unreachable;
+
+        self.state.last = Some((value, now));
+        Ok(value)
+    }
+
+    /// Forget the last value, so the next one starts from `max_rpm` again.
+    pub fn reset(&mut self) {
+        self.state = RpmTargetState::default();
+    }
+
+    /// The inputs are the fan, then the target.
+    pub fn get_inputs(&self) -> Vec<String> {
+        self.fan.iter().chain(self.target.iter()).cloned().collect()
+    }
+
+    pub fn set_inputs(&mut self, inputs: Vec<String>) {
+        match inputs.as_slice() {
+            [fan, target] => {
+                self.fan = Some(fan.clone());
+                self.target = Some(target.clone());
+            }
+            // one of the inputs was removed, or renamed
+            [input] => match (&self.fan, &self.target) {
+                (Some(fan), Some(_)) if fan == input => {
+                    self.target.take();
+                }
+                (Some(_), Some(_)) | (None, Some(_)) => {
+                    self.fan.take();
+                    self.target = Some(input.clone());
+                }
+                (Some(_), None) | (None, None) => {
+                    self.fan = Some(input.clone());
+                }
+            },
+            _ => {
+                self.fan.take();
+                self.target.take();
+            }
+        }
+    }
+}
+
+impl IsValid for RpmTarget {
+    fn is_valid(&self) -> bool {
+        self.fan.is_some()
+    }
+}
+
+impl ToNode for RpmTarget {
+    fn to_node(mut self, app_graph: &mut AppGraph, _hardware: &Hardware) -> Node {
+        let default = Self::default();
+
+        if self.max_rpm == 0 {
+            self.max_rpm = default.max_rpm;
+        }
+        if self.max_speed < self.min_speed {
+            self.min_speed = default.min_speed;
+            self.max_speed = default.max_speed;
+        }
+        if self.max_speed > 100 {
+            self.max_speed = default.max_speed;
+        }
+        if self.gain.is_nan() || self.gain <= 0.0 {
+            self.gain = default.gain;
+        }
+
+        // the inputs are told apart by their kind
+        let is_fan = |name: &String| {
+            app_graph
+                .nodes
+                .values()
+                .any(|node| node.name() == name && matches!(node.node_type, NodeType::Fan(..)))
+        };
+        if self.fan.as_ref().is_some_and(|fan| !is_fan(fan)) {
+            warn!("{}: the input {:?} is not a fan", self.name, self.fan);
+            self.fan.take();
+        }
+        if self.target.as_ref().is_some_and(is_fan) {
+            warn!("{}: the target {:?} is a fan", self.name, self.target);
+            self.target.take();
+        }
+
+        Node::new(NodeType::RpmTarget(self), app_graph)
+    }
+}
+
+impl Default for RpmTarget {
+    fn default() -> Self {
+        Self {
+            name: Default::default(),
+            rpm: 1000,
+            max_rpm: 2000,
+            gain: 1.0,
+            tolerance: 20,
+            min_speed: 0,
+            max_speed: 100,
+            fan: Default::default(),
+            target: Default::default(),
+            state: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use hardware::Value;
+
+    use crate::utils::init_test_logging;
+
+    use super::{RpmTarget, RpmTargetState};
+
+    /// Fan slower than announced, which reaches its speed after some time.
+    struct Fan {
+        rpm: Value,
+    }
+
+    impl Fan {
+        fn step(&mut self, value: Value) {
+            let target = 1500.0 * value / 100.0;
+            self.rpm += (target - self.rpm) * 0.4;
+        }
+    }
+
+    fn run(rpm_target: &mut RpmTarget, fan: &mut Fan, target: Option<Value>, secs: u64) -> Value {
+        let start = Instant::now();
+        let mut value = 0.0;
+        for secs in 0..secs {
+            value = rpm_target
+                .get_value(fan.rpm, target, start + Duration::from_secs(secs))
+                .unwrap();
+            fan.step(value);
+        }
+        value
+    }
+
+    #[test]
+    fn test_update() {
+        init_test_logging();
+
+        let mut rpm_target = RpmTarget {
+            name: "rpm_target".into(),
+            rpm: 900,
+            fan: Some("fan1".into()),
+            ..Default::default()
+        };
+        let mut fan = Fan { rpm: 0.0 };
+
+        // 45% for 2000 RPM, but 60% for this fan
+        let value = run(&mut rpm_target, &mut fan, None, 300);
+        assert!((fan.rpm - 900.0).abs() <= 20.0, "{}", fan.rpm);
+        assert!((value - 60.0).abs() < 2.0, "{value}");
+
+        // 50% of max_rpm
+        rpm_target.reset();
+        run(&mut rpm_target, &mut fan, Some(50.0), 300);
+        assert!((fan.rpm - 1000.0).abs() <= 20.0, "{}", fan.rpm);
+    }
+
+    #[test]
+    fn test_unreachable() {
+        init_test_logging();
+
+        let mut rpm_target = RpmTarget {
+            name: "rpm_target".into(),
+            rpm: 1800,
+            max_speed: 90,
+            fan: Some("fan1".into()),
+            ..Default::default()
+        };
+        let mut fan = Fan { rpm: 0.0 };
+
+        // the output stays at the limit, and comes back as soon as the target is lowered
+        assert!(run(&mut rpm_target, &mut fan, None, 300) == 90.0);
+        assert!(rpm_target.state.unreachable);
+
+        rpm_target.rpm = 1200;
+        let value = run(&mut rpm_target, &mut fan, None, 300);
+        assert!((value - 80.0).abs() < 2.0, "{value}");
+        assert!(!rpm_target.state.unreachable);
+
+        rpm_target.reset();
+        assert_eq!(rpm_target.state, RpmTargetState::default());
+    }
+
+    #[test]
+    fn test_set_inputs() {
+        let mut rpm_target = RpmTarget {
+            fan: Some("fan1".into()),
+            target: Some("linear1".into()),
+            ..Default::default()
+        };
+
+        rpm_target.set_inputs(vec!["fan1".into()]);
+        assert_eq!(rpm_target.get_inputs(), vec!["fan1".to_string()]);
+
+        rpm_target.set_inputs(vec!["fan2".into()]);
+        assert_eq!(rpm_target.fan, Some("fan2".into()));
+
+        rpm_target.target = Some("linear1".into());
+        rpm_target.set_inputs(vec!["linear1".into()]);
+        assert_eq!(rpm_target.fan, None);
+        assert_eq!(rpm_target.target, Some("linear1".into()));
+    }
+}
@@ -2,7 +2,7 @@
 #![allow(unused_imports)]
 
 use const_format::formatcp;
-use hardware::{HControl, HSensor, Hardware};
+use hardware::{HControl, HSensor, Hardware, OnExit, file_sensor::FileSensor};
 use serial_test::serial;
 use std::fmt::Debug;
 use std::fs::{self, File};
@@ -10,6 +10,8 @@ use std::io::Write;
 use std::path::Path;
 
 use crate::config::Config;
+use crate::failsafe::{CriticalTemp, Failsafe};
+use crate::filter::Filter;
 
 use crate::settings::Settings;
 
@@ -19,7 +21,12 @@ use super::custom_temp::{CustomTemp, CustomTempKind};
 use super::fan::Fan;
 use super::flat::Flat;
 use super::graph::{Coord, Graph};
+use super::hysteresis::Hysteresis;
 use super::linear::Linear;
+use super::pid::Pid;
+use super::rpm_target::RpmTarget;
+use super::sensor::Sensor;
+use super::smoothing::{Smoothing, SmoothingKind};
 use super::target::Target;
 use super::temp::Temp;
 
@@ -130,22 +137,38 @@ fn hardware1() -> Hardware {
 
 fn config1() -> Config {
     Config {
-        controls: vec![Control::new(
-            "Control".into(),
-            Some("Control".into()),
-            None,
-            true,
-            None,
-        )],
+        controls: vec![Control {
+            zero_rpm: Some(20),
+            kick: Some(60),
+            kick_duration: Some(500),
+            ramp_up: Some(10.0),
+            ramp_down: Some(5.0),
+            on_exit: OnExit::Fixed(60.0),
+            ..Control::new("Control".into(), Some("Control".into()), None, true, None)
+        }],
         temps: vec![Temp {
             name: "Temp".into(),
             hardware_id: Some("temp".into()),
             temp_h: None,
+            filter: Filter {
+                min: Some(-20.0),
+                max: Some(120.0),
+                max_rate: Some(5.0),
+                hold: 3,
+            },
+            ..Default::default()
         }],
         fans: vec![Fan {
             name: "Fan".into(),
             hardware_id: None,
             fan_h: None,
+            ..Default::default()
+        }],
+        sensors: vec![Sensor {
+            name: "Sensor".into(),
+            hardware_id: Some("power".into()),
+            kind: hardware::SensorKind::Power,
+            sensor_h: None,
         }],
         custom_temps: vec![CustomTemp::new(
             "CustomTemp".into(),
@@ -156,11 +179,11 @@ fn config1() -> Config {
             name: "Graph".into(),
             coords: vec![
                 Coord {
-                    temp: 10,
+                    temp: 10.0,
                     percent: 10,
                 },
                 Coord {
-                    temp: 50,
+                    temp: 50.0,
                     percent: 30,
                 },
             ]
@@ -174,20 +197,74 @@ fn config1() -> Config {
         }],
         linears: vec![Linear {
             name: "Linear".into(),
-            min_temp: 10,
+            min_temp: 10.0,
             min_speed: 10,
-            max_temp: 70,
+            max_temp: 70.0,
             max_speed: 100,
             input: Some("temp1".into()),
         }],
         targets: vec![Target {
             name: "Target".into(),
-            idle_temp: 40,
+            idle_temp: 40.0,
             idle_speed: 10,
-            load_temp: 70,
+            load_temp: 70.0,
             load_speed: 100,
             input: Some("temp3".into()),
             idle_has_been_reatch: false,
         }],
+        pids: vec![Pid {
+            name: "Pid".into(),
+            setpoint: 45.0,
+            kp: 5.0,
+            ki: 0.1,
+            kd: 1.0,
+            min_speed: 20,
+            max_speed: 100,
+            derivative_filter: 2.0,
+            input: Some("temp2".into()),
+            ..Default::default()
+        }],
+        rpm_targets: vec![RpmTarget {
+            name: "RpmTarget".into(),
+            rpm: 900,
+            max_rpm: 2000,
+            gain: 1.0,
+            tolerance: 20,
+            min_speed: 20,
+            max_speed: 100,
+            fan: Some("Fan".into()),
+            target: Some("Linear".into()),
+            ..Default::default()
+        }],
+        smoothings: vec![Smoothing {
+            name: "Smoothing".into(),
+            kind: SmoothingKind::Exponential,
+            samples: 5,
+            time_constant: 10.0,
+            input: Some("temp1".into()),
+            ..Default::default()
+        }],
+        hystereses: vec![Hysteresis {
+            name: "Hysteresis".into(),
+            up_delta: 2.0,
+            down_delta: 4.0,
+            min_interval: 5,
+            input: Some("Linear".into()),
+            last: None,
+        }],
+        failsafe: Failsafe {
+            critical_temp: Some(95.0),
+            temps: vec![CriticalTemp {
+                name: "Temp".into(),
+                critical: 85.0,
+            }],
+            ..Default::default()
+        },
+        file_sensors: vec![FileSensor {
+            name: "SoC".into(),
+            path: "/sys/devices/virtual/thermal/thermal_zone0/temp".into(),
+            scale: 0.001,
+            offset: 0.0,
+        }],
     }
 }
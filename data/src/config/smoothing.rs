@@ -0,0 +1,193 @@
+use std::{collections::VecDeque, fmt::Display, time::Instant};
+
+use crate::{
+    app_graph::AppGraph,
+    node::{IsValid, Node, NodeType, ToNode},
+    update::UpdateError,
+};
+use hardware::{Hardware, Value};
+use light_enum::Values;
+use serde::{Deserialize, Serialize};
+
+/// Smooth the readings of a temp, so a spike of one sample doesn't make the fans rev.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Smoothing {
+    pub name: String,
+    pub kind: SmoothingKind,
+    /// number of readings, for the average and the median
+    pub samples: u8,
+    /// seconds, for the exponential average
+    #[serde(rename = "timeConstant", alias = "time_constant")]
+    pub time_constant: Value,
+    pub input: Option<String>,
+
+    /// last readings, for the average and the median
+    #[serde(skip)]
+    pub history: VecDeque<Value>,
+    /// last output and when it was computed, for the exponential average
+    #[serde(skip)]
+    pub last: Option<(Value, Instant)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Values, Default, PartialEq, Eq)]
+pub enum SmoothingKind {
+    #[default]
+    Average,
+    Exponential,
+    Median,
+}
+
+impl PartialEq for Smoothing {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.kind == other.kind
+            && self.samples == other.samples
+            && self.time_constant == other.time_constant
+            && self.input == other.input
+    }
+}
+
+impl Smoothing {
+    pub fn get_value(&mut self, value: Value, now: Instant) -> Result<Value, UpdateError> {
+        self.history.push_back(value);
+        while self.history.len() > self.samples.max(1).into() {
+            self.history.pop_front();
+        }
+
+        let value = match self.kind {
+            SmoothingKind::Average => {
+                self.history.iter().sum::<Value>() / self.history.len() as Value
+            }
+            SmoothingKind::Median => {
+                let mut sorted = self.history.iter().copied().collect::<Vec<_>>();
+                sorted.sort_by(Value::total_cmp);
+
+                let middle = sorted.len() / 2;
+                if sorted.len() % 2 == 0 {
+                    (sorted[middle - 1] + sorted[middle]) / 2.0
+                } else {
+                    sorted[middle]
+                }
+            }
+            SmoothingKind::Exponential => match self.last {
+                // the weight of the new reading depends on the real time since the last one,
+                // because the update delay can be changed
+                Some((last, at)) if self.time_constant > 0.0 => {
+                    let seconds = now.saturating_duration_since(at).as_secs_f64();
+                    let alpha = 1.0 - (-seconds / self.time_constant).exp();
+                    last + alpha * (value - last)
+                }
+                _ => value,
+            },
+        };
+
+        self.last = Some((value, now));
+        Ok(value)
+    }
+
+    /// Forget the previous readings.
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.last.take();
+    }
+}
+
+impl IsValid for Smoothing {
+    fn is_valid(&self) -> bool {
+        self.input.is_some()
+    }
+}
+
+impl ToNode for Smoothing {
+    fn to_node(mut self, app_graph: &mut AppGraph, _hardware: &Hardware) -> Node {
+        let default = Self::default();
+
+        if self.samples == 0 {
+            self.samples = default.samples;
+        }
+        if self.time_constant.is_nan() || self.time_constant < 0.0 {
+            self.time_constant = default.time_constant;
+        }
+
+        Node::new(NodeType::Smoothing(self), app_graph)
+    }
+}
+
+impl Default for Smoothing {
+    fn default() -> Self {
+        Self {
+            name: Default::default(),
+            kind: Default::default(),
+            samples: 5,
+            time_constant: 5.0,
+            input: Default::default(),
+            history: Default::default(),
+            last: None,
+        }
+    }
+}
+
+impl Display for SmoothingKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            SmoothingKind::Average => fl!("average"),
+            SmoothingKind::Exponential => fl!("exponential"),
+            SmoothingKind::Median => fl!("median"),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use crate::utils::init_test_logging;
+
+    use super::{Smoothing, SmoothingKind};
+
+    #[test]
+    fn test_update() {
+        init_test_logging();
+
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let spike = [50.0, 50.0, 90.0, 50.0, 50.0];
+
+        let mut smoothing = Smoothing {
+            name: "smoothing".into(),
+            kind: SmoothingKind::Median,
+            samples: 3,
+            input: Some("temp1".into()),
+            ..Default::default()
+        };
+        for (i, value) in spike.into_iter().enumerate() {
+            assert!(smoothing.get_value(value, at(i as u64)).unwrap() == 50.0);
+        }
+
+        smoothing.kind = SmoothingKind::Average;
+        smoothing.reset();
+        let values = spike
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| smoothing.get_value(value, at(i as u64)).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![50.0, 50.0, 190.0 / 3.0, 190.0 / 3.0, 190.0 / 3.0]
+        );
+
+        smoothing.kind = SmoothingKind::Exponential;
+        smoothing.time_constant = 10.0;
+        smoothing.reset();
+        assert!(smoothing.get_value(50.0, at(0)).unwrap() == 50.0);
+        let value = smoothing.get_value(90.0, at(1)).unwrap();
+        assert!((value - 53.81).abs() < 0.01, "{value}");
+
+        // a longer delay gives more weight to the new reading
+        smoothing.reset();
+        smoothing.get_value(50.0, at(0)).unwrap();
+        let value = smoothing.get_value(90.0, at(10)).unwrap();
+        assert!((value - 75.28).abs() < 0.01, "{value}");
+    }
+}
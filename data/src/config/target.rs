@@ -6,15 +6,15 @@ use crate::{
 use hardware::{Hardware, Value};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Target {
     pub name: String,
     #[serde(rename = "idleTemp", alias = "idle_temp")]
-    pub idle_temp: u8,
+    pub idle_temp: Value,
     #[serde(rename = "idleSpeed", alias = "idle_speed")]
     pub idle_speed: u8,
     #[serde(rename = "loadTemp", alias = "load_temp")]
-    pub load_temp: u8,
+    pub load_temp: Value,
     #[serde(rename = "loadSpeed", alias = "load_speed")]
     pub load_speed: u8,
     pub input: Option<String>,
@@ -37,7 +37,7 @@ impl PartialEq for Target {
 impl Target {
     pub fn get_value(&mut self, value: Value) -> Result<Value, UpdateError> {
         if self.idle_has_been_reatch {
-            if value < self.load_temp.into() {
+            if value < self.load_temp {
                 return Ok(self.idle_speed.into());
             }
 
@@ -45,7 +45,7 @@ impl Target {
             return Ok(self.load_speed.into());
         }
 
-        if value > self.idle_temp.into() {
+        if value > self.idle_temp {
             return Ok(self.load_speed.into());
         }
 
@@ -64,13 +64,13 @@ impl ToNode for Target {
     fn to_node(mut self, app_graph: &mut AppGraph, _hardware: &Hardware) -> Node {
         let default = Self::default();
 
-        if self.idle_temp > 100 {
+        if self.idle_temp > 100.0 {
             self.idle_temp = default.idle_temp;
         }
         if self.idle_speed > 100 {
             self.idle_speed = default.idle_speed;
         }
-        if self.load_temp > 100 {
+        if self.load_temp > 100.0 {
             self.load_temp = default.load_temp;
         }
         if self.load_speed > 100 {
@@ -85,9 +85,9 @@ impl Default for Target {
     fn default() -> Self {
         Self {
             name: Default::default(),
-            idle_temp: 40,
+            idle_temp: 40.0,
             idle_speed: 10,
-            load_temp: 70,
+            load_temp: 70.0,
             load_speed: 100,
             input: Default::default(),
             idle_has_been_reatch: false,
@@ -109,16 +109,17 @@ mod test {
         let mut target = Target {
             name: "linear".to_string(),
             input: Some("temp1".into()),
-            idle_temp: 40,
+            idle_temp: 40.0,
             idle_speed: 10,
-            load_temp: 70,
+            load_temp: 70.0,
             load_speed: 100,
             idle_has_been_reatch: false,
         };
 
-        assert!(target.get_value(55).unwrap() == 100);
-        assert!(target.get_value(30).unwrap() == 10);
-        assert!(target.get_value(55).unwrap() == 10);
-        assert!(target.get_value(70).unwrap() == 100);
+        assert!(target.get_value(55.0).unwrap() == 100.0);
+        assert!(target.get_value(30.0).unwrap() == 10.0);
+        assert!(target.get_value(55.0).unwrap() == 10.0);
+        assert!(target.get_value(69.9).unwrap() == 10.0);
+        assert!(target.get_value(70.0).unwrap() == 100.0);
     }
 }
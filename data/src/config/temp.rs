@@ -5,11 +5,12 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     app_graph::AppGraph,
+    filter::{Filter, FilterState},
     node::{IsValid, Node, NodeType, ToNode},
     update::UpdateError,
 };
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Temp {
     pub name: String,
     #[serde(rename = "id")]
@@ -17,22 +18,32 @@ pub struct Temp {
 
     #[serde(skip)]
     pub temp_h: Option<Rc<HSensor>>,
+
+    #[serde(default, skip_serializing_if = "Filter::is_default")]
+    pub filter: Filter,
+    #[serde(skip)]
+    pub filter_state: FilterState,
 }
 
 impl PartialEq for Temp {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name && self.hardware_id == other.hardware_id
+        self.name == other.name
+            && self.hardware_id == other.hardware_id
+            && self.filter == other.filter
     }
 }
 
 impl Temp {
-    pub fn get_value<H: HardwareBridge>(&self, bridge: &mut H) -> Result<Value, UpdateError> {
-        match &self.temp_h {
+    pub fn get_value<H: HardwareBridge>(&mut self, bridge: &mut H) -> Result<Value, UpdateError> {
+        let reading = match &self.temp_h {
             Some(temp_h) => bridge
                 .get_sensor_value(temp_h)
                 .map_err(UpdateError::Hardware),
-            None => Err(UpdateError::NodeIsInvalid(self.name.clone())),
-        }
+            None => return Err(UpdateError::NodeIsInvalid(self.name.clone())),
+        };
+
+        self.filter
+            .apply(&mut self.filter_state, &self.name, reading, bridge.now())
     }
 }
 
@@ -2,17 +2,17 @@ use hardware::Value;
 
 #[derive(Debug)]
 pub struct Affine {
-    pub xa: f32,
-    pub ya: f32,
-    pub xb: f32,
-    pub yb: f32,
+    pub xa: Value,
+    pub ya: Value,
+    pub xb: Value,
+    pub yb: Value,
 }
 
 impl Affine {
-    pub fn calcule(&self, value: Value) -> f32 {
+    pub fn calcule(&self, value: Value) -> Value {
         let a = (self.yb - self.ya) / (self.xb - self.xa);
         let b = self.ya - a * self.xa;
 
-        a * value as f32 + b
+        a * value + b
     }
 }
@@ -1,5 +1,6 @@
 use std::{
-    fs::{self},
+    fs::{self, File, TryLockError},
+    io::Write,
     path::{Path, PathBuf},
 };
 
@@ -10,7 +11,9 @@ use thiserror::Error;
 use utils::{APP, ORG, QUALIFIER};
 
 use crate::{
+    calibration::{Calibration, Calibrations},
     config::Config,
+    remap::Remapping,
     settings::{Settings, SettingsState},
     utils::RemoveElem,
 };
@@ -27,9 +30,13 @@ pub struct DirManager {
     pub config_dir_path: PathBuf,
     pub state_dir_path: PathBuf,
     pub cache_dir_path: PathBuf,
+    /// Shared by all users, tried before the state dir for the lock,
+    /// so a service running as root and the app of a user exclude each other.
+    pub system_lock_dir_paths: Vec<PathBuf>,
     pub config_names: ConfigNames,
     settings: Settings,
     state: SettingsState,
+    calibrations: Calibrations,
 }
 
 #[derive(Error, Debug)]
@@ -42,6 +49,8 @@ pub enum ConfigError {
     TomlSerialization(#[from] toml::ser::Error),
     #[error("There is no name")]
     NoName,
+    #[error("Another instance of fan-control (pid {0}) already controls the fans, see {1}")]
+    AlreadyRunning(String, String),
 }
 
 type Result<T> = std::result::Result<T, ConfigError>;
@@ -50,6 +59,19 @@ static SETTINGS_FILENAME: &str = "settings.toml";
 static STATE_FILENAME: &str = "state.toml";
 static HARDWARE_FILENAME: &str = "hardware.toml";
 static CACHED_CONFIG_FILENAME: &str = "cached_config.toml";
+static CALIBRATIONS_FILENAME: &str = "calibrations.toml";
+static LOCK_FILENAME: &str = "fan-control.lock";
+#[cfg(unix)]
+static SYSTEM_LOCK_DIRS: &[&str] = &["/run/lock", "/var/lock"];
+#[cfg(not(unix))]
+static SYSTEM_LOCK_DIRS: &[&str] = &[];
+
+/// Held by the only instance allowed to control the fans,
+/// released when dropped or when the process dies.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+}
 
 impl DirManager {
     pub fn new(
@@ -137,13 +159,31 @@ impl DirManager {
             }
         };
 
+        let calibrations = {
+            let calibrations_file_path = config_dir_path.join(CALIBRATIONS_FILENAME);
+
+            if !calibrations_file_path.exists() {
+                Calibrations::default()
+            } else {
+                match deserialize(&calibrations_file_path) {
+                    Ok(t) => t,
+                    Err(e) => {
+                        error!("can't deserialize calibrations at init: {}", e);
+                        Calibrations::default()
+                    }
+                }
+            }
+        };
+
         DirManager {
             config_names,
+            calibrations,
             config_dir_path,
             settings,
             state,
             state_dir_path,
             cache_dir_path: project_dirs.cache_dir().to_path_buf(),
+            system_lock_dir_paths: SYSTEM_LOCK_DIRS.iter().map(PathBuf::from).collect(),
         }
     }
 
@@ -159,10 +199,66 @@ impl DirManager {
         self.config_dir_path.join(HARDWARE_FILENAME)
     }
 
+    fn calibrations_file_path(&self) -> PathBuf {
+        self.config_dir_path.join(CALIBRATIONS_FILENAME)
+    }
+
     fn state_file_path(&self) -> PathBuf {
         self.state_dir_path.join(STATE_FILENAME)
     }
 
+    /// Take the exclusive lock, before creating the bridge,
+    /// so two instances don't fight over the controls.
+    ///
+    /// The lock is system-wide when possible. Otherwise, in a sandbox for instance,
+    /// it only excludes the instances of the same user.
+    pub fn lock_instance(&self) -> Result<InstanceLock> {
+        let system_lock = self
+            .system_lock_dir_paths
+            .iter()
+            .filter(|dir| dir.is_dir())
+            .map(|dir| dir.join(LOCK_FILENAME))
+            .find_map(|path| match helper::open_lock_file(&path) {
+                Ok(file) => Some((path, file)),
+                Err(e) => {
+                    debug!("can't open lock file {}: {}", path.display(), e);
+                    None
+                }
+            });
+
+        let (path, mut file) = match system_lock {
+            Some(system_lock) => system_lock,
+            None => {
+                fs::create_dir_all(&self.state_dir_path)?;
+                let path = self.state_dir_path.join(LOCK_FILENAME);
+                let file = helper::open_lock_file(&path)?;
+                (path, file)
+            }
+        };
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let pid = fs::read_to_string(&path).unwrap_or_default();
+                return Err(ConfigError::AlreadyRunning(
+                    pid.trim().to_owned(),
+                    path.display().to_string(),
+                ));
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        // the file can be read-only, when created by another user
+        if let Err(e) = file
+            .set_len(0)
+            .and_then(|_| write!(file, "{}", std::process::id()))
+        {
+            debug!("can't write the pid in {}: {}", path.display(), e);
+        }
+
+        Ok(InstanceLock { _file: file })
+    }
+
     fn config_file_path(&self, name: &str) -> PathBuf {
         self.config_dir_path
             .join(helper::add_toml_extension(name).into_owned())
@@ -192,6 +288,18 @@ impl DirManager {
         }
     }
 
+    pub fn calibrations(&self) -> &Calibrations {
+        &self.calibrations
+    }
+
+    pub fn save_calibration(&mut self, calibration: Calibration) {
+        self.calibrations.insert(calibration);
+
+        if let Err(e) = serialize(&self.calibrations_file_path(), &self.calibrations) {
+            error!("{e}");
+        }
+    }
+
     pub fn get_config(&self) -> Option<Config> {
         match &self.settings().current_config {
             Some(config_name) => match deserialize::<Config>(&self.config_file_path(config_name)) {
@@ -225,6 +333,70 @@ impl DirManager {
         Ok(())
     }
 
+    /// Rewrite the hardware ids generated by older versions, in all configs,
+    /// the cached config and the calibrations.
+    pub fn migrate_hardware_ids(&mut self, hardware: &Hardware) {
+        if hardware.legacy_ids.is_empty() {
+            return;
+        }
+
+        let mut paths = self
+            .config_names
+            .names()
+            .iter()
+            .map(|name| self.config_file_path(name))
+            .collect::<Vec<_>>();
+
+        let cached_config_file_path = self.cached_config_file_path();
+        if cached_config_file_path.exists() {
+            paths.push(cached_config_file_path);
+        }
+
+        Self::rewrite_configs(paths, |config| config.migrate_hardware_ids(hardware));
+
+        if self.calibrations.migrate_hardware_ids(hardware) {
+            if let Err(e) = serialize(&self.calibrations_file_path(), &self.calibrations) {
+                error!("{e}");
+            }
+        }
+    }
+
+    /// Save remapped hardware ids in the current config and the cached config.
+    pub fn remap_hardware_ids(&self, remappings: &[Remapping]) {
+        let mut paths = Vec::new();
+
+        if let Some(config_name) = &self.settings().current_config {
+            paths.push(self.config_file_path(config_name));
+        }
+
+        let cached_config_file_path = self.cached_config_file_path();
+        if cached_config_file_path.exists() {
+            paths.push(cached_config_file_path);
+        }
+
+        Self::rewrite_configs(paths, |config| config.remap_hardware_ids(remappings));
+    }
+
+    /// Save the configs changed by `f`.
+    fn rewrite_configs(paths: Vec<PathBuf>, mut f: impl FnMut(&mut Config) -> bool) {
+        for path in paths {
+            let mut config = match deserialize::<Config>(&path) {
+                Ok(config) => config,
+                Err(e) => {
+                    warn!("Can't rewrite {}: {}.", path.display(), e);
+                    continue;
+                }
+            };
+
+            if f(&mut config) {
+                match serialize(&path, &config) {
+                    Ok(_) => info!("Hardware ids of {} rewritten.", path.display()),
+                    Err(e) => error!("Can't save {}: {}.", path.display(), e),
+                }
+            }
+        }
+    }
+
     pub fn serialize_hardware(&self, hardware: &Hardware) {
         let hardware_file_path = self.hardware_file_path();
 
@@ -343,7 +515,10 @@ impl ConfigNames {
 
             let file_name = file.file_name();
 
-            if file_name == SETTINGS_FILENAME || file_name == HARDWARE_FILENAME {
+            if file_name == SETTINGS_FILENAME
+                || file_name == HARDWARE_FILENAME
+                || file_name == CALIBRATIONS_FILENAME
+            {
                 continue;
             }
 
@@ -437,12 +612,28 @@ impl ConfigNames {
 }
 
 mod helper {
-    use std::{borrow::Cow, fs, path::Path};
+    use std::{
+        borrow::Cow,
+        fs::{self, File},
+        path::Path,
+    };
 
     use serde::{Serialize, de::DeserializeOwned};
 
     static TOML_EXT: &str = ".toml";
 
+    /// Readable by everyone, so the other users can take the lock too,
+    /// which works on a read-only file.
+    pub fn open_lock_file(path: &Path) -> std::io::Result<File> {
+        let mut options = File::options();
+        // don't truncate: the pid of the owner is read on failure
+        options.read(true).write(true).create(true).truncate(false);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o644);
+
+        options.open(path).or_else(|_| File::open(path))
+    }
+
     pub fn add_toml_extension(input: &str) -> Cow<'_, str> {
         if !input.ends_with(TOML_EXT) {
             let val = format!("{}{}", input, TOML_EXT);
@@ -477,3 +668,45 @@ mod helper {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{ConfigError, DirManager};
+
+    #[test]
+    fn test_lock_instance() {
+        let dir = std::env::temp_dir().join("fan-control-test-lock");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut dir_manager = DirManager::new(&Some(dir.join("config")), &None);
+        dir_manager.state_dir_path = dir.join("state");
+        dir_manager.system_lock_dir_paths = vec![dir.join("missing"), dir.join("run")];
+        std::fs::create_dir_all(dir.join("run")).unwrap();
+
+        let lock = dir_manager.lock_instance().unwrap();
+
+        match dir_manager.lock_instance() {
+            Err(ConfigError::AlreadyRunning(pid, _)) => {
+                assert_eq!(pid, std::process::id().to_string())
+            }
+            res => panic!("{res:?}"),
+        }
+
+        // the app of a user and a service share the lock
+        let mut other_user = DirManager::new(&Some(dir.join("config")), &None);
+        other_user.state_dir_path = dir.join("other_state");
+        other_user.system_lock_dir_paths = dir_manager.system_lock_dir_paths.clone();
+        assert!(other_user.lock_instance().is_err());
+        assert!(dir.join("run/fan-control.lock").exists());
+
+        drop(lock);
+        dir_manager.lock_instance().unwrap();
+
+        // no system-wide dir, in a sandbox
+        dir_manager.system_lock_dir_paths.clear();
+        dir_manager.lock_instance().unwrap();
+        assert!(dir.join("state/fan-control.lock").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+//! Set all the controls to 100% when a temp reaches a critical value,
+//! or can't be read anymore, whatever their behavior.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use hardware::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_graph::Nodes,
+    id::Id,
+    node::{IsValid, NodeType},
+};
+
+/// What to do when a temp can't be read for `sensorLostDelay`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SensorLostAction {
+    /// The controls keep their last value
+    #[default]
+    Ignore,
+    FullSpeed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CriticalTemp {
+    /// name of the temp node
+    pub name: String,
+    /// °C
+    pub critical: Value,
+}
+
+/// Failsafe policy of a config.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Failsafe {
+    /// °C, critical temp of all the temps without their own
+    #[serde(rename = "criticalTemp", alias = "critical_temp")]
+    pub critical_temp: Option<Value>,
+    /// °C, the failsafe ends when all the temps are this much under their critical temp
+    pub hysteresis: Value,
+    /// s
+    #[serde(rename = "sensorLostDelay", alias = "sensor_lost_delay")]
+    pub sensor_lost_delay: u64,
+    #[serde(rename = "sensorLost", alias = "sensor_lost")]
+    pub sensor_lost: SensorLostAction,
+    #[serde(rename = "Temp", skip_serializing_if = "Vec::is_empty")]
+    pub temps: Vec<CriticalTemp>,
+}
+
+impl Default for Failsafe {
+    fn default() -> Self {
+        Self {
+            critical_temp: None,
+            hysteresis: 5.0,
+            sensor_lost_delay: 10,
+            sensor_lost: SensorLostAction::default(),
+            temps: Vec::new(),
+        }
+    }
+}
+
+impl Failsafe {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.critical_temp.is_some()
+            || !self.temps.is_empty()
+            || self.sensor_lost != SensorLostAction::Ignore
+    }
+
+    pub fn critical_temp(&self, temp_name: &str) -> Option<Value> {
+        self.temps
+            .iter()
+            .find(|critical_temp| critical_temp.name == temp_name)
+            .map(|critical_temp| critical_temp.critical)
+            .or(self.critical_temp)
+    }
+
+    pub fn sensor_lost_delay(&self) -> Duration {
+        Duration::from_secs(self.sensor_lost_delay)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailsafeReason {
+    CriticalTemp {
+        temp: String,
+        value: Value,
+        critical: Value,
+    },
+    SensorLost {
+        temp: String,
+    },
+}
+
+impl Display for FailsafeReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            FailsafeReason::CriticalTemp {
+                temp,
+                value,
+                critical,
+            } => fl!(
+                "failsafe_critical_temp",
+                temp = temp.clone(),
+                value = value,
+                critical = critical
+            ),
+            FailsafeReason::SensorLost { temp } => {
+                fl!("failsafe_sensor_lost", temp = temp.clone())
+            }
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FailsafeMonitor {
+    reason: Option<FailsafeReason>,
+    /// first failed read, per temp name
+    lost_since: HashMap<String, Instant>,
+    triggered: Option<FailsafeReason>,
+}
+
+impl FailsafeMonitor {
+    /// Should be called after the temps were updated,
+    /// a valid temp without value couldn't be read.
+    pub fn check(&mut self, policy: &Failsafe, nodes: &Nodes, now: Instant) {
+        if !policy.is_enabled() {
+            self.lost_since.clear();
+            if self.reason.take().is_some() {
+                info!("Failsafe disabled.");
+            }
+            return;
+        }
+
+        let mut reason = None;
+        let mut cooled = true;
+        let mut temps = HashSet::new();
+
+        for node in nodes.values() {
+            let NodeType::Temp(temp) = &node.node_type else {
+                continue;
+            };
+
+            if !temp.is_valid() {
+                continue;
+            }
+
+            temps.insert(&temp.name);
+
+            match node.value {
+                Some(value) => {
+                    self.lost_since.remove(&temp.name);
+
+                    let Some(critical) = policy.critical_temp(&temp.name) else {
+                        continue;
+                    };
+
+                    if value >= critical {
+                        reason.get_or_insert(FailsafeReason::CriticalTemp {
+                            temp: temp.name.clone(),
+                            value,
+                            critical,
+                        });
+                    }
+
+                    if value > critical - policy.hysteresis {
+                        cooled = false;
+                    }
+                }
+                None => {
+                    let since = *self.lost_since.entry(temp.name.clone()).or_insert(now);
+
+                    if policy.sensor_lost == SensorLostAction::FullSpeed
+                        && now.duration_since(since) >= policy.sensor_lost_delay()
+                    {
+                        reason.get_or_insert(FailsafeReason::SensorLost {
+                            temp: temp.name.clone(),
+                        });
+                        cooled = false;
+                    }
+                }
+            }
+        }
+
+        self.lost_since.retain(|name, _| temps.contains(name));
+
+        match (&self.reason, reason) {
+            (None, Some(reason)) => {
+                error!("Failsafe: {reason}. All the controls are set to 100%.");
+                self.triggered = Some(reason.clone());
+                self.reason = Some(reason);
+            }
+            (Some(_), None) if cooled => {
+                info!("Failsafe ended.");
+                self.reason = None;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn reason(&self) -> Option<&FailsafeReason> {
+        self.reason.as_ref()
+    }
+
+    /// Reason of the failsafe, if it was triggered since the last call.
+    pub fn take_triggered(&mut self) -> Option<FailsafeReason> {
+        self.triggered.take()
+    }
+
+    pub fn forced_controls(&self, nodes: &Nodes) -> HashSet<Id> {
+        if self.reason.is_none() {
+            return HashSet::new();
+        }
+
+        nodes
+            .values()
+            .filter(|node| matches!(node.node_type, NodeType::Control(_)))
+            .map(|node| node.id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Failsafe, SensorLostAction};
+
+    #[test]
+    fn test_default() {
+        assert!(!Failsafe::default().is_enabled());
+
+        let failsafe: Failsafe = toml::from_str("sensorLost = \"FullSpeed\"").unwrap();
+        assert!(failsafe.is_enabled());
+
+        // written by older versions
+        let failsafe: Failsafe =
+            toml::from_str("critical_temp = 90\nsensor_lost_delay = 5\nsensor_lost = \"Ignore\"")
+                .unwrap();
+        assert_eq!(failsafe.critical_temp, Some(90.0));
+        assert_eq!(failsafe.sensor_lost_delay, 5);
+        assert_eq!(failsafe.sensor_lost, SensorLostAction::Ignore);
+    }
+}
@@ -0,0 +1,166 @@
+//! Filter the readings of a sensor before they reach its node.
+//!
+//! Some drivers occasionally return nonsense, like -128 °C, 255 °C
+//! or 0 RPM for one cycle, which would make the behaviors jump.
+
+use std::time::Instant;
+
+use hardware::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::update::UpdateError;
+
+/// Sanity filter of a `Temp` or a `Fan`. Nothing is filtered by default.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Filter {
+    /// readings under this value are rejected
+    pub min: Option<Value>,
+    /// readings above this value are rejected
+    pub max: Option<Value>,
+    /// per second, larger changes are capped
+    #[serde(rename = "maxRate", alias = "max_rate")]
+    pub max_rate: Option<Value>,
+    /// number of consecutive cycles where the last good value is kept,
+    /// when a reading is rejected or fails
+    pub hold: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterState {
+    last: Option<(Value, Instant)>,
+    held: u32,
+}
+
+impl Filter {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    fn is_in_range(&self, value: Value) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+
+    /// `name`: of the node, for the logs.
+    pub fn apply(
+        &self,
+        state: &mut FilterState,
+        name: &str,
+        reading: Result<Value, UpdateError>,
+        now: Instant,
+    ) -> Result<Value, UpdateError> {
+        if self.is_default() {
+            return reading;
+        }
+
+        let value = match reading {
+            Ok(value) if self.is_in_range(value) => value,
+            Ok(value) => {
+                warn!("{name}: rejected the reading {value}, out of range.");
+                return self.hold(state, name, Err(UpdateError::Rejected(value)));
+            }
+            Err(e) => return self.hold(state, name, Err(e)),
+        };
+
+        let value = match (self.max_rate, state.last) {
+            (Some(max_rate), Some((last, at))) => {
+                let seconds = now.duration_since(at).as_secs_f64();
+                let max_change = max_rate.max(0.0) * seconds;
+                let capped = value.clamp(last - max_change, last + max_change);
+
+                if capped != value {
+                    warn!("{name}: capped the reading {value} to {capped}, it changed too fast.");
+                }
+                capped
+            }
+            _ => value,
+        };
+
+        state.last = Some((value, now));
+        state.held = 0;
+        Ok(value)
+    }
+
+    /// Keep the last good value, for at most `hold` cycles.
+    fn hold(
+        &self,
+        state: &mut FilterState,
+        name: &str,
+        error: Result<Value, UpdateError>,
+    ) -> Result<Value, UpdateError> {
+        match state.last {
+            Some((last, _)) if state.held < self.hold => {
+                state.held += 1;
+                debug!("{name}: hold the last value {last}.");
+                Ok(last)
+            }
+            _ => {
+                state.last = None;
+                error
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use crate::update::UpdateError;
+
+    use super::{Filter, FilterState};
+
+    #[test]
+    fn test_filter() {
+        let filter = Filter {
+            min: Some(0.0),
+            max: Some(150.0),
+            max_rate: Some(10.0),
+            hold: 2,
+        };
+        let mut state = FilterState::default();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(
+            filter.apply(&mut state, "t", Ok(40.0), at(0)).unwrap(),
+            40.0
+        );
+
+        // held twice, then lost
+        assert_eq!(
+            filter.apply(&mut state, "t", Ok(-128.0), at(1)).unwrap(),
+            40.0
+        );
+        assert_eq!(
+            filter.apply(&mut state, "t", Ok(255.0), at(2)).unwrap(),
+            40.0
+        );
+        assert!(filter.apply(&mut state, "t", Ok(255.0), at(3)).is_err());
+
+        // no previous value to cap from
+        assert_eq!(
+            filter.apply(&mut state, "t", Ok(60.0), at(4)).unwrap(),
+            60.0
+        );
+        assert_eq!(
+            filter.apply(&mut state, "t", Ok(100.0), at(6)).unwrap(),
+            80.0
+        );
+        assert_eq!(
+            filter.apply(&mut state, "t", Ok(75.0), at(7)).unwrap(),
+            75.0
+        );
+
+        let failed = Err(UpdateError::ValueIsNone);
+        assert_eq!(filter.apply(&mut state, "t", failed, at(8)).unwrap(), 75.0);
+
+        // the default filter doesn't change anything
+        let mut state = FilterState::default();
+        let filter = Filter::default();
+        assert_eq!(
+            filter.apply(&mut state, "t", Ok(-128.0), at(0)).unwrap(),
+            -128.0
+        );
+    }
+}
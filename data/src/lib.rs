@@ -5,11 +5,16 @@ extern crate log;
 pub mod localize;
 
 pub mod app_graph;
+pub mod calibration;
 pub mod config;
 pub mod dir_manager;
+pub mod failsafe;
+pub mod filter;
 pub mod id;
 pub mod node;
+pub mod remap;
 pub mod settings;
+pub mod stall;
 pub mod update;
 pub mod utils;
 
@@ -25,3 +30,22 @@ pub struct AppState<H: HardwareBridge> {
     pub app_graph: AppGraph,
     pub update: Update,
 }
+
+impl<H: HardwareBridge> AppState<H> {
+    pub fn new(
+        dir_manager: DirManager,
+        mut bridge: H,
+        app_graph: AppGraph,
+        mut update: Update,
+    ) -> Self {
+        // the bridge must know the exit policies before a signal or a crash
+        update.set_on_exit(&app_graph.nodes, &mut bridge);
+
+        Self {
+            dir_manager,
+            bridge,
+            app_graph,
+            update,
+        }
+    }
+}
@@ -2,15 +2,16 @@ use std::cmp::Ordering;
 use std::vec;
 
 use derive_more::{Display, Unwrap};
-use hardware::{Hardware, Value};
+use hardware::{Hardware, SensorKind, Value};
 use light_enum::LightEnum;
 use std::fmt::Display;
 
 use crate::app_graph::{AppGraph, Nodes};
 
 use crate::config::{
-    control::Control, custom_temp::CustomTemp, fan::Fan, flat::Flat, graph::Graph, linear::Linear,
-    target::Target, temp::Temp,
+    control::Control, custom_temp::CustomTemp, fan::Fan, flat::Flat, graph::Graph,
+    hysteresis::Hysteresis, linear::Linear, pid::Pid, rpm_target::RpmTarget, sensor::Sensor,
+    smoothing::Smoothing, target::Target, temp::Temp,
 };
 
 use crate::id::Id;
@@ -21,11 +22,16 @@ pub enum NodeType {
     Control(Control),
     Fan(Fan),
     Temp(Temp),
+    Sensor(Sensor),
     CustomTemp(CustomTemp),
     Graph(Graph),
     Flat(Flat),
     Linear(Linear),
     Target(Target),
+    Hysteresis(Hysteresis),
+    Smoothing(Smoothing),
+    Pid(Pid),
+    RpmTarget(RpmTarget),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -106,22 +112,25 @@ pub fn sanitize_inputs(node: &Node, nodes: &Nodes, log: bool) -> Sanitize {
     for name in node.node_type.get_inputs() {
         match nodes.values().find(|n| n.name() == &name) {
             Some(n) => {
-                match node
+                if !node
                     .node_type
                     .allowed_dep()
                     .contains(&n.node_type.to_light())
                 {
-                    true => {
-                        sanitize.add(n.id, &name);
-                    }
-                    false => {
-                        warn!(
-                            "sanitize_inputs {}: dep {} have an unauthorized node type: {:?}",
-                            node.name(),
-                            name,
-                            n.node_type.to_light(),
-                        );
-                    }
+                    warn!(
+                        "sanitize_inputs {}: dep {} have an unauthorized node type: {:?}",
+                        node.name(),
+                        name,
+                        n.node_type.to_light(),
+                    );
+                } else if mixes_units(nodes, node, n) {
+                    warn!(
+                        "sanitize_inputs {}: dep {} would mix temps and percentages",
+                        node.name(),
+                        name,
+                    );
+                } else {
+                    sanitize.add(n.id, &name);
                 }
             }
             None => {
@@ -142,6 +151,43 @@ pub fn validate_name(nodes: &Nodes, id: &Id, name: &String) -> bool {
     !nodes.values().any(|n| n.name() == name && &n.id != id)
 }
 
+/// Whether `dep` can be an input of `node`.
+pub fn is_allowed_input(nodes: &Nodes, node: &Node, dep: &Node) -> bool {
+    node.node_type
+        .allowed_dep()
+        .contains(&dep.node_type.to_light())
+        && !mixes_units(nodes, node, dep)
+}
+
+/// A Hysteresis passes temps or percentages, depending on its input.
+/// Whether linking `dep` to `node` would feed one where the other is expected,
+/// like a behavior into a graph.
+fn mixes_units(nodes: &Nodes, node: &Node, dep: &Node) -> bool {
+    match (&node.node_type, &dep.node_type) {
+        (_, NodeType::Hysteresis(..)) => dep
+            .inputs
+            .iter()
+            .filter_map(|input| nodes.get(&input.id))
+            .any(|input| input.node_type.is_behavior() != node.node_type.takes_behaviors()),
+        (NodeType::Hysteresis(..), _) => nodes
+            .values()
+            .filter(|n| n.inputs.iter().any(|input| input.id == node.id))
+            .any(|output| dep.node_type.is_behavior() != output.node_type.takes_behaviors()),
+        _ => false,
+    }
+}
+
+/// Whether `id` is `dep`, or depends on it through its inputs.
+/// Used to not create a cycle when choosing an input.
+pub fn depends_on(nodes: &Nodes, id: &Id, dep: &Id) -> bool {
+    id == dep
+        || nodes.get(id).is_some_and(|node| {
+            node.inputs
+                .iter()
+                .any(|input| depends_on(nodes, &input.id, dep))
+        })
+}
+
 pub trait ToNode {
     fn to_node(self, app_graph: &mut AppGraph, hardware: &Hardware) -> Node;
 }
@@ -184,18 +230,24 @@ impl Node {
             NodeType::Control(i) => &i.hardware_id,
             NodeType::Fan(i) => &i.hardware_id,
             NodeType::Temp(i) => &i.hardware_id,
+            NodeType::Sensor(i) => &i.hardware_id,
             _ => panic!(),
         }
     }
 
     pub fn value_text(&self, kind: &ValueKind) -> String {
-        match self.value {
-            Some(val) => match kind {
-                ValueKind::Celsius => fl!("value_celsius", value = val),
-                ValueKind::Porcentage => fl!("value_percentage", value = val),
-                ValueKind::RPM => fl!("value_rpm", value = val),
-            },
-            None => fl!("no_value"),
+        let Some(val) = self.value else {
+            return fl!("no_value");
+        };
+
+        // the drivers give a precision of a tenth of degree at best
+        let tenths = (val * 10.0).round() / 10.0;
+
+        match kind {
+            ValueKind::Celsius => fl!("value_celsius", value = tenths),
+            ValueKind::Porcentage => fl!("value_percentage", value = val.round()),
+            ValueKind::RPM => fl!("value_rpm", value = val.round()),
+            ValueKind::Sensor(kind) => fl!("value_unit", value = tenths, unit = kind.unit()),
         }
     }
 
@@ -208,6 +260,7 @@ pub enum ValueKind {
     Celsius,
     Porcentage,
     RPM,
+    Sensor(SensorKind),
 }
 
 impl NodeType {
@@ -216,11 +269,16 @@ impl NodeType {
             NodeType::Control(control) => &control.name,
             NodeType::Fan(fan) => &fan.name,
             NodeType::Temp(temp) => &temp.name,
+            NodeType::Sensor(sensor) => &sensor.name,
             NodeType::CustomTemp(custom_temp) => &custom_temp.name,
             NodeType::Graph(graph) => &graph.name,
             NodeType::Flat(flat) => &flat.name,
             NodeType::Linear(linear, ..) => &linear.name,
             NodeType::Target(target, ..) => &target.name,
+            NodeType::Hysteresis(hysteresis) => &hysteresis.name,
+            NodeType::Smoothing(smoothing) => &smoothing.name,
+            NodeType::Pid(pid) => &pid.name,
+            NodeType::RpmTarget(rpm_target) => &rpm_target.name,
         }
     }
     pub fn set_name(&mut self, name: String) {
@@ -228,11 +286,16 @@ impl NodeType {
             NodeType::Control(i) => i.name = name,
             NodeType::Fan(i) => i.name = name,
             NodeType::Temp(i) => i.name = name,
+            NodeType::Sensor(i) => i.name = name,
             NodeType::CustomTemp(i) => i.name = name,
             NodeType::Graph(i) => i.name = name,
             NodeType::Flat(i) => i.name = name,
             NodeType::Linear(i, ..) => i.name = name,
             NodeType::Target(i, ..) => i.name = name,
+            NodeType::Hysteresis(i) => i.name = name,
+            NodeType::Smoothing(i) => i.name = name,
+            NodeType::Pid(i) => i.name = name,
+            NodeType::RpmTarget(i) => i.name = name,
         }
     }
 
@@ -241,11 +304,16 @@ impl NodeType {
             NodeType::Control(control) => control.is_valid(),
             NodeType::Fan(fan) => fan.is_valid(),
             NodeType::Temp(temp) => temp.is_valid(),
+            NodeType::Sensor(sensor) => sensor.is_valid(),
             NodeType::CustomTemp(custom_temp) => custom_temp.is_valid(),
             NodeType::Graph(graph) => graph.is_valid(),
             NodeType::Flat(flat) => flat.is_valid(),
             NodeType::Linear(linear, ..) => linear.is_valid(),
             NodeType::Target(target, ..) => target.is_valid(),
+            NodeType::Hysteresis(hysteresis) => hysteresis.is_valid(),
+            NodeType::Smoothing(smoothing) => smoothing.is_valid(),
+            NodeType::Pid(pid) => pid.is_valid(),
+            NodeType::RpmTarget(rpm_target) => rpm_target.is_valid(),
         }
     }
 
@@ -254,11 +322,16 @@ impl NodeType {
             NodeType::Control(i) => i.input.clone().map_or(Vec::new(), |i| vec![i]),
             NodeType::Fan(_) => Vec::new(),
             NodeType::Temp(_) => Vec::new(),
+            NodeType::Sensor(_) => Vec::new(),
             NodeType::CustomTemp(i) => i.inputs.clone(),
             NodeType::Graph(i) => i.input.clone().map_or(Vec::new(), |i| vec![i]),
             NodeType::Flat(_) => Vec::new(),
             NodeType::Linear(i, ..) => i.input.clone().map_or(Vec::new(), |i| vec![i]),
             NodeType::Target(i, ..) => i.input.clone().map_or(Vec::new(), |i| vec![i]),
+            NodeType::Hysteresis(i) => i.input.clone().map_or(Vec::new(), |i| vec![i]),
+            NodeType::Smoothing(i) => i.input.clone().map_or(Vec::new(), |i| vec![i]),
+            NodeType::Pid(i) => i.input.clone().map_or(Vec::new(), |i| vec![i]),
+            NodeType::RpmTarget(i) => i.get_inputs(),
         }
     }
 
@@ -299,8 +372,34 @@ impl NodeType {
                     i.input.take();
                 }
             },
+            NodeType::Hysteresis(i) => match inputs.first() {
+                Some(input) => {
+                    let _ = i.input.insert(input.clone());
+                }
+                None => {
+                    i.input.take();
+                }
+            },
+            NodeType::Smoothing(i) => match inputs.first() {
+                Some(input) => {
+                    let _ = i.input.insert(input.clone());
+                }
+                None => {
+                    i.input.take();
+                }
+            },
+            NodeType::Pid(i) => match inputs.first() {
+                Some(input) => {
+                    let _ = i.input.insert(input.clone());
+                }
+                None => {
+                    i.input.take();
+                }
+            },
+            NodeType::RpmTarget(i) => i.set_inputs(inputs),
             NodeType::Fan(_) => {}
             NodeType::Temp(_) => {}
+            NodeType::Sensor(_) => {}
             NodeType::Flat(_) => {}
         };
     }
@@ -312,14 +411,66 @@ impl NodeType {
                 NodeTypeLight::Graph,
                 NodeTypeLight::Target,
                 NodeTypeLight::Linear,
+                NodeTypeLight::Pid,
+                NodeTypeLight::Hysteresis,
+                NodeTypeLight::RpmTarget,
             ],
             NodeType::Fan(..) => &[],
             NodeType::Temp(..) => &[],
+            NodeType::Sensor(..) => &[],
             NodeType::CustomTemp(..) => &[NodeTypeLight::Temp],
-            NodeType::Graph(..) => &[NodeTypeLight::Temp, NodeTypeLight::CustomTemp],
+            NodeType::Graph(..) => &[
+                NodeTypeLight::Temp,
+                NodeTypeLight::CustomTemp,
+                NodeTypeLight::Sensor,
+                NodeTypeLight::Smoothing,
+                NodeTypeLight::Hysteresis,
+            ],
             NodeType::Flat(..) => &[],
-            NodeType::Linear(..) => &[NodeTypeLight::Temp, NodeTypeLight::CustomTemp],
-            NodeType::Target(..) => &[NodeTypeLight::Temp, NodeTypeLight::CustomTemp],
+            NodeType::Smoothing(..) => &[
+                NodeTypeLight::Temp,
+                NodeTypeLight::CustomTemp,
+                NodeTypeLight::Sensor,
+            ],
+            NodeType::Pid(..) => &[
+                NodeTypeLight::Temp,
+                NodeTypeLight::CustomTemp,
+                NodeTypeLight::Smoothing,
+            ],
+            NodeType::RpmTarget(..) => &[
+                NodeTypeLight::Fan,
+                NodeTypeLight::Flat,
+                NodeTypeLight::Graph,
+                NodeTypeLight::Target,
+                NodeTypeLight::Linear,
+                NodeTypeLight::Pid,
+                NodeTypeLight::Hysteresis,
+            ],
+            NodeType::Linear(..) => &[
+                NodeTypeLight::Temp,
+                NodeTypeLight::CustomTemp,
+                NodeTypeLight::Sensor,
+                NodeTypeLight::Smoothing,
+                NodeTypeLight::Hysteresis,
+            ],
+            NodeType::Target(..) => &[
+                NodeTypeLight::Temp,
+                NodeTypeLight::CustomTemp,
+                NodeTypeLight::Sensor,
+                NodeTypeLight::Smoothing,
+                NodeTypeLight::Hysteresis,
+            ],
+            NodeType::Hysteresis(..) => &[
+                NodeTypeLight::Temp,
+                NodeTypeLight::CustomTemp,
+                NodeTypeLight::Sensor,
+                NodeTypeLight::Smoothing,
+                NodeTypeLight::Flat,
+                NodeTypeLight::Graph,
+                NodeTypeLight::Target,
+                NodeTypeLight::Linear,
+                NodeTypeLight::Pid,
+            ],
         }
     }
 
@@ -328,22 +479,35 @@ impl NodeType {
             NodeType::Control(..) => NbInput::One,
             NodeType::Fan(..) => NbInput::Zero,
             NodeType::Temp(..) => NbInput::Zero,
+            NodeType::Sensor(..) => NbInput::Zero,
             NodeType::CustomTemp(..) => NbInput::Infinity,
             NodeType::Graph(..) => NbInput::One,
             NodeType::Flat(..) => NbInput::Zero,
             NodeType::Linear(..) => NbInput::One,
             NodeType::Target(..) => NbInput::One,
+            NodeType::Hysteresis(..) => NbInput::One,
+            NodeType::Smoothing(..) => NbInput::One,
+            NodeType::Pid(..) => NbInput::One,
+            NodeType::RpmTarget(..) => NbInput::Infinity,
         }
     }
 
     pub fn is_sensor(&self) -> bool {
-        matches!(self, NodeType::Fan(..) | NodeType::Temp(..))
+        matches!(
+            self,
+            NodeType::Fan(..) | NodeType::Temp(..) | NodeType::Sensor(..)
+        )
     }
 
     pub fn is_behavior(&self) -> bool {
         matches!(
             self,
-            NodeType::Graph(..) | NodeType::Flat(..) | NodeType::Linear(..) | NodeType::Target(..)
+            NodeType::Graph(..)
+                | NodeType::Flat(..)
+                | NodeType::Linear(..)
+                | NodeType::Target(..)
+                | NodeType::Pid(..)
+                | NodeType::RpmTarget(..)
         )
     }
 
@@ -351,6 +515,23 @@ impl NodeType {
         matches!(self, NodeType::Control(..))
     }
 
+    /// Whether the inputs are percentages, instead of temps.
+    pub fn takes_behaviors(&self) -> bool {
+        matches!(self, NodeType::Control(..) | NodeType::RpmTarget(..))
+    }
+
+    /// Forget the state kept between updates,
+    /// when the node is invalid or its input is lost.
+    pub fn reset_state(&mut self) {
+        match self {
+            NodeType::Hysteresis(hysteresis) => hysteresis.reset(),
+            NodeType::Smoothing(smoothing) => smoothing.reset(),
+            NodeType::Pid(pid) => pid.reset(),
+            NodeType::RpmTarget(rpm_target) => rpm_target.reset(),
+            _ => {}
+        }
+    }
+
     pub fn compare_update_priority(&self, other: &Self) -> Ordering {
         match self {
             NodeType::Control(_) => match other {
@@ -364,26 +545,34 @@ impl NodeType {
                     Ordering::Less
                 }
             }
-            NodeType::Temp(_) => {
+            NodeType::Temp(_) | NodeType::Sensor(_) => {
                 if other.is_sensor() {
                     Ordering::Equal
                 } else {
                     Ordering::Less
                 }
             }
-            NodeType::CustomTemp(_) => match other {
-                NodeType::CustomTemp(_) => Ordering::Equal,
+            NodeType::CustomTemp(_) | NodeType::Smoothing(_) => match other {
+                NodeType::CustomTemp(_) | NodeType::Smoothing(_) => Ordering::Equal,
                 NodeType::Fan(_) => Ordering::Greater,
                 NodeType::Temp(_) => Ordering::Greater,
+                NodeType::Sensor(_) => Ordering::Greater,
                 _ => Ordering::Less,
             },
             NodeType::Flat(_) => Ordering::Equal,
 
-            NodeType::Graph(_) | NodeType::Linear(..) | NodeType::Target(..) => match other {
+            NodeType::Graph(_)
+            | NodeType::Linear(..)
+            | NodeType::Target(..)
+            | NodeType::Pid(..)
+            | NodeType::RpmTarget(..)
+            | NodeType::Hysteresis(..) => match other {
                 NodeType::Control(_) => Ordering::Less,
                 NodeType::Fan(_) => Ordering::Greater,
                 NodeType::Temp(_) => Ordering::Greater,
+                NodeType::Sensor(_) => Ordering::Greater,
                 NodeType::CustomTemp(_) => Ordering::Greater,
+                NodeType::Smoothing(_) => Ordering::Greater,
                 _ => Ordering::Equal,
             },
         }
@@ -0,0 +1,285 @@
+//! Find the hardware referenced by a config which doesn't exist anymore,
+//! after a bios update or a kernel upgrade for instance,
+//! and propose the most likely replacements.
+
+use std::{collections::HashSet, fmt::Display, rc::Rc};
+
+use hardware::{HItem, Hardware, SensorKind};
+
+use crate::config::Config;
+
+/// Maximum number of candidates proposed for one hardware id.
+const MAX_CANDIDATES: usize = 3;
+
+/// Kind of hardware referenced by a node of a config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HardwareKind {
+    Control,
+    Fan,
+    Temp,
+    Sensor(SensorKind),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub hardware_id: String,
+    pub name: String,
+    pub score: u32,
+}
+
+impl Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// A hardware id of a config not found in the current hardware.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unresolved {
+    pub node_name: String,
+    pub kind: HardwareKind,
+    pub hardware_id: String,
+    /// Best first
+    pub candidates: Vec<Candidate>,
+}
+
+impl Unresolved {
+    pub fn remapping(&self, candidate: &Candidate) -> Remapping {
+        Remapping {
+            kind: self.kind,
+            old_id: self.hardware_id.clone(),
+            new_id: candidate.hardware_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remapping {
+    pub kind: HardwareKind,
+    pub old_id: String,
+    pub new_id: String,
+}
+
+impl Config {
+    /// Return true if the config changed.
+    pub fn remap_hardware_ids(&mut self, remappings: &[Remapping]) -> bool {
+        let mut changed = false;
+
+        for (kind, hardware_id) in self.hardware_ids_mut() {
+            let Some(hardware_id) = hardware_id else {
+                continue;
+            };
+
+            if let Some(remapping) = remappings
+                .iter()
+                .find(|r| r.kind == kind && &r.old_id == hardware_id)
+            {
+                info!("Remap hardware id {} to {}.", hardware_id, remapping.new_id);
+                *hardware_id = remapping.new_id.clone();
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// List the hardware ids of this config which are not in `hardware`,
+    /// with the hardware that could replace them.
+    pub fn unresolved_hardware_ids(&self, hardware: &Hardware) -> Vec<Unresolved> {
+        let ids = self.hardware_ids();
+
+        // hardware already used by another node
+        let used = ids
+            .iter()
+            .filter_map(|(kind, _, id)| id.as_ref().map(|id| (*kind, id)))
+            .collect::<HashSet<_>>();
+
+        let mut unresolved = Vec::new();
+
+        for (kind, node_name, hardware_id) in &ids {
+            let Some(hardware_id) = hardware_id else {
+                continue;
+            };
+
+            let items = items_of(hardware, *kind);
+
+            if items.iter().any(|item| item.id() == hardware_id) {
+                continue;
+            }
+
+            let mut candidates = items
+                .into_iter()
+                .filter(|item| !used.contains(&(*kind, item.id())))
+                .map(|item| Candidate {
+                    hardware_id: item.id().clone(),
+                    name: item.name().clone(),
+                    score: score(hardware_id, item.id(), item.name()),
+                })
+                .filter(|candidate| candidate.score > 0)
+                .collect::<Vec<_>>();
+
+            // stable sort: keep the hardware order for equal scores
+            candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.score));
+            candidates.truncate(MAX_CANDIDATES);
+
+            unresolved.push(Unresolved {
+                node_name: (*node_name).clone(),
+                kind: *kind,
+                hardware_id: hardware_id.clone(),
+                candidates,
+            });
+        }
+
+        unresolved
+    }
+}
+
+fn items_of(hardware: &Hardware, kind: HardwareKind) -> Vec<Rc<dyn HItem>> {
+    fn to_dyn<T: HItem + 'static>(items: &[Rc<T>]) -> Vec<Rc<dyn HItem>> {
+        items
+            .iter()
+            .map(|item| item.clone() as Rc<dyn HItem>)
+            .collect()
+    }
+
+    match kind {
+        HardwareKind::Control => to_dyn(&hardware.controls),
+        HardwareKind::Fan => to_dyn(&hardware.fans),
+        HardwareKind::Temp => to_dyn(&hardware.temps),
+        HardwareKind::Sensor(sensor_kind) => to_dyn(hardware.sensors(sensor_kind)),
+    }
+}
+
+fn tokens(str: &str) -> HashSet<String> {
+    str.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_lowercase())
+        .collect()
+}
+
+/// `pwm2` -> `pwm`, `fan1_input` -> `fan`
+fn feature_prefix(sub_feature: &str) -> Option<&str> {
+    sub_feature
+        .find(|c: char| c.is_ascii_digit())
+        .map(|index| &sub_feature[..index])
+}
+
+/// How likely the hardware `candidate_id` replaced `missing_id`.
+///
+/// Works with the current (`chip@device/sub_feature`) and the legacy
+/// (`label-chip-sub_feature`) ids, by comparing the chip, the index of the
+/// feature and the label.
+fn score(missing_id: &str, candidate_id: &str, candidate_name: &str) -> u32 {
+    let mut score = 0;
+
+    let missing_tokens = tokens(missing_id);
+
+    // same chip, or at least the same driver
+    if let Some((chip, _)) = candidate_id.split_once('@') {
+        let same_chip = match missing_id.split_once('@') {
+            Some((missing_chip, _)) => missing_chip == chip,
+            None => missing_id.contains(&format!("-{chip}-")),
+        };
+
+        if same_chip {
+            score += 4;
+        } else if chip
+            .split('-')
+            .next()
+            .is_some_and(|driver| missing_tokens.contains(&driver.to_lowercase()))
+        {
+            score += 2;
+        }
+    }
+
+    // same index
+    let sub_feature = |id: &'_ str| id.rsplit(['/', '-']).next().unwrap_or_default().to_owned();
+    let missing_sub_feature = sub_feature(missing_id);
+    let candidate_sub_feature = sub_feature(candidate_id);
+
+    if missing_sub_feature == candidate_sub_feature {
+        score += 3;
+    } else if feature_prefix(&missing_sub_feature).is_some()
+        && feature_prefix(&missing_sub_feature) == feature_prefix(&candidate_sub_feature)
+    {
+        score += 1;
+    }
+
+    // similar label
+    let candidate_tokens = tokens(candidate_name);
+    let common = missing_tokens.intersection(&candidate_tokens).count();
+    let total = missing_tokens.union(&candidate_tokens).count();
+    if total > 0 {
+        score += (3 * common as u32 + total as u32 / 2) / total as u32;
+    }
+
+    score
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::{Config, control::Control};
+
+    use super::{HardwareKind, Remapping, score};
+
+    #[test]
+    fn test_score() {
+        let missing = "nct6775-isa-0290@platform/nct6775.656/pwm2";
+
+        let same_sub_feature = score(
+            missing,
+            "nct6798-isa-0290@platform/nct6775.656/pwm2",
+            "pwm2 nct6798-isa-0290",
+        );
+        let other_index = score(
+            missing,
+            "nct6798-isa-0290@platform/nct6775.656/pwm3",
+            "pwm3 nct6798-isa-0290",
+        );
+        let other_chip = score(
+            missing,
+            "amdgpu-pci-0300@pci0000:00/0000:00:01.1/0000:03:00.0/pwm1",
+            "pwm1 amdgpu-pci-0300",
+        );
+        assert!(same_sub_feature > other_index);
+        assert!(other_index > other_chip);
+
+        // the driver changed the label, but not the chip
+        let legacy = "CPU FAN-nct6775-isa-0290-pwm2";
+        assert!(
+            score(
+                legacy,
+                "nct6775-isa-0290@platform/nct6775.656/pwm2",
+                "CPU_FAN nct6775-isa-0290",
+            ) > score(
+                legacy,
+                "nct6775-isa-0290@platform/nct6775.656/pwm1",
+                "SYS_FAN nct6775-isa-0290",
+            )
+        );
+    }
+
+    #[test]
+    fn test_remap() {
+        let mut config = Config {
+            controls: vec![Control::new(
+                "Control".into(),
+                Some("old".into()),
+                None,
+                true,
+                None,
+            )],
+            ..Default::default()
+        };
+
+        let remapping = |kind| Remapping {
+            kind,
+            old_id: "old".into(),
+            new_id: "new".into(),
+        };
+
+        assert!(!config.remap_hardware_ids(&[remapping(HardwareKind::Fan)]));
+        assert!(config.remap_hardware_ids(&[remapping(HardwareKind::Control)]));
+        assert_eq!(config.controls[0].hardware_id.as_deref(), Some("new"));
+    }
+}
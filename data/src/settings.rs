@@ -1,7 +1,10 @@
+use hardware::command_sensor::CommandSensor;
 use light_enum::Values;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
+use crate::stall::StallSettings;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct SettingsState {
@@ -24,6 +27,14 @@ pub struct Settings {
     pub update_delay: u64,
 
     pub current_config: Option<String>,
+
+    /// Temps parsed from the output of a command, added to the hardware
+    #[serde(rename = "CommandSensor")]
+    pub command_sensors: Vec<CommandSensor>,
+
+    /// Alarms raised when a fan stops spinning
+    #[serde(rename = "StallDetection")]
+    pub stall: StallSettings,
 }
 
 // todo: find a better solution to expose themes
@@ -44,6 +55,8 @@ impl Default for Settings {
             theme: Default::default(),
             update_delay: 1500,
             current_config: Default::default(),
+            command_sensors: Default::default(),
+            stall: Default::default(),
         }
     }
 }
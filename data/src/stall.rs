@@ -0,0 +1,387 @@
+//! Detect the fans which stopped, or slowed down a lot, while their control
+//! asks them to spin: a dead fan, a disconnected cable or a blocked rotor.
+//!
+//! The speed observed at each duty cycle is learned while the fan is healthy,
+//! so the detection doesn't need a calibration.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    process::Command,
+    thread,
+};
+
+use hardware::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::{app_graph::Nodes, id::Id, node::NodeType};
+
+/// What to do when a stall is detected, in addition to the log.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub enum StallAction {
+    #[default]
+    Log,
+    /// The program, followed by its arguments. The alarm is passed in the
+    /// `FAN_CONTROL_CONTROL`, `FAN_CONTROL_FAN`, `FAN_CONTROL_DUTY`
+    /// and `FAN_CONTROL_RPM` environment variables.
+    Command(Vec<String>),
+    /// Set all the other controls to 100%, until the fan recovers.
+    FullSpeed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct StallSettings {
+    pub enabled: bool,
+    /// %, the fan is only watched when its control is at or above this duty cycle
+    pub min_duty: Value,
+    /// %, the fan is stalled under this ratio of the speed previously observed
+    /// at the same duty cycle
+    pub ratio: Value,
+    /// number of consecutive updates before raising an alarm
+    pub cycles: u32,
+    pub action: StallAction,
+}
+
+impl Default for StallSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_duty: 30.0,
+            ratio: 30.0,
+            cycles: 5,
+            action: StallAction::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StallAlarm {
+    pub control: String,
+    pub fan: String,
+    /// %
+    pub duty: Value,
+    pub rpm: Value,
+    /// RPM previously observed at this duty cycle, if any
+    pub expected: Option<Value>,
+}
+
+impl Display for StallAlarm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self.expected {
+            Some(expected) => fl!(
+                "stall_alarm_expected",
+                fan = self.fan.clone(),
+                control = self.control.clone(),
+                rpm = self.rpm,
+                duty = self.duty,
+                expected = expected
+            ),
+            None => fl!(
+                "stall_alarm",
+                fan = self.fan.clone(),
+                control = self.control.clone(),
+                rpm = self.rpm,
+                duty = self.duty
+            ),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// One RPM per tenth of duty cycle, from 0% to 100%.
+const BUCKETS: usize = 11;
+
+fn bucket(duty: Value) -> usize {
+    (duty.clamp(0.0, 100.0) / 10.0) as usize
+}
+
+/// Checks in a row at the same tenth of duty cycle before its speed is learned,
+/// so a fan still slowing down from a higher duty cycle is not learned.
+const SETTLE_CYCLES: u32 = 3;
+
+#[derive(Debug, Default)]
+struct FanState {
+    /// highest speed seen at each tenth of duty cycle, while the fan was healthy
+    observed: [Option<Value>; BUCKETS],
+    /// tenth of duty cycle of the last check, and the number of checks in a row there
+    last_bucket: Option<(usize, u32)>,
+    bad_cycles: u32,
+    alarm: Option<StallAlarm>,
+}
+
+impl FanState {
+    /// Return true when the fan just stalled.
+    fn check(&mut self, settings: &StallSettings, duty: Value, rpm: Value) -> bool {
+        let bucket = bucket(duty);
+        let expected = self.observed[bucket];
+
+        let settled = match &mut self.last_bucket {
+            Some((last, count)) if *last == bucket => {
+                *count += 1;
+                *count >= SETTLE_CYCLES
+            }
+            _ => {
+                self.last_bucket = Some((bucket, 1));
+                false
+            }
+        };
+
+        let stalled = duty >= settings.min_duty
+            && match expected {
+                Some(expected) => rpm * 100.0 < expected * settings.ratio,
+                None => rpm == 0.0,
+            };
+
+        if !stalled {
+            self.bad_cycles = 0;
+            self.alarm = None;
+            // never learn a lower speed, or a slowly dying fan would never stall
+            if settled {
+                self.observed[bucket] = Some(expected.map_or(rpm, |expected| expected.max(rpm)));
+            }
+            return false;
+        }
+
+        self.bad_cycles += 1;
+        self.bad_cycles == settings.cycles.max(1)
+    }
+}
+
+/// Watch the fans of each control, see [`crate::config::control::Control::fans`].
+#[derive(Debug, Default)]
+pub struct StallMonitor {
+    pub settings: StallSettings,
+    /// per (control, fan) names
+    states: HashMap<(String, String), FanState>,
+    new_alarms: Vec<StallAlarm>,
+}
+
+impl StallMonitor {
+    /// Fans of the active controls, which must be updated before [`Self::check`].
+    pub fn watched_fans(&self, nodes: &Nodes) -> Vec<Id> {
+        if !self.settings.enabled {
+            return Vec::new();
+        }
+
+        let names = nodes
+            .values()
+            .filter_map(|node| match &node.node_type {
+                NodeType::Control(control) if control.active => Some(control.fans()),
+                _ => None,
+            })
+            .flatten()
+            .collect::<HashSet<_>>();
+
+        nodes
+            .values()
+            .filter(|node| {
+                matches!(node.node_type, NodeType::Fan(..)) && names.contains(node.name())
+            })
+            .map(|node| node.id)
+            .collect()
+    }
+
+    /// Should be called after each update of the controls, and of the watched fans.
+    /// The speeds are the values of the fan nodes, so they went through their filter.
+    pub fn check(&mut self, nodes: &Nodes) {
+        if !self.settings.enabled {
+            self.states.clear();
+            return;
+        }
+
+        let fans = nodes
+            .values()
+            .filter_map(|node| match &node.node_type {
+                NodeType::Fan(fan) => Some((&fan.name, node.value)),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut watched = HashSet::new();
+
+        for node in nodes.values() {
+            let NodeType::Control(control) = &node.node_type else {
+                continue;
+            };
+
+            if !control.active {
+                continue;
+            }
+
+            let Some(duty) = control.duty() else {
+                continue;
+            };
+
+            for fan_name in control.fans() {
+                let Some(rpm) = fans.get(fan_name) else {
+                    continue;
+                };
+
+                let Some(rpm) = *rpm else {
+                    debug!("Can't read the speed of {fan_name}.");
+                    continue;
+                };
+
+                let key = (control.name.clone(), fan_name.clone());
+                let state = self.states.entry(key.clone()).or_default();
+                let had_alarm = state.alarm.is_some();
+
+                if state.check(&self.settings, duty, rpm) {
+                    let alarm = StallAlarm {
+                        control: control.name.clone(),
+                        fan: fan_name.clone(),
+                        duty,
+                        rpm,
+                        expected: state.observed[bucket(duty)],
+                    };
+                    state.alarm = Some(alarm.clone());
+                    self.raise(alarm);
+                } else if had_alarm && state.alarm.is_none() {
+                    info!("The fan {fan_name} of {} recovered.", control.name);
+                }
+
+                watched.insert(key);
+            }
+        }
+
+        self.states.retain(|key, _| watched.contains(key));
+    }
+
+    fn raise(&mut self, alarm: StallAlarm) {
+        error!("{alarm}.");
+
+        match &self.settings.action {
+            StallAction::Log => {}
+            StallAction::Command(command) => run_command(command, &alarm),
+            StallAction::FullSpeed => warn!("Set the other controls to 100%."),
+        }
+
+        self.new_alarms.push(alarm);
+    }
+
+    /// Alarms raised since the last call.
+    pub fn take_new_alarms(&mut self) -> Vec<StallAlarm> {
+        std::mem::take(&mut self.new_alarms)
+    }
+
+    pub fn alarms(&self) -> impl Iterator<Item = &StallAlarm> {
+        self.states
+            .values()
+            .filter_map(|state| state.alarm.as_ref())
+    }
+
+    /// Controls which must be set to 100% because of an alarm.
+    pub fn forced_controls(&self, nodes: &Nodes) -> HashSet<Id> {
+        if self.settings.action != StallAction::FullSpeed {
+            return HashSet::new();
+        }
+
+        let stalled = self
+            .alarms()
+            .map(|alarm| &alarm.control)
+            .collect::<HashSet<_>>();
+
+        if stalled.is_empty() {
+            return HashSet::new();
+        }
+
+        nodes
+            .values()
+            .filter(|node| match &node.node_type {
+                NodeType::Control(control) => !stalled.contains(&control.name),
+                _ => false,
+            })
+            .map(|node| node.id)
+            .collect()
+    }
+}
+
+fn run_command(command: &[String], alarm: &StallAlarm) {
+    let Some((program, args)) = command.split_first() else {
+        warn!("The stall command is empty.");
+        return;
+    };
+
+    let child = Command::new(program)
+        .args(args)
+        .env("FAN_CONTROL_CONTROL", &alarm.control)
+        .env("FAN_CONTROL_FAN", &alarm.fan)
+        .env("FAN_CONTROL_DUTY", alarm.duty.to_string())
+        .env("FAN_CONTROL_RPM", alarm.rpm.to_string())
+        .spawn();
+
+    match child {
+        // don't block the update loop, but reap the process
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(e) => error!("Can't run the stall command {program}: {e}."),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hardware::Value;
+
+    use super::{FanState, SETTLE_CYCLES, StallSettings};
+
+    #[test]
+    fn test_stall() {
+        let settings = StallSettings::default();
+        let mut state = FanState::default();
+
+        // learn the speed of a healthy fan, once settled
+        assert!(!state.check(&settings, 60.0, 1500.0));
+        assert_eq!(state.observed[6], None);
+        for _ in 1..SETTLE_CYCLES {
+            assert!(!state.check(&settings, 60.0, 1200.0));
+        }
+        assert_eq!(state.observed[6], Some(1200.0));
+
+        // under the min duty, a stopped fan is expected
+        for _ in 0..10 {
+            assert!(!state.check(&settings, 20.0, 0.0));
+        }
+
+        // slower, but not stalled
+        assert!(!state.check(&settings, 60.0, 800.0));
+
+        let stalled = (0..settings.cycles)
+            .map(|_| state.check(&settings, 60.0, 100.0))
+            .collect::<Vec<_>>();
+        assert_eq!(stalled.iter().filter(|stalled| **stalled).count(), 1);
+        assert!(stalled.last().unwrap());
+
+        // the alarm is raised only once
+        assert!(!state.check(&settings, 60.0, 100.0));
+
+        assert!(!state.check(&settings, 60.0, 1100.0));
+        assert_eq!(state.bad_cycles, 0);
+
+        // never observed at this duty
+        let mut state = FanState::default();
+        for _ in 1..settings.cycles {
+            assert!(!state.check(&settings, 100.0, 0.0));
+        }
+        assert!(state.check(&settings, 100.0, 0.0));
+    }
+
+    #[test]
+    fn test_slow_failure() {
+        let settings = StallSettings::default();
+        let mut state = FanState::default();
+
+        for _ in 0..10 {
+            assert!(!state.check(&settings, 60.0, 1200.0));
+        }
+
+        // the fan wears out over many cycles
+        let stalled_at = (0..200)
+            .map(|i| 1200.0 * (1.0 - i as Value / 200.0))
+            .position(|rpm| state.check(&settings, 60.0, rpm));
+
+        assert!(stalled_at.is_some());
+        assert_eq!(state.observed[6], Some(1200.0));
+    }
+}
@@ -1,13 +1,15 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, rc::Rc};
 
-use hardware::{HardwareBridge, Mode, Value};
+use hardware::{HSensor, HardwareBridge, Mode, OnExit, Value};
 
 use thiserror::Error;
 
 use crate::{
     app_graph::{Nodes, RootNodes},
+    failsafe::{Failsafe, FailsafeMonitor},
     id::Id,
     node::{Node, NodeType},
+    stall::StallMonitor,
 };
 
 #[derive(Error, Debug)]
@@ -20,6 +22,8 @@ pub enum UpdateError {
     NodeIsInvalid(String),
     #[error("No input data")]
     NoInputData,
+    #[error("The reading {0} was rejected")]
+    Rejected(Value),
     #[error("Can't set mode")]
     CantSetMode,
     #[error(transparent)]
@@ -28,7 +32,10 @@ pub enum UpdateError {
 
 type Result<T> = std::result::Result<T, UpdateError>;
 
-pub struct Update {}
+pub struct Update {
+    pub stall: StallMonitor,
+    pub failsafe: FailsafeMonitor,
+}
 
 impl Default for Update {
     fn default() -> Self {
@@ -38,7 +45,10 @@ impl Default for Update {
 
 impl Update {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            stall: StallMonitor::default(),
+            failsafe: FailsafeMonitor::default(),
+        }
     }
 
     // todo: remember what nodes are valid
@@ -49,20 +59,30 @@ impl Update {
         &mut self,
         nodes: &mut Nodes,
         root_nodes: &RootNodes,
+        failsafe: &Failsafe,
         bridge: &mut H,
     ) -> Result<()> {
         let mut updated: HashSet<Id> = HashSet::new();
+        let forced = self.forced_controls(nodes, failsafe, &mut updated, bridge);
+
         for node_id in root_nodes {
-            if let Err(e) = Self::update_rec(nodes, node_id, &mut updated, bridge) {
+            if let Err(e) = Self::update_rec(nodes, node_id, &mut updated, &forced, bridge) {
                 error!("Can't update node: {}.", e);
             }
         }
+
+        self.check_stalls(nodes, &mut updated, bridge);
         Ok(())
     }
 
     /// Doesn't update root nodes and doesn't re update nodes that could have been updated (fans).
     /// Warning: doesn't call update from the bridge, it's the role of the caller.
-    pub fn all<H: HardwareBridge>(&mut self, nodes: &mut Nodes, bridge: &mut H) -> Result<()> {
+    pub fn all<H: HardwareBridge>(
+        &mut self,
+        nodes: &mut Nodes,
+        failsafe: &Failsafe,
+        bridge: &mut H,
+    ) -> Result<()> {
         let ids_to_update_sorted: Vec<Id>;
         {
             let mut key_values = nodes.iter().collect::<Vec<_>>();
@@ -75,15 +95,71 @@ impl Update {
         }
 
         let mut updated = HashSet::new();
+        let forced = self.forced_controls(nodes, failsafe, &mut updated, bridge);
+
         for id in ids_to_update_sorted {
-            if let Err(e) = Self::update_rec(nodes, &id, &mut updated, bridge) {
+            if let Err(e) = Self::update_rec(nodes, &id, &mut updated, &forced, bridge) {
                 error!("can't update node: {}", e);
             }
         }
 
+        self.check_stalls(nodes, &mut updated, bridge);
         Ok(())
     }
 
+    /// Read the fans watched by the stall detection, once per cycle and through
+    /// their filter like the other nodes, then look for stalls.
+    fn check_stalls<H: HardwareBridge>(
+        &mut self,
+        nodes: &mut Nodes,
+        updated: &mut HashSet<Id>,
+        bridge: &mut H,
+    ) {
+        for id in self.stall.watched_fans(nodes) {
+            if !updated.insert(id) {
+                continue;
+            }
+
+            if let Some(node) = nodes.get_mut(&id) {
+                if let Err(e) = node.update(&[], bridge) {
+                    debug!("Can't update node {}: {}.", node.name(), e);
+                }
+            }
+        }
+
+        self.stall.check(nodes);
+    }
+
+    /// Controls which must be set to 100%, by the failsafe or the stall detection.
+    /// The temps are all read first when the failsafe is enabled,
+    /// even those which aren't used by a control.
+    fn forced_controls<H: HardwareBridge>(
+        &mut self,
+        nodes: &mut Nodes,
+        failsafe: &Failsafe,
+        updated: &mut HashSet<Id>,
+        bridge: &mut H,
+    ) -> HashSet<Id> {
+        if failsafe.is_enabled() {
+            for node in nodes.values_mut() {
+                if !matches!(node.node_type, NodeType::Temp(_)) || !node.node_type.is_valid() {
+                    continue;
+                }
+
+                updated.insert(node.id);
+                if let Err(e) = node.update(&[], bridge) {
+                    error!("Can't update node {}: {}.", node.name(), e);
+                }
+            }
+        }
+
+        self.failsafe.check(failsafe, nodes, bridge.now());
+
+        let mut forced = self.stall.forced_controls(nodes);
+        forced.extend(self.failsafe.forced_controls(nodes));
+        forced
+    }
+
     pub fn nodes_which_update_can_change<H: HardwareBridge>(
         &mut self,
         nodes: &mut Nodes,
@@ -120,6 +196,26 @@ impl Update {
         Ok(())
     }
 
+    /// Pass the exit policy of the controls to the bridge.
+    /// Called whenever the graph is built or a control changes,
+    /// so the policies are also applied on a signal or after a crash.
+    pub fn set_on_exit<H: HardwareBridge>(&mut self, nodes: &Nodes, bridge: &mut H) {
+        // the controls no longer used by the graph go back to the default
+        for control_h in bridge.hardware().controls.clone() {
+            if let Err(e) = bridge.set_on_exit(&control_h, OnExit::default()) {
+                error!("Can't reset the exit policy of {}: {}.", control_h.name, e);
+            }
+        }
+
+        for node in nodes.values() {
+            if let NodeType::Control(control) = &node.node_type {
+                if let Err(e) = control.set_on_exit(bridge) {
+                    error!("Can't set the exit policy of {}: {}.", control.name, e);
+                }
+            }
+        }
+    }
+
     fn set_node_to_auto<H: HardwareBridge>(
         &mut self,
         nodes: &mut Nodes,
@@ -189,10 +285,25 @@ impl Update {
         true
     }
 
+    /// Hardware of the fan node associated with a control.
+    fn control_fan_h(nodes: &Nodes, node_id: &Id) -> Option<Rc<HSensor>> {
+        let NodeType::Control(control) = &nodes.get(node_id)?.node_type else {
+            return None;
+        };
+        let fan_name = control.fan.as_ref()?;
+
+        nodes.values().find_map(|node| match &node.node_type {
+            NodeType::Fan(fan) if &fan.name == fan_name => fan.fan_h.clone(),
+            _ => None,
+        })
+    }
+
+    /// `forced`: controls set to 100%, whatever their inputs.
     fn update_rec<H: HardwareBridge>(
         nodes: &mut Nodes,
         node_id: &Id,
         updated: &mut HashSet<Id>,
+        forced: &HashSet<Id>,
         bridge: &mut H,
     ) -> Result<Option<Value>> {
         if updated.contains(node_id) {
@@ -213,14 +324,30 @@ impl Update {
                 if !node.is_root() {
                     node.value = None;
                 }
+                node.node_type.reset_state();
                 return Ok(None);
             }
+
+            if forced.contains(node_id) {
+                if let NodeType::Control(control) = &mut node.node_type {
+                    control.verify(bridge);
+                    control.set_full_speed(bridge)?;
+                    node.value = Some(100.0);
+                    return Ok(node.value);
+                }
+            }
+
             input_ids = node.inputs.iter().map(|i| i.id).collect();
         }
 
         let mut input_values = Vec::new();
         for id in &input_ids {
-            match Self::update_rec(nodes, id, updated, bridge)? {
+            let value = Self::update_rec(nodes, id, updated, forced, bridge).inspect_err(|_| {
+                if let Some(node) = nodes.get_mut(node_id) {
+                    node.node_type.reset_state();
+                }
+            })?;
+            match value {
                 Some(value) => input_values.push(value),
                 None => {
                     return match nodes.get_mut(node_id) {
@@ -228,6 +355,8 @@ impl Update {
                             if !node.is_root() {
                                 node.value = None;
                             }
+                            // the input is lost, its history is no longer relevant
+                            node.node_type.reset_state();
                             Ok(None)
                         }
                         None => Err(UpdateError::NodeNotFound(*node_id)),
@@ -236,10 +365,16 @@ impl Update {
             }
         }
 
+        let fan_h = Self::control_fan_h(nodes, node_id);
+
         let Some(node) = nodes.get_mut(node_id) else {
             return Err(UpdateError::NodeNotFound(*node_id));
         };
 
+        if let NodeType::Control(control) = &mut node.node_type {
+            control.fan_h = fan_h;
+        }
+
         node.update(&input_values, bridge)?;
 
         Ok(node.value)
@@ -250,8 +385,10 @@ impl Node {
     fn update<H: HardwareBridge>(&mut self, input_values: &[Value], bridge: &mut H) -> Result<()> {
         let value = match &mut self.node_type {
             crate::node::NodeType::Control(control) => {
-                let input_value = input_values[0];
-                return if self.value == Some(input_value) {
+                let input_value = control.ramp(input_values[0], self.value, bridge.now());
+                let input_value = control.limit(input_value);
+                let overridden = control.verify(bridge);
+                return if self.value == Some(input_value) && !overridden {
                     debug!("Control {} already set to {}", control.name, input_value);
                     Ok(())
                 } else {
@@ -261,11 +398,23 @@ impl Node {
             }
             crate::node::NodeType::Fan(fan) => fan.get_value(bridge),
             crate::node::NodeType::Temp(temp) => temp.get_value(bridge),
+            crate::node::NodeType::Sensor(sensor) => sensor.get_value(bridge),
             crate::node::NodeType::CustomTemp(custom_temp) => custom_temp.get_value(input_values),
             crate::node::NodeType::Graph(graph) => graph.get_value(input_values[0]),
             crate::node::NodeType::Flat(flat) => Ok(flat.value.into()),
             crate::node::NodeType::Linear(linear, ..) => linear.get_value(input_values[0]),
             crate::node::NodeType::Target(target, ..) => target.get_value(input_values[0]),
+            crate::node::NodeType::Hysteresis(hysteresis) => {
+                hysteresis.get_value(input_values[0], bridge.now())
+            }
+            crate::node::NodeType::Smoothing(smoothing) => {
+                smoothing.get_value(input_values[0], bridge.now())
+            }
+            crate::node::NodeType::Pid(pid) => pid.get_value(input_values[0], bridge.now()),
+            // the fan is the first input, the target the second one
+            crate::node::NodeType::RpmTarget(rpm_target) => {
+                rpm_target.get_value(input_values[0], input_values.get(1).copied(), bridge.now())
+            }
         };
 
         match value {
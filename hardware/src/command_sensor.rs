@@ -0,0 +1,493 @@
+//! Temps read from the output of a command, like `nvidia-smi` or `smartctl`.
+//!
+//! Each command runs in its own thread, so a slow command never blocks
+//! the update loop: [`CommandSensorBridge`] only returns the last value parsed.
+
+use std::{
+    io::{BufRead, BufReader, Read},
+    process::{Child, Command, Stdio},
+    rc::Rc,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{HControl, HSensor, Hardware, HardwareBridge, Mode, OnExit, Value};
+
+#[derive(Error, Debug)]
+pub enum CommandSensorError {
+    #[error("Invalid regex for {0}: {1}")]
+    Regex(String, regex::Error),
+    #[error("No value yet for {0}")]
+    NoValue(String),
+    #[error("{0}: {1}")]
+    Command(String, String),
+}
+
+/// Match the first number of the output.
+static DEFAULT_REGEX: &str = r"-?\d+(?:\.\d+)?";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CommandSensor {
+    pub name: String,
+    /// The program, followed by its arguments
+    pub command: Vec<String>,
+    /// The command keeps running, and each line of its output is a new value.
+    /// Otherwise, the command is run again at each interval.
+    #[serde(default)]
+    pub stream: bool,
+    /// in ms
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    /// in ms. Maximum duration of a run, or between two lines in stream mode
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    /// The first capture group, or the whole match, is parsed as the value.
+    /// Default to the first number of the output.
+    pub regex: Option<String>,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub offset: f64,
+}
+
+fn default_interval() -> u64 {
+    2000
+}
+
+fn default_timeout() -> u64 {
+    5000
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+impl CommandSensor {
+    pub fn hardware_id(&self) -> String {
+        format!("command-{}", self.name)
+    }
+
+    fn parse(&self, regex: &Regex, output: &str) -> Result<f64, String> {
+        let captures = regex
+            .captures(output)
+            .ok_or_else(|| format!("no match in {:?}", output.trim()))?;
+
+        let matched = captures.get(1).or_else(|| captures.get(0)).unwrap();
+
+        matched
+            .as_str()
+            .trim()
+            .parse::<f64>()
+            .map(|number| number * self.scale + self.offset)
+            .map_err(|e| format!("{:?}: {}", matched.as_str(), e))
+    }
+
+    fn spawn(&self) -> Result<Child, String> {
+        let Some((program, args)) = self.command.split_first() else {
+            return Err("empty command".into());
+        };
+
+        Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| e.to_string())
+    }
+
+    fn run_once(&self, regex: &Regex) -> Result<f64, String> {
+        let mut child = self.spawn()?;
+        let deadline = Instant::now() + Duration::from_millis(self.timeout);
+
+        // read while waiting: a command writing more than the pipe buffer
+        // blocks until its output is read, and would never exit
+        let reader = child.stdout.take().map(|mut stdout| {
+            thread::spawn(move || {
+                let mut output = String::new();
+                stdout.read_to_string(&mut output).map(|_| output)
+            })
+        });
+
+        loop {
+            match child.try_wait() {
+                Ok(Some(_)) => break,
+                Ok(None) if Instant::now() > deadline => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err("timeout".into());
+                }
+                Ok(None) => thread::sleep(Duration::from_millis(10)),
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        let output = match reader {
+            Some(reader) => reader
+                .join()
+                .map_err(|_| "the output reader panicked".to_string())?
+                .map_err(|e| e.to_string())?,
+            None => String::new(),
+        };
+
+        self.parse(regex, &output)
+    }
+}
+
+type LastValue = Arc<Mutex<Option<(Instant, Result<f64, String>)>>>;
+
+struct Worker {
+    sensor: CommandSensor,
+    last_value: LastValue,
+    stop: Arc<AtomicBool>,
+    /// the running command, in stream mode
+    child: Arc<Mutex<Option<Child>>>,
+}
+
+impl Worker {
+    fn start(sensor: CommandSensor, regex: Regex) -> Self {
+        let worker = Self {
+            sensor,
+            last_value: Default::default(),
+            stop: Default::default(),
+            child: Default::default(),
+        };
+
+        let sensor = worker.sensor.clone();
+        let last_value = worker.last_value.clone();
+        let stop = worker.stop.clone();
+        let child = worker.child.clone();
+
+        thread::spawn(move || {
+            if sensor.stream {
+                stream(&sensor, &regex, &last_value, &stop, &child)
+            } else {
+                poll(&sensor, &regex, &last_value, &stop)
+            }
+        });
+
+        worker
+    }
+
+    fn value(&self) -> std::result::Result<Value, CommandSensorError> {
+        let name = &self.sensor.name;
+
+        match &*self.last_value.lock().unwrap() {
+            None => Err(CommandSensorError::NoValue(name.clone())),
+            Some((instant, _))
+                if self.sensor.stream
+                    && instant.elapsed() > Duration::from_millis(self.sensor.timeout) =>
+            {
+                Err(CommandSensorError::Command(
+                    name.clone(),
+                    "no new value before the timeout".into(),
+                ))
+            }
+            Some((_, Ok(value))) => Ok(*value),
+            Some((_, Err(e))) => Err(CommandSensorError::Command(name.clone(), e.clone())),
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(mut child) = self.child.lock().unwrap().take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+fn poll(sensor: &CommandSensor, regex: &Regex, last_value: &LastValue, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        let result = sensor.run_once(regex);
+
+        if let Err(e) = &result {
+            debug!("command sensor {}: {}", sensor.name, e);
+        }
+        last_value.lock().unwrap().replace((Instant::now(), result));
+
+        // sleep by small steps, to stop quickly
+        let next = Instant::now() + Duration::from_millis(sensor.interval);
+        while !stop.load(Ordering::Relaxed) && Instant::now() < next {
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+fn stream(
+    sensor: &CommandSensor,
+    regex: &Regex,
+    last_value: &LastValue,
+    stop: &AtomicBool,
+    child: &Mutex<Option<Child>>,
+) {
+    while !stop.load(Ordering::Relaxed) {
+        let stdout = {
+            let mut child = child.lock().unwrap();
+
+            // the worker could have been dropped while we were sleeping
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+
+            match sensor.spawn() {
+                Ok(mut new_child) => {
+                    let stdout = new_child.stdout.take();
+                    child.replace(new_child);
+                    stdout
+                }
+                Err(e) => {
+                    last_value.lock().unwrap().replace((Instant::now(), Err(e)));
+                    None
+                }
+            }
+        };
+
+        if let Some(stdout) = stdout {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                // lines without value are ignored, like headers
+                if let Ok(value) = sensor.parse(regex, &line) {
+                    last_value
+                        .lock()
+                        .unwrap()
+                        .replace((Instant::now(), Ok(value)));
+                }
+            }
+
+            if let Some(mut child) = child.lock().unwrap().take() {
+                let _ = child.wait();
+            }
+        }
+
+        if !stop.load(Ordering::Relaxed) {
+            warn!("command sensor {} stopped, restarting it", sensor.name);
+            thread::sleep(Duration::from_millis(sensor.interval));
+        }
+    }
+}
+
+/// Add command sensors to the temps of another bridge.
+pub struct CommandSensorBridge<H: HardwareBridge> {
+    inner: H,
+    hardware: Hardware,
+    workers: Vec<(Rc<HSensor>, Worker)>,
+}
+
+impl<H: HardwareBridge> CommandSensorBridge<H> {
+    pub fn with_sensors(mut inner: H, command_sensors: Vec<CommandSensor>) -> Self {
+        let mut hardware = inner.take_hardware();
+
+        let mut workers = Vec::new();
+        let first_index = hardware.next_sensor_index();
+
+        for (index, command_sensor) in command_sensors.into_iter().enumerate() {
+            let hardware_id = command_sensor.hardware_id();
+
+            if hardware
+                .temps
+                .iter()
+                .any(|temp| temp.hardware_id == hardware_id)
+            {
+                warn!("command sensor {} is declared twice", hardware_id);
+                continue;
+            }
+
+            let regex = match Regex::new(command_sensor.regex.as_deref().unwrap_or(DEFAULT_REGEX)) {
+                Ok(regex) => regex,
+                Err(e) => {
+                    error!("{}", CommandSensorError::Regex(command_sensor.name, e));
+                    continue;
+                }
+            };
+
+            let sensor = Rc::new(HSensor {
+                name: command_sensor.name.clone(),
+                hardware_id,
+                info: command_sensor.command.join(" "),
+                internal_index: first_index + index,
+            });
+
+            hardware.temps.push(sensor.clone());
+            workers.push((sensor, Worker::start(command_sensor, regex)));
+        }
+
+        Self {
+            inner,
+            hardware,
+            workers,
+        }
+    }
+
+    fn worker(&self, sensor: &HSensor) -> Option<&Worker> {
+        self.workers
+            .iter()
+            .find(|(h_sensor, _)| std::ptr::eq(h_sensor.as_ref(), sensor))
+            .map(|(_, worker)| worker)
+    }
+}
+
+impl<H: HardwareBridge> HardwareBridge for CommandSensorBridge<H> {
+    /// Without any command sensor.
+    fn new() -> crate::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self::with_sensors(H::new()?, Vec::new()))
+    }
+
+    fn hardware(&self) -> &Hardware {
+        &self.hardware
+    }
+
+    fn take_hardware(&mut self) -> Hardware {
+        std::mem::take(&mut self.hardware)
+    }
+
+    fn get_sensor_value(&mut self, sensor: &HSensor) -> crate::Result<Value> {
+        match self.worker(sensor) {
+            Some(worker) => Ok(worker.value()?),
+            None => self.inner.get_sensor_value(sensor),
+        }
+    }
+
+    fn get_control_value(&mut self, control: &HControl) -> crate::Result<Value> {
+        self.inner.get_control_value(control)
+    }
+
+    fn set_value(&mut self, control: &HControl, value: Value) -> crate::Result<()> {
+        self.inner.set_value(control, value)
+    }
+
+    fn set_mode(&mut self, control: &HControl, mode: &Mode) -> crate::Result<()> {
+        self.inner.set_mode(control, mode)
+    }
+
+    fn get_mode(&mut self, control: &HControl) -> crate::Result<Option<Mode>> {
+        self.inner.get_mode(control)
+    }
+
+    fn set_on_exit(&mut self, control: &HControl, on_exit: OnExit) -> crate::Result<()> {
+        self.inner.set_on_exit(control, on_exit)
+    }
+
+    fn now(&self) -> Instant {
+        self.inner.now()
+    }
+
+    fn update(&mut self) -> crate::Result<()> {
+        self.inner.update()
+    }
+
+    fn shutdown(&mut self) -> crate::Result<()> {
+        self.workers.clear();
+        self.inner.shutdown()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::{thread, time::Duration};
+
+    use regex::Regex;
+
+    use super::{CommandSensor, DEFAULT_REGEX, Worker};
+
+    fn sensor(command: &str) -> CommandSensor {
+        CommandSensor {
+            name: "test".into(),
+            command: vec!["sh".into(), "-c".into(), command.into()],
+            stream: false,
+            interval: 50,
+            timeout: 500,
+            regex: None,
+            scale: 1.0,
+            offset: 0.0,
+        }
+    }
+
+    fn wait_value(worker: &Worker) {
+        for _ in 0..100 {
+            if worker.last_value.lock().unwrap().is_some() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let sensor = CommandSensor {
+            regex: Some(r"Temperature:\s+(\d+) Celsius".into()),
+            scale: 2.0,
+            offset: 1.0,
+            ..sensor("")
+        };
+        let regex = Regex::new(sensor.regex.as_ref().unwrap()).unwrap();
+        assert_eq!(
+            sensor.parse(&regex, "ID 1\nTemperature:   38 Celsius\n"),
+            Ok(77.0)
+        );
+        assert!(sensor.parse(&regex, "nothing").is_err());
+
+        let regex = Regex::new(DEFAULT_REGEX).unwrap();
+        assert_eq!(sensor.parse(&regex, "gpu -4.5 C"), Ok(-8.0));
+    }
+
+    #[test]
+    fn test_poll() {
+        let regex = Regex::new(DEFAULT_REGEX).unwrap();
+
+        let worker = Worker::start(sensor("echo 42.7"), regex.clone());
+        wait_value(&worker);
+        assert_eq!(worker.value().unwrap(), 42.7);
+
+        let worker = Worker::start(sensor("sleep 5; echo 42"), regex);
+        wait_value(&worker);
+        assert!(worker.value().is_err());
+    }
+
+    #[test]
+    fn test_large_output() {
+        let regex = Regex::new(DEFAULT_REGEX).unwrap();
+
+        // more than the pipe buffer, before the value
+        let sensor = sensor("head -c 200000 /dev/zero | tr '\\0' x; echo; echo 42");
+        assert_eq!(sensor.run_once(&regex), Ok(42.0));
+    }
+
+    #[test]
+    fn test_stream() {
+        let regex = Regex::new(DEFAULT_REGEX).unwrap();
+
+        let worker = Worker::start(
+            CommandSensor {
+                stream: true,
+                ..sensor("echo header; echo 30; sleep 0.2; echo 31; sleep 5")
+            },
+            regex,
+        );
+
+        wait_value(&worker);
+        assert_eq!(worker.value().unwrap(), 30.0);
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(worker.value().unwrap(), 31.0);
+        // no line for longer than the timeout
+        thread::sleep(Duration::from_millis(500));
+        assert!(worker.value().is_err());
+    }
+}
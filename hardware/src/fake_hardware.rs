@@ -1,82 +1,880 @@
-use rand::Rng;
+use std::{
+    f64::consts::PI,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
-use crate::{HControl, HSensor, Hardware, HardwareBridge, Mode, Value};
+use serde::Deserialize;
+use thiserror::Error;
 
+use crate::{HControl, HSensor, Hardware, HardwareBridge, Mode, OnExit, SensorKind, Value};
+
+/// Path of an inventory file, loaded by [`FakeHardwareBridge::new`] when set.
+pub const FAKE_HARDWARE_ENV: &str = "FAN_CONTROL_FAKE_HARDWARE";
+
+#[derive(Error, Debug)]
+pub enum FakeHardwareError {
+    #[error("{0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("Invalid inventory in {0}: {1}")]
+    InvalidData(String, String),
+    #[error("Unknown control {0}")]
+    UnknownControl(String),
+}
+
+type Result<T> = std::result::Result<T, FakeHardwareError>;
+
+/// Simulated hardware, with a simple thermal model.
+///
+/// Each temp is heated by a constant load, and cooled passively toward
+/// the ambient temperature, and actively by the controls linked to it.
+/// Fans follow the duty of their control with some inertia.
+/// The other sensors only follow their script.
+/// The simulation advances by `time_step` at each call to `update`.
+///
+/// The inventory can be loaded from a file, using the format of `hardware.toml`,
+/// see [`FakeHardwareBridge::from_file`].
 pub struct FakeHardwareBridge {
     hardware: Hardware,
+    pub simulation: Simulation,
+    /// the simulated time is counted from here
+    start: Instant,
 }
 
-static TEMP_INTERNAL_INDEX: usize = 0;
-static FAN_INTERNAL_INDEX: usize = 1;
-static CONTROL_INTERNAL_INDEX: usize = 2;
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub time_step: Duration,
+    /// °C
+    pub ambient: f64,
+    pub zones: Vec<ThermalZone>,
+    pub fans: Vec<SimFan>,
+    pub controls: Vec<SimControl>,
+    pub sensors: Vec<SimSensor>,
+    /// number of steps since the start
+    pub steps: u64,
+}
 
-impl HardwareBridge for FakeHardwareBridge {
-    fn new() -> crate::Result<Self> {
-        let mut hardware = Hardware::default();
+#[derive(Debug, Clone)]
+pub struct ThermalZone {
+    /// W
+    pub heat_load: f64,
+    /// J/°C
+    pub heat_capacity: f64,
+    /// W/°C, when all fans are stopped
+    pub passive_cooling: f64,
+    /// W/°C, added for each linked control at 100%
+    pub active_cooling: f64,
+    /// index of the controls cooling this zone
+    pub controls: Vec<usize>,
+    /// °C
+    pub temp: f64,
+    /// replace the thermal model when set
+    pub script: Option<Script>,
+}
 
-        let temp1 = HSensor {
-            name: "temp1".into(),
-            hardware_id: "temp1".into(),
-            info: String::new(),
-            internal_index: TEMP_INTERNAL_INDEX,
-        };
-        hardware.temps.push(temp1.into());
+#[derive(Debug, Clone)]
+pub struct SimFan {
+    /// index of the control driving this fan
+    pub control: usize,
+    pub max_rpm: f64,
+    /// under this duty, the fan stops
+    pub stop_duty: f64,
+    /// under this duty, a stopped fan doesn't start
+    pub start_duty: f64,
+    /// time constant of the fan, in seconds
+    pub inertia: f64,
+    pub rpm: f64,
+    /// replace the fan model when set
+    pub script: Option<Script>,
+}
+
+/// Power, voltage, current or fan target.
+#[derive(Debug, Clone, Default)]
+pub struct SimSensor {
+    pub kind: SensorKind,
+    /// in the unit of the kind, constant without a script
+    pub value: f64,
+    pub script: Option<Script>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimControl {
+    /// %, written by set_value
+    pub manual_duty: f64,
+    /// %, applied by the "firmware" in auto mode
+    pub auto_duty: f64,
+    pub manual: bool,
+    /// applied by the shutdown, so it can be checked after
+    pub on_exit: OnExit,
+}
+
+impl SimControl {
+    pub fn duty(&self) -> f64 {
+        if self.manual {
+            self.manual_duty
+        } else {
+            self.auto_duty
+        }
+    }
+}
+
+/// Predefined values of a sensor, as a function of the simulated time.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum Script {
+    Constant {
+        value: f64,
+    },
+    /// Go from `from` to `to` in `duration` seconds, then stay at `to`.
+    Ramp {
+        from: f64,
+        to: f64,
+        duration: f64,
+    },
+    /// Oscillate between `min` and `max`, with a period in seconds.
+    Sine {
+        min: f64,
+        max: f64,
+        period: f64,
+    },
+    /// One value per step, in the last column of each line, looping at the end.
+    /// A relative path is resolved from the directory of the inventory.
+    Csv {
+        path: PathBuf,
+        #[serde(skip)]
+        values: Vec<f64>,
+    },
+}
+
+impl Script {
+    pub fn value(&self, steps: u64, time_step: Duration) -> f64 {
+        let time = steps as f64 * time_step.as_secs_f64();
 
-        let temp2 = HSensor {
-            name: "temp2".into(),
-            hardware_id: "temp2".into(),
-            info: String::new(),
-            internal_index: TEMP_INTERNAL_INDEX,
+        match self {
+            Script::Constant { value } => *value,
+            Script::Ramp { from, to, duration } => {
+                if *duration <= 0.0 {
+                    return *to;
+                }
+                from + (to - from) * (time / duration).min(1.0)
+            }
+            Script::Sine { min, max, period } => {
+                let middle = (min + max) / 2.0;
+                middle + (max - middle) * (2.0 * PI * time / period).sin()
+            }
+            Script::Csv { values, .. } => {
+                if values.is_empty() {
+                    return 0.0;
+                }
+                values[(steps % values.len() as u64) as usize]
+            }
+        }
+    }
+
+    fn load(&mut self, dir: &Path) -> Result<()> {
+        let Script::Csv { path, values } = self else {
+            return Ok(());
         };
-        hardware.temps.push(temp2.into());
 
-        let fan1 = HSensor {
-            name: "fan1".into(),
-            hardware_id: "fan1".into(),
-            info: String::new(),
-            internal_index: FAN_INTERNAL_INDEX,
+        let path = dir.join(path);
+        let path_str = path.display().to_string();
+
+        let content =
+            fs::read_to_string(&path).map_err(|e| FakeHardwareError::Io(path_str.clone(), e))?;
+
+        for line in content.lines() {
+            let Some(field) = line.split(',').next_back().map(str::trim) else {
+                continue;
+            };
+
+            if field.is_empty() {
+                continue;
+            }
+
+            match field.parse() {
+                Ok(value) => values.push(value),
+                // header
+                Err(_) if values.is_empty() => {}
+                Err(e) => {
+                    return Err(FakeHardwareError::InvalidData(path_str, e.to_string()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Inventory {
+    #[serde(default, rename = "Control")]
+    controls: Vec<InventoryControl>,
+    #[serde(default, rename = "Fan")]
+    fans: Vec<InventoryFan>,
+    #[serde(default, rename = "Temp")]
+    temps: Vec<InventoryTemp>,
+    #[serde(default, rename = "Power")]
+    powers: Vec<InventorySensor>,
+    #[serde(default, rename = "Voltage")]
+    voltages: Vec<InventorySensor>,
+    #[serde(default, rename = "Current")]
+    currents: Vec<InventorySensor>,
+    #[serde(default, rename = "FanTarget")]
+    fan_targets: Vec<InventorySensor>,
+}
+
+impl Inventory {
+    fn take_sensors(&mut self) -> Vec<(SensorKind, InventorySensor)> {
+        [
+            (SensorKind::Power, &mut self.powers),
+            (SensorKind::Voltage, &mut self.voltages),
+            (SensorKind::Current, &mut self.currents),
+            (SensorKind::FanTarget, &mut self.fan_targets),
+        ]
+        .into_iter()
+        .flat_map(|(kind, sensors)| std::mem::take(sensors).into_iter().map(move |s| (kind, s)))
+        .collect()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct InventoryControl {
+    name: String,
+    #[serde(rename = "id")]
+    hardware_id: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct InventoryFan {
+    name: String,
+    #[serde(rename = "id")]
+    hardware_id: String,
+    /// id of the control driving this fan, default to the control with the same index
+    control: Option<String>,
+    script: Option<Script>,
+}
+
+#[derive(Deserialize, Debug)]
+struct InventoryTemp {
+    name: String,
+    #[serde(rename = "id")]
+    hardware_id: String,
+    /// ids of the controls cooling this temp, default to the control with the same index
+    controls: Option<Vec<String>>,
+    script: Option<Script>,
+}
+
+#[derive(Deserialize, Debug)]
+struct InventorySensor {
+    name: String,
+    #[serde(rename = "id")]
+    hardware_id: String,
+    script: Option<Script>,
+}
+
+impl Default for ThermalZone {
+    fn default() -> Self {
+        Self {
+            heat_load: 60.0,
+            heat_capacity: 30.0,
+            passive_cooling: 1.0,
+            active_cooling: 2.0,
+            controls: Vec::new(),
+            temp: 25.0,
+            script: None,
+        }
+    }
+}
+
+impl Default for SimFan {
+    fn default() -> Self {
+        Self {
+            control: 0,
+            max_rpm: 2000.0,
+            stop_duty: 20.0,
+            start_duty: 30.0,
+            inertia: 2.0,
+            rpm: 0.0,
+            script: None,
+        }
+    }
+}
+
+impl Default for SimControl {
+    fn default() -> Self {
+        Self {
+            manual_duty: 0.0,
+            auto_duty: 40.0,
+            manual: false,
+            on_exit: OnExit::default(),
+        }
+    }
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self {
+            time_step: Duration::from_secs(1),
+            ambient: 25.0,
+            zones: vec![
+                ThermalZone {
+                    controls: vec![0],
+                    ..Default::default()
+                },
+                ThermalZone {
+                    heat_load: 40.0,
+                    controls: vec![1],
+                    ..Default::default()
+                },
+            ],
+            fans: vec![SimFan::default()],
+            controls: vec![SimControl::default(), SimControl::default()],
+            sensors: Vec::new(),
+            steps: 0,
+        }
+    }
+}
+
+impl Simulation {
+    /// Simulated time since the start.
+    pub fn elapsed(&self) -> Duration {
+        self.time_step.mul_f64(self.steps as f64)
+    }
+
+    pub fn step(&mut self) {
+        let dt = self.time_step.as_secs_f64();
+        self.steps += 1;
+
+        for zone in &mut self.zones {
+            if let Some(script) = &zone.script {
+                zone.temp = script.value(self.steps, self.time_step);
+                continue;
+            }
+
+            let cooling = zone.passive_cooling
+                + zone
+                    .controls
+                    .iter()
+                    .filter_map(|index| self.controls.get(*index))
+                    .map(|control| zone.active_cooling * control.duty() / 100.0)
+                    .sum::<f64>();
+
+            let power = zone.heat_load - cooling * (zone.temp - self.ambient);
+            zone.temp += power * dt / zone.heat_capacity;
+        }
+
+        for sensor in &mut self.sensors {
+            if let Some(script) = &sensor.script {
+                sensor.value = script.value(self.steps, self.time_step);
+            }
+        }
+
+        for fan in &mut self.fans {
+            if let Some(script) = &fan.script {
+                fan.rpm = script.value(self.steps, self.time_step);
+                continue;
+            }
+
+            let duty = self
+                .controls
+                .get(fan.control)
+                .map(|control| control.duty())
+                .unwrap_or_default();
+
+            let stopped = fan.rpm == 0.0;
+
+            let target = if duty < fan.stop_duty || (stopped && duty < fan.start_duty) {
+                0.0
+            } else {
+                fan.max_rpm * duty / 100.0
+            };
+
+            let alpha = 1.0 - (-dt / fan.inertia).exp();
+            fan.rpm += (target - fan.rpm) * alpha;
+
+            // friction stops a slow fan without power
+            if target == 0.0 && fan.rpm < fan.max_rpm * 0.05 {
+                fan.rpm = 0.0;
+            }
+        }
+    }
+}
+
+impl FakeHardwareBridge {
+    pub fn with_simulation(simulation: Simulation) -> Self {
+        let names = |prefix: &str, len: usize| {
+            (1..=len)
+                .map(|index| {
+                    let name = format!("{prefix}{index}");
+                    (name.clone(), name)
+                })
+                .collect::<Vec<_>>()
         };
-        hardware.fans.push(fan1.into());
 
-        let control1 = HControl {
-            name: "control1".into(),
-            hardware_id: "control1".into(),
-            info: String::new(),
-            internal_index: CONTROL_INTERNAL_INDEX,
+        let controls = names("control", simulation.controls.len());
+        let fans = names("fan", simulation.fans.len());
+        let temps = names("temp", simulation.zones.len());
+        let sensors = names("sensor", simulation.sensors.len());
+
+        Self::from_parts(simulation, controls, fans, temps, sensors)
+    }
+
+    /// Load the inventory from a toml file, or a json file if its extension is `json`.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let path_str = path.display().to_string();
+
+        let content =
+            fs::read_to_string(path).map_err(|e| FakeHardwareError::Io(path_str.clone(), e))?;
+
+        let inventory: Inventory = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&content).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&content).map_err(|e| e.to_string())
+        }
+        .map_err(|e| FakeHardwareError::InvalidData(path_str, e))?;
+
+        Self::from_inventory(inventory, path.parent().unwrap_or(Path::new(".")))
+    }
+
+    fn from_inventory(mut inventory: Inventory, dir: &Path) -> Result<Self> {
+        let inventory_sensors = inventory.take_sensors();
+
+        let control_index = |id: &String| {
+            inventory
+                .controls
+                .iter()
+                .position(|control| &control.hardware_id == id)
+                .ok_or_else(|| FakeHardwareError::UnknownControl(id.clone()))
         };
-        hardware.controls.push(control1.into());
 
-        let control2 = HControl {
-            name: "control2".into(),
-            hardware_id: "control2".into(),
-            info: String::new(),
-            internal_index: CONTROL_INTERNAL_INDEX,
+        // by default, link to the control with the same index
+        let default_control = |index: usize| (index < inventory.controls.len()).then_some(index);
+
+        let mut simulation = Simulation {
+            zones: Vec::new(),
+            fans: Vec::new(),
+            controls: vec![SimControl::default(); inventory.controls.len()],
+            ..Default::default()
         };
-        hardware.controls.push(control2.into());
 
-        Ok(Self { hardware })
+        for (index, fan) in inventory.fans.iter().enumerate() {
+            let control = match &fan.control {
+                Some(id) => control_index(id)?,
+                None => default_control(index).unwrap_or(usize::MAX),
+            };
+
+            let mut script = fan.script.clone();
+            if let Some(script) = &mut script {
+                script.load(dir)?;
+            }
+
+            simulation.fans.push(SimFan {
+                control,
+                script,
+                ..Default::default()
+            });
+        }
+
+        for (index, temp) in inventory.temps.iter().enumerate() {
+            let controls = match &temp.controls {
+                Some(ids) => ids.iter().map(control_index).collect::<Result<_>>()?,
+                None => default_control(index).into_iter().collect(),
+            };
+
+            let mut script = temp.script.clone();
+            if let Some(script) = &mut script {
+                script.load(dir)?;
+            }
+
+            simulation.zones.push(ThermalZone {
+                controls,
+                script,
+                ..Default::default()
+            });
+        }
+
+        let mut sensors = Vec::new();
+        for (kind, sensor) in inventory_sensors {
+            let mut script = sensor.script;
+            if let Some(script) = &mut script {
+                script.load(dir)?;
+            }
+
+            simulation.sensors.push(SimSensor {
+                kind,
+                script,
+                ..Default::default()
+            });
+            sensors.push((sensor.name, sensor.hardware_id));
+        }
+
+        let controls = inventory
+            .controls
+            .into_iter()
+            .map(|control| (control.name, control.hardware_id))
+            .collect();
+        let fans = inventory
+            .fans
+            .into_iter()
+            .map(|fan| (fan.name, fan.hardware_id))
+            .collect();
+        let temps = inventory
+            .temps
+            .into_iter()
+            .map(|temp| (temp.name, temp.hardware_id))
+            .collect();
+
+        Ok(Self::from_parts(simulation, controls, fans, temps, sensors))
+    }
+
+    /// `controls`, `fans`, `temps` and `sensors` are `(name, hardware_id)`,
+    /// in the same order as in the simulation.
+    fn from_parts(
+        simulation: Simulation,
+        controls: Vec<(String, String)>,
+        fans: Vec<(String, String)>,
+        temps: Vec<(String, String)>,
+        sensors: Vec<(String, String)>,
+    ) -> Self {
+        let mut hardware = Hardware::default();
+
+        // internal index: controls, then fans, then temps, then the other sensors
+        for (index, (name, hardware_id)) in controls.into_iter().enumerate() {
+            hardware.controls.push(Rc::new(HControl {
+                name,
+                hardware_id,
+                info: String::new(),
+                internal_index: index,
+            }));
+        }
+
+        let nb_fans = fans.len();
+
+        for (index, (name, hardware_id)) in fans.into_iter().enumerate() {
+            hardware.fans.push(Rc::new(HSensor {
+                name,
+                hardware_id,
+                info: String::new(),
+                internal_index: index,
+            }));
+        }
+
+        let nb_temps = temps.len();
+
+        for (index, (name, hardware_id)) in temps.into_iter().enumerate() {
+            hardware.temps.push(Rc::new(HSensor {
+                name,
+                hardware_id,
+                info: String::new(),
+                internal_index: nb_fans + index,
+            }));
+        }
+
+        for (index, (sim_sensor, (name, hardware_id))) in
+            simulation.sensors.iter().zip(sensors).enumerate()
+        {
+            hardware.sensors_mut(sim_sensor.kind).push(Rc::new(HSensor {
+                name,
+                hardware_id,
+                info: String::new(),
+                internal_index: nb_fans + nb_temps + index,
+            }));
+        }
+
+        Self {
+            hardware,
+            simulation,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl HardwareBridge for FakeHardwareBridge {
+    fn new() -> crate::Result<Self> {
+        match std::env::var_os(FAKE_HARDWARE_ENV) {
+            Some(path) => {
+                info!("load fake hardware from {}", path.display());
+                Ok(Self::from_file(Path::new(&path))?)
+            }
+            None => Ok(Self::with_simulation(Simulation::default())),
+        }
     }
+
     fn hardware(&self) -> &Hardware {
         &self.hardware
     }
 
-    fn get_sensor_value(&mut self, _sensor: &HSensor) -> crate::Result<Value> {
-        let nb = rand::rng().random_range(30..80);
-        Ok(nb)
+    fn take_hardware(&mut self) -> Hardware {
+        std::mem::take(&mut self.hardware)
+    }
+
+    fn get_sensor_value(&mut self, sensor: &HSensor) -> crate::Result<Value> {
+        let nb_fans = self.simulation.fans.len();
+        let nb_temps = self.simulation.zones.len();
+
+        let value = if sensor.internal_index < nb_fans {
+            self.simulation.fans[sensor.internal_index].rpm
+        } else if sensor.internal_index < nb_fans + nb_temps {
+            self.simulation.zones[sensor.internal_index - nb_fans].temp
+        } else {
+            self.simulation.sensors[sensor.internal_index - nb_fans - nb_temps].value
+        };
+
+        Ok(value)
     }
 
-    fn get_control_value(&mut self, _control: &HControl) -> crate::Result<Value> {
-        let nb = rand::rng().random_range(30..80);
-        Ok(nb)
+    fn get_control_value(&mut self, control: &HControl) -> crate::Result<Value> {
+        let duty = self.simulation.controls[control.internal_index].duty();
+        Ok(duty)
     }
 
-    fn set_value(&mut self, _control: &HControl, value: Value) -> crate::Result<()> {
+    fn set_value(&mut self, control: &HControl, value: Value) -> crate::Result<()> {
         debug!("set value {}", value);
+        self.simulation.controls[control.internal_index].manual_duty = value.clamp(0.0, 100.0);
         Ok(())
     }
 
-    fn set_mode(&mut self, _control: &HControl, mode: &Mode) -> crate::Result<()> {
+    fn set_mode(&mut self, control: &HControl, mode: &Mode) -> crate::Result<()> {
         debug!("set mode {}", mode);
+        self.simulation.controls[control.internal_index].manual = mode == &Mode::Manual;
         Ok(())
     }
+
+    fn get_mode(&mut self, control: &HControl) -> crate::Result<Option<Mode>> {
+        let mode = if self.simulation.controls[control.internal_index].manual {
+            Mode::Manual
+        } else {
+            Mode::Auto
+        };
+        Ok(Some(mode))
+    }
+
+    fn set_on_exit(&mut self, control: &HControl, on_exit: OnExit) -> crate::Result<()> {
+        self.simulation.controls[control.internal_index].on_exit = on_exit;
+        Ok(())
+    }
+
+    fn now(&self) -> Instant {
+        self.start + self.simulation.elapsed()
+    }
+
+    fn update(&mut self) -> crate::Result<()> {
+        self.simulation.step();
+        Ok(())
+    }
+
+    fn shutdown(&mut self) -> crate::Result<()> {
+        for control in &mut self.simulation.controls {
+            match control.on_exit {
+                OnExit::Auto => control.manual = false,
+                OnExit::Keep => {}
+                OnExit::Fixed(value) => {
+                    control.manual = true;
+                    control.manual_duty = value.clamp(0.0, 100.0);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, time::Duration};
+
+    use crate::{HItem, HardwareBridge, Mode, SensorKind};
+
+    use super::{FakeHardwareBridge, Script};
+
+    #[test]
+    fn test_simulation() {
+        let mut bridge = FakeHardwareBridge::new().unwrap();
+        let temp1 = bridge.hardware().temps[0].clone();
+        let fan1 = bridge.hardware().fans[0].clone();
+        let control1 = bridge.hardware().controls[0].clone();
+
+        bridge.set_mode(&control1, &Mode::Manual).unwrap();
+
+        // no cooling: 25 + 60 / 1
+        bridge.set_value(&control1, 0.0).unwrap();
+        for _ in 0..500 {
+            bridge.update().unwrap();
+        }
+        assert_eq!(bridge.get_sensor_value(&temp1).unwrap().round(), 85.0);
+        assert_eq!(bridge.get_sensor_value(&fan1).unwrap(), 0.0);
+
+        // full cooling: 25 + 60 / (1 + 2)
+        bridge.set_value(&control1, 100.0).unwrap();
+        for _ in 0..500 {
+            bridge.update().unwrap();
+        }
+        assert_eq!(bridge.get_sensor_value(&temp1).unwrap().round(), 45.0);
+        assert_eq!(bridge.get_sensor_value(&fan1).unwrap().round(), 2000.0);
+        assert_eq!(bridge.get_control_value(&control1).unwrap(), 100.0);
+    }
+
+    #[test]
+    fn test_inventory() {
+        let dir = std::env::temp_dir().join("fan-control-test-fake-hardware");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join("series.csv"), "step,value\n0,30\n1,35\n2,40\n").unwrap();
+
+        let inventory = r#"
+            [[Control]]
+            name = "CPU_FAN"
+            id = "pwm1"
+
+            [[Control]]
+            name = "SYS_FAN"
+            id = "pwm2"
+
+            [[Fan]]
+            name = "SYS_FAN"
+            id = "fan2"
+            control = "pwm2"
+
+            [[Temp]]
+            name = "CPU"
+            id = "cpu"
+            script = { type = "Ramp", from = 30, to = 90, duration = 60 }
+
+            [[Temp]]
+            name = "GPU"
+            id = "gpu"
+            script = { type = "Csv", path = "series.csv" }
+
+            [[Temp]]
+            name = "Board"
+            id = "board"
+            controls = ["pwm1", "pwm2"]
+        "#;
+        let path = dir.join("hardware.toml");
+        fs::write(&path, inventory).unwrap();
+
+        let mut bridge = FakeHardwareBridge::from_file(&path).unwrap();
+
+        let hardware = bridge.hardware();
+        assert_eq!(hardware.controls.len(), 2);
+        assert_eq!(hardware.fans.len(), 1);
+        assert_eq!(hardware.temps.len(), 3);
+        assert_eq!(hardware.controls[1].id(), "pwm2");
+        assert_eq!(hardware.temps[2].name(), "Board");
+
+        let cpu = hardware.temps[0].clone();
+        let gpu = hardware.temps[1].clone();
+        assert_eq!(bridge.simulation.fans[0].control, 1);
+        assert_eq!(bridge.simulation.zones[2].controls, vec![0, 1]);
+
+        for _ in 0..30 {
+            bridge.update().unwrap();
+        }
+        assert_eq!(bridge.get_sensor_value(&cpu).unwrap().round(), 60.0);
+        // the series loop: 30 % 3
+        assert_eq!(bridge.get_sensor_value(&gpu).unwrap(), 30.0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// A hardware file written by `--serialize-hardware` can be loaded back.
+    #[test]
+    fn test_serialized_hardware() {
+        let dir = std::env::temp_dir().join("fan-control-test-fake-hardware-serialized");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let inventory = r#"
+            [[Control]]
+            name = "CPU_FAN"
+            id = "pwm1"
+
+            [[Fan]]
+            name = "CPU_FAN"
+            id = "fan1"
+
+            [[Temp]]
+            name = "CPU"
+            id = "cpu"
+
+            [[Power]]
+            name = "PPT"
+            id = "power1"
+            script = { type = "Constant", value = 65 }
+
+            [[Voltage]]
+            name = "Vcore"
+            id = "in0"
+
+            [[Current]]
+            name = "Icore"
+            id = "curr1"
+
+            [[FanTarget]]
+            name = "CPU_FAN target"
+            id = "fan1_target"
+        "#;
+        let path = dir.join("inventory.toml");
+        fs::write(&path, inventory).unwrap();
+        let mut bridge = FakeHardwareBridge::from_file(&path).unwrap();
+
+        let power = bridge.hardware().powers[0].clone();
+        bridge.update().unwrap();
+        assert_eq!(bridge.get_sensor_value(&power).unwrap(), 65.0);
+
+        let path = dir.join("hardware.toml");
+        fs::write(&path, toml::to_string_pretty(bridge.hardware()).unwrap()).unwrap();
+        let loaded = FakeHardwareBridge::from_file(&path).unwrap();
+
+        let ids = |bridge: &FakeHardwareBridge| {
+            let hardware = bridge.hardware();
+            let mut ids = vec![
+                hardware
+                    .controls
+                    .iter()
+                    .map(|h| h.id().clone())
+                    .collect::<Vec<_>>(),
+                hardware.fans.iter().map(|h| h.id().clone()).collect(),
+                hardware.temps.iter().map(|h| h.id().clone()).collect(),
+            ];
+            for kind in SensorKind::VALUES {
+                ids.push(
+                    hardware
+                        .sensors(kind)
+                        .iter()
+                        .map(|h| h.id().clone())
+                        .collect(),
+                );
+            }
+            ids
+        };
+        assert_eq!(ids(&loaded), ids(&bridge));
+        assert_eq!(loaded.hardware().fan_targets[0].name(), "CPU_FAN target");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_script() {
+        let step = Duration::from_secs(1);
+
+        let sine = Script::Sine {
+            min: 40.0,
+            max: 60.0,
+            period: 20.0,
+        };
+        assert_eq!(sine.value(0, step), 50.0);
+        assert_eq!(sine.value(5, step).round(), 60.0);
+        assert_eq!(sine.value(15, step).round(), 40.0);
+
+        let ramp = Script::Ramp {
+            from: 80.0,
+            to: 40.0,
+            duration: 10.0,
+        };
+        assert_eq!(ramp.value(5, step), 60.0);
+        assert_eq!(ramp.value(100, step), 40.0);
+    }
 }
@@ -0,0 +1,348 @@
+//! Temps read from a file containing a number.
+//!
+//! Used for thermal zones (`/sys/class/thermal/thermal_zone*/temp`),
+//! which are often the only temps available on laptops and ARM boards,
+//! and for any file declared in the config.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{HControl, HSensor, Hardware, HardwareBridge, Mode, OnExit, Value};
+
+#[derive(Error, Debug)]
+pub enum FileSensorError {
+    #[error("{0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("Invalid data in {0}: {1}")]
+    InvalidData(String, String),
+}
+
+type Result<T> = std::result::Result<T, FileSensorError>;
+
+/// The value of the sensor is `number * scale + offset`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FileSensor {
+    pub name: String,
+    pub path: PathBuf,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub offset: f64,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+impl FileSensor {
+    pub fn hardware_id(&self) -> String {
+        format!("file-{}", self.path.display())
+    }
+
+    pub fn read(&self) -> Result<Value> {
+        let path_str = self.path.display().to_string();
+
+        let content =
+            fs::read_to_string(&self.path).map_err(|e| FileSensorError::Io(path_str.clone(), e))?;
+
+        let number: f64 = content
+            .trim()
+            .parse()
+            .map_err(|e: std::num::ParseFloatError| {
+                FileSensorError::InvalidData(path_str, e.to_string())
+            })?;
+
+        Ok(number * self.scale + self.offset)
+    }
+}
+
+/// Thermal zones of the system, in millidegree Celsius.
+/// The zones already exposed by hwmon, like `acpitz`, are skipped.
+pub fn thermal_zones() -> Vec<FileSensor> {
+    if cfg!(target_os = "linux") {
+        thermal_zones_from(
+            Path::new("/sys/class/thermal"),
+            Path::new("/sys/class/hwmon"),
+        )
+    } else {
+        Vec::new()
+    }
+}
+
+pub fn thermal_zones_from(root: &Path, hwmon_root: &Path) -> Vec<FileSensor> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+
+    // the kernel registers a hwmon chip named after the type of the zone,
+    // with '-' replaced by '_'
+    let hwmon_names = fs::read_dir(hwmon_root)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| fs::read_to_string(entry.path().join("name")).ok())
+                .map(|name| name.trim().to_owned())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let mut zones = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("thermal_zone")
+        })
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let path = dir.join("temp");
+
+            if !path.exists() {
+                return None;
+            }
+
+            let zone_name = entry.file_name().to_string_lossy().to_string();
+            let name = match fs::read_to_string(dir.join("type")) {
+                Ok(kind) => {
+                    let kind = kind.trim();
+                    if hwmon_names.contains(&kind.replace('-', "_")) {
+                        debug!("thermal zone {} is exposed by hwmon", zone_name);
+                        return None;
+                    }
+                    format!("{} ({})", kind, zone_name)
+                }
+                Err(_) => zone_name,
+            };
+
+            Some(FileSensor {
+                name,
+                path,
+                scale: 0.001,
+                offset: 0.0,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    zones.sort_by(|a, b| a.path.cmp(&b.path));
+    zones
+}
+
+/// Add file sensors to the temps of another bridge.
+pub struct FileSensorBridge<H: HardwareBridge> {
+    inner: H,
+    hardware: Hardware,
+    sensors: Vec<(Rc<HSensor>, FileSensor)>,
+}
+
+impl<H: HardwareBridge> FileSensorBridge<H> {
+    pub fn with_sensors(mut inner: H, file_sensors: Vec<FileSensor>) -> Self {
+        let mut hardware = inner.take_hardware();
+
+        let mut sensors = Vec::new();
+        let first_index = hardware.next_sensor_index();
+
+        for (index, file_sensor) in file_sensors.into_iter().enumerate() {
+            let hardware_id = file_sensor.hardware_id();
+
+            if hardware
+                .temps
+                .iter()
+                .any(|temp| temp.hardware_id == hardware_id)
+            {
+                warn!("file sensor {} is declared twice", hardware_id);
+                continue;
+            }
+
+            if let Err(e) = file_sensor.read() {
+                warn!("can't read file sensor {}: {}", file_sensor.name, e);
+            }
+
+            let sensor = Rc::new(HSensor {
+                name: file_sensor.name.clone(),
+                hardware_id,
+                info: file_sensor.path.display().to_string(),
+                internal_index: first_index + index,
+            });
+
+            hardware.temps.push(sensor.clone());
+            sensors.push((sensor, file_sensor));
+        }
+
+        Self {
+            inner,
+            hardware,
+            sensors,
+        }
+    }
+
+    fn file_sensor(&self, sensor: &HSensor) -> Option<&FileSensor> {
+        self.sensors
+            .iter()
+            .find(|(h_sensor, _)| std::ptr::eq(h_sensor.as_ref(), sensor))
+            .map(|(_, file_sensor)| file_sensor)
+    }
+}
+
+impl<H: HardwareBridge> HardwareBridge for FileSensorBridge<H> {
+    /// Only add the thermal zones.
+    fn new() -> crate::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self::with_sensors(H::new()?, thermal_zones()))
+    }
+
+    fn hardware(&self) -> &Hardware {
+        &self.hardware
+    }
+
+    fn take_hardware(&mut self) -> Hardware {
+        std::mem::take(&mut self.hardware)
+    }
+
+    fn get_sensor_value(&mut self, sensor: &HSensor) -> crate::Result<Value> {
+        match self.file_sensor(sensor) {
+            Some(file_sensor) => Ok(file_sensor.read()?),
+            None => self.inner.get_sensor_value(sensor),
+        }
+    }
+
+    fn get_control_value(&mut self, control: &HControl) -> crate::Result<Value> {
+        self.inner.get_control_value(control)
+    }
+
+    fn set_value(&mut self, control: &HControl, value: Value) -> crate::Result<()> {
+        self.inner.set_value(control, value)
+    }
+
+    fn set_mode(&mut self, control: &HControl, mode: &Mode) -> crate::Result<()> {
+        self.inner.set_mode(control, mode)
+    }
+
+    fn get_mode(&mut self, control: &HControl) -> crate::Result<Option<Mode>> {
+        self.inner.get_mode(control)
+    }
+
+    fn set_on_exit(&mut self, control: &HControl, on_exit: OnExit) -> crate::Result<()> {
+        self.inner.set_on_exit(control, on_exit)
+    }
+
+    fn now(&self) -> Instant {
+        self.inner.now()
+    }
+
+    fn update(&mut self) -> crate::Result<()> {
+        self.inner.update()
+    }
+
+    fn shutdown(&mut self) -> crate::Result<()> {
+        self.inner.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, rc::Rc};
+
+    use crate::{HItem, HSensor, Hardware, HardwareBridge, Mode, Value};
+
+    use super::{FileSensor, FileSensorBridge, thermal_zones_from};
+
+    struct EmptyBridge(Hardware);
+
+    impl HardwareBridge for EmptyBridge {
+        fn new() -> crate::Result<Self> {
+            Ok(Self(Hardware::default()))
+        }
+
+        fn hardware(&self) -> &Hardware {
+            &self.0
+        }
+
+        fn take_hardware(&mut self) -> Hardware {
+            std::mem::take(&mut self.0)
+        }
+
+        fn get_sensor_value(&mut self, _sensor: &crate::HSensor) -> crate::Result<Value> {
+            unreachable!()
+        }
+
+        fn get_control_value(&mut self, _control: &crate::HControl) -> crate::Result<Value> {
+            unreachable!()
+        }
+
+        fn set_value(&mut self, _control: &crate::HControl, _value: Value) -> crate::Result<()> {
+            unreachable!()
+        }
+
+        fn set_mode(&mut self, _control: &crate::HControl, _mode: &Mode) -> crate::Result<()> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_file_sensors() {
+        let root = std::env::temp_dir().join("fan-control-test-file-sensor");
+        let _ = fs::remove_dir_all(&root);
+
+        let zone = root.join("thermal_zone0");
+        fs::create_dir_all(&zone).unwrap();
+        fs::write(zone.join("type"), "cpu-thermal\n").unwrap();
+        fs::write(zone.join("temp"), "48312\n").unwrap();
+        // listed by hwmon, with the other chips
+        let acpitz = root.join("thermal_zone1");
+        fs::create_dir_all(&acpitz).unwrap();
+        fs::write(acpitz.join("type"), "acpitz\n").unwrap();
+        fs::write(acpitz.join("temp"), "27800\n").unwrap();
+        let hwmon = root.join("hwmon");
+        fs::create_dir_all(hwmon.join("hwmon0")).unwrap();
+        fs::write(hwmon.join("hwmon0/name"), "acpitz\n").unwrap();
+        // cooling devices are in the same directory
+        fs::create_dir_all(root.join("cooling_device0")).unwrap();
+
+        let custom = root.join("custom");
+        fs::write(&custom, "  21.5 \n").unwrap();
+
+        let mut sensors = thermal_zones_from(&root, &hwmon);
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].name, "cpu-thermal (thermal_zone0)");
+
+        sensors.push(FileSensor {
+            name: "custom".into(),
+            path: custom.clone(),
+            scale: 2.0,
+            offset: -3.0,
+        });
+
+        let mut hardware = Hardware::default();
+        hardware.fans.push(Rc::new(HSensor {
+            name: "fan1".into(),
+            hardware_id: "fan1".into(),
+            info: String::new(),
+            internal_index: 0,
+        }));
+        let mut bridge = FileSensorBridge::with_sensors(EmptyBridge(hardware), sensors);
+
+        let temps = bridge.hardware().temps.clone();
+        assert_eq!(temps.len(), 2);
+        // sensors are compared by internal index
+        assert_ne!(temps[0], bridge.hardware().fans[0]);
+        assert_eq!(temps[1].id(), &format!("file-{}", custom.display()));
+
+        assert_eq!(bridge.get_sensor_value(&temps[0]).unwrap(), 48.312);
+        assert_eq!(bridge.get_sensor_value(&temps[1]).unwrap(), 40.0);
+
+        fs::write(&custom, "not a number").unwrap();
+        assert!(bridge.get_sensor_value(&temps[1]).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,844 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    num::ParseIntError,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+    str::FromStr,
+    sync::Mutex,
+};
+
+use thiserror::Error;
+
+use crate::{HControl, HSensor, Hardware, HardwareBridge, Mode, OnExit, SensorKind, Value};
+
+// https://www.kernel.org/doc/Documentation/hwmon/sysfs-interface
+// https://github.com/lm-sensors/lm-sensors/blob/master/lib/sysfs.c
+
+static DEFAULT_SYSFS_ROOT: &str = "/sys/class/hwmon";
+static DEFAULT_PWM_ENABLE: i32 = 5;
+static MANUAL_MODE: i32 = 1;
+
+/// Read hwmon devices directly from sysfs, without libsensors.
+pub struct HwmonBridge {
+    sensors: Vec<InternalSubFeature>,
+    hardware: Hardware,
+}
+
+#[derive(Error, Debug)]
+pub enum HwmonError {
+    #[error("{0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("Invalid data in {0}: {1}")]
+    InvalidData(String, String),
+}
+
+type Result<T> = std::result::Result<T, HwmonError>;
+
+#[derive(Debug, Clone)]
+pub(crate) struct PwmFiles {
+    pub io: PathBuf,
+    pub enable: PathBuf,
+    pub default_enable_cached: i32,
+    pub on_exit: OnExit,
+}
+
+impl PwmFiles {
+    /// Apply the exit policy of the control.
+    fn exit(&self) -> Result<()> {
+        match self.on_exit {
+            OnExit::Auto => write_value(&self.enable, self.default_enable_cached),
+            OnExit::Keep => Ok(()),
+            OnExit::Fixed(value) => write_value(&self.enable, MANUAL_MODE)
+                .and_then(|_| write_value(&self.io, to_pwm(value))),
+        }
+    }
+}
+
+/// Controls of the bridges not dropped yet, by pwm file.
+/// They are restored from here when the bridge can't be dropped:
+/// on a signal, or after a panic with `panic = "abort"`.
+static PENDING_EXITS: Mutex<BTreeMap<PathBuf, PwmFiles>> = Mutex::new(BTreeMap::new());
+
+fn pending_exits() -> std::sync::MutexGuard<'static, BTreeMap<PathBuf, PwmFiles>> {
+    // the restore must work, even after a panic
+    PENDING_EXITS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub(crate) fn register_exit(pwm_files: &PwmFiles) {
+    pending_exits().insert(pwm_files.io.clone(), pwm_files.clone());
+}
+
+pub(crate) fn forget_exit(io: &Path) {
+    pending_exits().remove(io);
+}
+
+/// Apply the exit policy of all the controls of the bridges which are still alive,
+/// without the bridges, which are owned by the main thread.
+/// Same as dropping the bridges.
+pub fn restore_pending_exits() {
+    let pending = std::mem::take(&mut *pending_exits());
+
+    for pwm_files in pending.values() {
+        if let Err(e) = pwm_files.exit() {
+            error!("can't apply the exit policy of a pwm sensor: {}", e)
+        }
+    }
+}
+
+pub(crate) struct SensorFile {
+    pub(crate) io: PathBuf,
+    /// sysfs unit / user unit (millidegree -> degree for temps, microwatt -> watt for powers)
+    pub(crate) divisor: f64,
+}
+
+impl SensorFile {
+    pub(crate) fn read(&self) -> Result<Value> {
+        let value = read_value::<i64>(&self.io)?;
+        Ok(value as Value / self.divisor)
+    }
+}
+
+enum InternalSubFeature {
+    Pwm(PwmFiles),
+    Sensor(SensorFile),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FeatureKind {
+    Fan,
+    Temperature,
+    Pwm,
+    Power,
+    /// Some drivers, like amdgpu, only expose an average power
+    PowerAverage,
+    Voltage,
+    Current,
+    FanTarget,
+}
+
+impl FeatureKind {
+    fn prefix(&self) -> &'static str {
+        match self {
+            FeatureKind::Fan | FeatureKind::FanTarget => "fan",
+            FeatureKind::Temperature => "temp",
+            FeatureKind::Pwm => "pwm",
+            FeatureKind::Power | FeatureKind::PowerAverage => "power",
+            FeatureKind::Voltage => "in",
+            FeatureKind::Current => "curr",
+        }
+    }
+
+    fn suffix(&self) -> &'static str {
+        match self {
+            FeatureKind::Pwm => "",
+            FeatureKind::PowerAverage => "_average",
+            FeatureKind::FanTarget => "_target",
+            _ => "_input",
+        }
+    }
+
+    /// Name of the sub feature used for io, like libsensors does.
+    fn sub_feature_name(&self, number: u32) -> String {
+        format!("{}{}{}", self.prefix(), number, self.suffix())
+    }
+}
+
+/// A chip, as libsensors sees it: a hwmon directory with a `name` file.
+struct Chip {
+    path: PathBuf,
+    /// Same format as `sensors_snprintf_chip_name`
+    name: String,
+    bus: String,
+    /// See [`ChipIds`]
+    id: String,
+}
+
+fn read_string(path: &Path) -> Result<String> {
+    fs::read_to_string(path)
+        .map(|s| s.trim().to_owned())
+        .map_err(|e| HwmonError::Io(format!("can't read {}", path.display()), e))
+}
+
+/// sysfs only contains integers
+fn read_value<T: FromStr<Err = ParseIntError>>(path: &Path) -> Result<T> {
+    let str = read_string(path)?;
+    str.parse::<T>()
+        .map_err(|e| HwmonError::InvalidData(path.display().to_string(), e.to_string()))
+}
+
+fn write_value(path: &Path, value: impl Into<i64>) -> Result<()> {
+    let value = value.into();
+    fs::write(path, value.to_string())
+        .map_err(|e| HwmonError::Io(format!("can't write {} to {}", value, path.display()), e))
+}
+
+/// % -> from 0 to 255
+fn to_pwm(percent: Value) -> i64 {
+    (percent * 2.55).round() as i64
+}
+
+/// Parse `{prefix}{number}{suffix}`, for instance `fan2_input`.
+fn parse_feature_number(file_name: &str, prefix: &str, suffix: &str) -> Option<u32> {
+    file_name
+        .strip_prefix(prefix)?
+        .strip_suffix(suffix)?
+        .parse::<u32>()
+        .ok()
+}
+
+fn subsystem_of(device_path: &Path) -> Option<String> {
+    fs::read_link(device_path.join("subsystem"))
+        .ok()?
+        .file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+}
+
+fn device_name_of(device_path: &Path) -> Option<String> {
+    fs::canonicalize(device_path)
+        .or_else(|_| fs::read_link(device_path))
+        .ok()?
+        .file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+}
+
+/// Reproduce the chip name of libsensors (`prefix-bus-address`),
+/// so ids stay compatible with configs made with the libsensors backend.
+fn chip_name(prefix: &str, hwmon_path: &Path) -> (String, String) {
+    let device_path = hwmon_path.join("device");
+
+    let Some(subsystem) = subsystem_of(&device_path) else {
+        return (format!("{}-virtual-0", prefix), "virtual".into());
+    };
+    let dev_name = device_name_of(&device_path).unwrap_or_default();
+
+    match subsystem.as_str() {
+        "i2c" => {
+            // "0-002d"
+            let mut split = dev_name.splitn(2, '-');
+            let bus = split.next().and_then(|b| b.parse::<i16>().ok());
+            let addr = split.next().and_then(|a| u32::from_str_radix(a, 16).ok());
+            match (bus, addr) {
+                (Some(bus), Some(addr)) => {
+                    (format!("{}-i2c-{}-{:02x}", prefix, bus, addr), "i2c".into())
+                }
+                _ => (format!("{}-virtual-0", prefix), "virtual".into()),
+            }
+        }
+        "pci" => {
+            // "0000:03:00.0"
+            let addr = (|| {
+                let (domain, rest) = dev_name.split_once(':')?;
+                let (bus, rest) = rest.split_once(':')?;
+                let (slot, func) = rest.split_once('.')?;
+                let domain = u32::from_str_radix(domain, 16).ok()?;
+                let bus = u32::from_str_radix(bus, 16).ok()?;
+                let slot = u32::from_str_radix(slot, 16).ok()?;
+                let func = u32::from_str_radix(func, 16).ok()?;
+                Some((domain << 16) + (bus << 8) + (slot << 3) + func)
+            })()
+            .unwrap_or(0);
+            (format!("{}-pci-{:04x}", prefix, addr), "pci".into())
+        }
+        "platform" | "of_platform" => {
+            // "nct6775.656"
+            let addr = dev_name
+                .rsplit_once('.')
+                .and_then(|(_, addr)| addr.parse::<u32>().ok())
+                .unwrap_or(0);
+            (format!("{}-isa-{:04x}", prefix, addr), "isa".into())
+        }
+        "acpi" => (format!("{}-acpi-0", prefix), "acpi".into()),
+        _ => (format!("{}-virtual-0", prefix), "virtual".into()),
+    }
+}
+
+/// Path of the device of a hwmon directory, relative to `/sys/devices`.
+/// Unlike `hwmonN`, it doesn't depend on the probing order of the drivers.
+fn device_path_of(hwmon_path: &Path) -> Option<String> {
+    let device_path = hwmon_path.join("device");
+
+    let path = if device_path.exists() {
+        fs::canonicalize(device_path).ok()?
+    } else if hwmon_path.join("name").exists() {
+        // virtual chip, the only path we have is hwmonN
+        return None;
+    } else {
+        // old drivers: this is already the device directory
+        fs::canonicalize(hwmon_path).ok()?
+    };
+
+    let components = path.components().collect::<Vec<_>>();
+    let devices_index = components
+        .iter()
+        .rposition(|c| c == &Component::Normal("devices".as_ref()))?;
+
+    let relative = components[devices_index + 1..]
+        .iter()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    Some(relative)
+}
+
+/// Generate chip ids which stay the same between reboots, and distinguish
+/// identical chips: `chip_name@device_path`, with a `#n` suffix when
+/// this is still not enough (chips without device).
+#[derive(Default)]
+pub(crate) struct ChipIds {
+    seen: HashMap<String, u32>,
+}
+
+impl ChipIds {
+    pub(crate) fn chip_id(&mut self, chip_name: &str, hwmon_path: &Path) -> String {
+        let device = device_path_of(hwmon_path).unwrap_or_else(|| "virtual".into());
+        let id = format!("{}@{}", chip_name, device);
+
+        let count = self.seen.entry(id.clone()).or_default();
+        *count += 1;
+
+        if *count > 1 {
+            format!("{}#{}", id, *count - 1)
+        } else {
+            id
+        }
+    }
+}
+
+pub(crate) fn hardware_id(chip_id: &str, sub_feature_name: &str) -> String {
+    format!("{}/{}", chip_id, sub_feature_name)
+}
+
+/// Ids used before [`ChipIds`], they collide for identical chips.
+pub(crate) fn legacy_hardware_id(label: &str, chip_name: &str, sub_feature_name: &str) -> String {
+    format!("{}-{}-{}", label, chip_name, sub_feature_name)
+}
+
+fn find_chips(root: &Path) -> Result<Vec<Chip>> {
+    let entries = fs::read_dir(root)
+        .map_err(|e| HwmonError::Io(format!("can't read {}", root.display()), e))?;
+
+    let mut hwmon_paths = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect::<Vec<_>>();
+
+    // hwmon0, hwmon1, ..., hwmon10
+    hwmon_paths.sort_by_key(|path| {
+        path.file_name()
+            .and_then(|name| parse_feature_number(&name.to_string_lossy(), "hwmon", ""))
+            .unwrap_or(u32::MAX)
+    });
+
+    let mut chips = Vec::new();
+    let mut chip_ids = ChipIds::default();
+
+    for hwmon_path in hwmon_paths {
+        // old drivers put their attributes in the device directory
+        let attr_path = if hwmon_path.join("name").exists() {
+            hwmon_path.clone()
+        } else {
+            hwmon_path.join("device")
+        };
+
+        let prefix = match read_string(&attr_path.join("name")) {
+            Ok(prefix) => prefix,
+            Err(e) => {
+                debug!("skip {}: {}", hwmon_path.display(), e);
+                continue;
+            }
+        };
+
+        let (name, bus) = chip_name(&prefix, &hwmon_path);
+        let id = chip_ids.chip_id(&name, &hwmon_path);
+
+        chips.push(Chip {
+            path: attr_path,
+            name,
+            bus,
+            id,
+        });
+    }
+
+    Ok(chips)
+}
+
+/// Return the sorted feature numbers of `kind` found in this chip.
+fn feature_numbers(chip: &Chip, kind: FeatureKind) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir(&chip.path) else {
+        return Vec::new();
+    };
+
+    let suffix = kind.suffix();
+
+    let mut numbers = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            parse_feature_number(&entry.file_name().to_string_lossy(), kind.prefix(), suffix)
+        })
+        .collect::<Vec<_>>();
+
+    numbers.sort();
+    numbers
+}
+
+struct HInfo {
+    name: String,
+    hardware_id: String,
+    legacy_id: String,
+    info: String,
+}
+
+fn get_infos(chip: &Chip, kind: FeatureKind, number: u32) -> HInfo {
+    let feature_name = format!("{}{}", kind.prefix(), number);
+
+    let label = read_string(&chip.path.join(format!("{}_label", feature_name)))
+        .ok()
+        .filter(|label| !label.is_empty())
+        .unwrap_or(feature_name);
+
+    let sub_feature_name = kind.sub_feature_name(number);
+
+    HInfo {
+        name: format!("{} {}", label, chip.name),
+        hardware_id: hardware_id(&chip.id, &sub_feature_name),
+        legacy_id: legacy_hardware_id(&label, &chip.name, &sub_feature_name),
+        info: format!(
+            "chip path: {}\nchip name: {}\nchip id: {}\nbus: {}\nlabel: {}\nfeature: {}",
+            chip.path.display(),
+            chip.name,
+            chip.id,
+            chip.bus,
+            label,
+            sub_feature_name
+        ),
+    }
+}
+
+fn generate_hardware(root: &Path, hardware: &mut Hardware) -> Result<Vec<InternalSubFeature>> {
+    let mut sensors = Vec::new();
+
+    for chip in find_chips(root)? {
+        let power_inputs = feature_numbers(&chip, FeatureKind::Power);
+
+        let sensor_kinds = [
+            (FeatureKind::Fan, 1.0),
+            (FeatureKind::Temperature, 1000.0),
+            (FeatureKind::Power, 1_000_000.0),
+            (FeatureKind::PowerAverage, 1_000_000.0),
+            (FeatureKind::Voltage, 1.0),
+            (FeatureKind::Current, 1.0),
+            (FeatureKind::FanTarget, 1.0),
+        ];
+
+        // h_sensors borrows hardware
+        let mut legacy_ids = Vec::new();
+
+        for (kind, divisor) in sensor_kinds {
+            let h_sensors = match kind {
+                FeatureKind::Fan => &mut hardware.fans,
+                FeatureKind::Temperature => &mut hardware.temps,
+                FeatureKind::Power | FeatureKind::PowerAverage => {
+                    hardware.sensors_mut(SensorKind::Power)
+                }
+                FeatureKind::Voltage => hardware.sensors_mut(SensorKind::Voltage),
+                FeatureKind::Current => hardware.sensors_mut(SensorKind::Current),
+                FeatureKind::FanTarget => hardware.sensors_mut(SensorKind::FanTarget),
+                FeatureKind::Pwm => unreachable!(),
+            };
+
+            for number in feature_numbers(&chip, kind) {
+                // prefer the instantaneous power
+                if kind == FeatureKind::PowerAverage && power_inputs.contains(&number) {
+                    continue;
+                }
+
+                let h_info = get_infos(&chip, kind, number);
+
+                legacy_ids.push((h_info.legacy_id, h_info.hardware_id.clone()));
+                h_sensors.push(Rc::new(HSensor {
+                    name: h_info.name,
+                    hardware_id: h_info.hardware_id,
+                    info: h_info.info,
+                    internal_index: sensors.len(),
+                }));
+                sensors.push(InternalSubFeature::Sensor(SensorFile {
+                    io: chip.path.join(kind.sub_feature_name(number)),
+                    divisor,
+                }));
+            }
+        }
+
+        for (legacy_id, id) in legacy_ids {
+            hardware.add_legacy_id(legacy_id, &id);
+        }
+
+        for number in feature_numbers(&chip, FeatureKind::Pwm) {
+            let io = chip.path.join(FeatureKind::Pwm.sub_feature_name(number));
+            let enable = chip.path.join(format!("pwm{}_enable", number));
+
+            if !enable.exists() {
+                continue;
+            }
+
+            let default_enable_cached = match read_value(&enable) {
+                Ok(value) => {
+                    if value == MANUAL_MODE {
+                        DEFAULT_PWM_ENABLE
+                    } else {
+                        value
+                    }
+                }
+                Err(e) => {
+                    error!("can't read value of pwm {}", e);
+                    continue;
+                }
+            };
+
+            let h_info = get_infos(&chip, FeatureKind::Pwm, number);
+
+            hardware.add_legacy_id(h_info.legacy_id, &h_info.hardware_id);
+            hardware.controls.push(Rc::new(HControl {
+                name: h_info.name,
+                hardware_id: h_info.hardware_id,
+                info: h_info.info,
+                internal_index: sensors.len(),
+            }));
+            let pwm_files = PwmFiles {
+                io,
+                enable,
+                default_enable_cached,
+                on_exit: OnExit::default(),
+            };
+            register_exit(&pwm_files);
+            sensors.push(InternalSubFeature::Pwm(pwm_files));
+        }
+    }
+
+    Ok(sensors)
+}
+
+impl HwmonBridge {
+    /// Construct a bridge from a custom sysfs root, instead of `/sys/class/hwmon`.
+    pub fn from_root(root: &Path) -> crate::Result<Self> {
+        let mut hardware = Hardware::default();
+        let sensors = generate_hardware(root, &mut hardware)?;
+
+        Ok(Self { sensors, hardware })
+    }
+}
+
+impl Drop for HwmonBridge {
+    fn drop(&mut self) {
+        for sensor in &self.sensors {
+            if let InternalSubFeature::Pwm(pwm_files) = sensor {
+                if let Err(e) = pwm_files.exit() {
+                    error!(
+                        "can't apply the exit policy of a pwm sensor when quitting: {}",
+                        e
+                    )
+                }
+                forget_exit(&pwm_files.io);
+            }
+        }
+    }
+}
+
+impl HardwareBridge for HwmonBridge {
+    fn new() -> crate::Result<Self> {
+        Self::from_root(Path::new(DEFAULT_SYSFS_ROOT))
+    }
+
+    fn hardware(&self) -> &Hardware {
+        &self.hardware
+    }
+
+    fn take_hardware(&mut self) -> Hardware {
+        std::mem::take(&mut self.hardware)
+    }
+
+    fn get_sensor_value(&mut self, sensor: &HSensor) -> crate::Result<Value> {
+        match self
+            .sensors
+            .get(sensor.internal_index)
+            .expect("no sensor found")
+        {
+            InternalSubFeature::Sensor(sensor_file) => Ok(sensor_file.read()?),
+            _ => unreachable!(),
+        }
+    }
+
+    fn get_control_value(&mut self, control: &HControl) -> crate::Result<Value> {
+        match self
+            .sensors
+            .get(control.internal_index)
+            .expect("no sensor found")
+        {
+            InternalSubFeature::Pwm(pwm_files) => {
+                let value = read_value::<i64>(&pwm_files.io)?;
+                Ok(value as Value / 2.55)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn set_value(&mut self, control: &HControl, value: Value) -> crate::Result<()> {
+        match self
+            .sensors
+            .get(control.internal_index)
+            .expect("no sensor found")
+        {
+            InternalSubFeature::Pwm(pwm_files) => {
+                write_value(&pwm_files.io, to_pwm(value))?;
+                Ok(())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn set_mode(&mut self, control: &HControl, mode: &Mode) -> crate::Result<()> {
+        match self
+            .sensors
+            .get(control.internal_index)
+            .expect("no sensor found")
+        {
+            InternalSubFeature::Pwm(pwm_files) => {
+                let value = match mode {
+                    Mode::Auto => pwm_files.default_enable_cached,
+                    Mode::Manual => MANUAL_MODE,
+                    Mode::Specific(value) => *value,
+                };
+                write_value(&pwm_files.enable, value)?;
+                Ok(())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn get_mode(&mut self, control: &HControl) -> crate::Result<Option<Mode>> {
+        match self
+            .sensors
+            .get(control.internal_index)
+            .expect("no sensor found")
+        {
+            InternalSubFeature::Pwm(pwm_files) => {
+                let mode = match read_value(&pwm_files.enable)? {
+                    value if value == MANUAL_MODE => Mode::Manual,
+                    value if value == pwm_files.default_enable_cached => Mode::Auto,
+                    value => Mode::Specific(value),
+                };
+                Ok(Some(mode))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn set_on_exit(&mut self, control: &HControl, on_exit: OnExit) -> crate::Result<()> {
+        match self
+            .sensors
+            .get_mut(control.internal_index)
+            .expect("no sensor found")
+        {
+            InternalSubFeature::Pwm(pwm_files) => {
+                pwm_files.on_exit = on_exit;
+                register_exit(pwm_files);
+                Ok(())
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        os::unix::fs::symlink,
+        path::{Path, PathBuf},
+    };
+
+    use crate::{HardwareBridge, Mode, OnExit, SensorKind};
+
+    use super::{HwmonBridge, pending_exits};
+
+    fn create_tree(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("fan-control-hwmon-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        let class = root.join("class/hwmon");
+        let devices = root.join("devices");
+        let bus = root.join("bus");
+
+        // hwmon0: nct6775 on the isa bus, with a label for temp1
+        let platform_dev = devices.join("platform/nct6775.656");
+        fs::create_dir_all(&platform_dev).unwrap();
+        fs::create_dir_all(bus.join("platform")).unwrap();
+        symlink(bus.join("platform"), platform_dev.join("subsystem")).unwrap();
+
+        let hwmon0 = class.join("hwmon0");
+        fs::create_dir_all(&hwmon0).unwrap();
+        symlink(&platform_dev, hwmon0.join("device")).unwrap();
+
+        write(&hwmon0, "name", "nct6775");
+        write(&hwmon0, "temp1_input", "45900");
+        write(&hwmon0, "temp1_label", "SYSTIN");
+        write(&hwmon0, "temp2_input", "30000");
+        write(&hwmon0, "fan1_input", "1200");
+        write(&hwmon0, "fan1_target", "1500");
+        write(&hwmon0, "in0_input", "1104");
+        write(&hwmon0, "in0_label", "Vcore");
+        write(&hwmon0, "curr1_input", "2500");
+        write(&hwmon0, "pwm1", "128");
+        write(&hwmon0, "pwm1_enable", "1");
+        // no enable file: not controllable
+        write(&hwmon0, "pwm2", "255");
+
+        // hwmon1: virtual chip without device link
+        let hwmon1 = class.join("hwmon1");
+        fs::create_dir_all(&hwmon1).unwrap();
+        write(&hwmon1, "name", "acpitz");
+        write(&hwmon1, "temp1_input", "27800");
+        // power1_input is preferred over power1_average
+        write(&hwmon1, "power1_input", "65400000");
+        write(&hwmon1, "power1_average", "60000000");
+        write(&hwmon1, "power2_average", "12000000");
+
+        // hwmon2: identical to hwmon1
+        let hwmon2 = class.join("hwmon2");
+        fs::create_dir_all(&hwmon2).unwrap();
+        write(&hwmon2, "name", "acpitz");
+        write(&hwmon2, "temp1_input", "35000");
+
+        class
+    }
+
+    fn write(dir: &Path, file: &str, content: &str) {
+        fs::write(dir.join(file), format!("{content}\n")).unwrap();
+    }
+
+    #[test]
+    fn test_enumeration() {
+        let root = create_tree("enumeration");
+        let bridge = HwmonBridge::from_root(&root).unwrap();
+        let hardware = bridge.hardware();
+
+        let temp_ids = hardware
+            .temps
+            .iter()
+            .map(|t| t.hardware_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            temp_ids,
+            vec![
+                "nct6775-isa-0290@platform/nct6775.656/temp1_input",
+                "nct6775-isa-0290@platform/nct6775.656/temp2_input",
+                "acpitz-virtual-0@virtual/temp1_input",
+                "acpitz-virtual-0@virtual#1/temp1_input",
+            ]
+        );
+
+        // configs made with the legacy ids used the first chip
+        assert_eq!(
+            hardware.migrate_id("temp1-acpitz-virtual-0-temp1_input"),
+            Some(&hardware.temps[2].hardware_id)
+        );
+        assert_eq!(
+            hardware.migrate_id("pwm1-nct6775-isa-0290-pwm1"),
+            Some(&hardware.controls[0].hardware_id)
+        );
+
+        assert_eq!(hardware.fans.len(), 1);
+        assert_eq!(
+            hardware.fans[0].hardware_id,
+            "nct6775-isa-0290@platform/nct6775.656/fan1_input"
+        );
+
+        assert_eq!(hardware.controls.len(), 1);
+        assert_eq!(
+            hardware.controls[0].hardware_id,
+            "nct6775-isa-0290@platform/nct6775.656/pwm1"
+        );
+
+        let ids = |kind| {
+            hardware
+                .sensors(kind)
+                .iter()
+                .map(|t| t.hardware_id.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(SensorKind::Power),
+            vec![
+                "acpitz-virtual-0@virtual/power1_input",
+                "acpitz-virtual-0@virtual/power2_average",
+            ]
+        );
+        assert_eq!(
+            ids(SensorKind::Voltage),
+            vec!["nct6775-isa-0290@platform/nct6775.656/in0_input"]
+        );
+        assert_eq!(
+            ids(SensorKind::Current),
+            vec!["nct6775-isa-0290@platform/nct6775.656/curr1_input"]
+        );
+        assert_eq!(
+            ids(SensorKind::FanTarget),
+            vec!["nct6775-isa-0290@platform/nct6775.656/fan1_target"]
+        );
+    }
+
+    #[test]
+    fn test_io() {
+        let root = create_tree("io");
+        let hwmon0 = root.join("hwmon0");
+
+        {
+            let mut bridge = HwmonBridge::from_root(&root).unwrap();
+            let temp = bridge.hardware().temps[0].clone();
+            let fan = bridge.hardware().fans[0].clone();
+            let control = bridge.hardware().controls[0].clone();
+
+            assert_eq!(bridge.get_sensor_value(&temp).unwrap(), 45.9);
+            assert_eq!(bridge.get_sensor_value(&fan).unwrap(), 1200.0);
+            assert_eq!(bridge.get_control_value(&control).unwrap().round(), 50.0);
+
+            let power = bridge.hardware().powers[0].clone();
+            let voltage = bridge.hardware().voltages[0].clone();
+            assert_eq!(bridge.get_sensor_value(&power).unwrap(), 65.4);
+            assert_eq!(bridge.get_sensor_value(&voltage).unwrap(), 1104.0);
+
+            bridge.set_mode(&control, &Mode::Manual).unwrap();
+            bridge.set_value(&control, 100.0).unwrap();
+            assert_eq!(fs::read_to_string(hwmon0.join("pwm1")).unwrap(), "255");
+            assert_eq!(fs::read_to_string(hwmon0.join("pwm1_enable")).unwrap(), "1");
+        }
+
+        // pwm was in manual mode at startup, so we fall back to the default mode
+        assert_eq!(fs::read_to_string(hwmon0.join("pwm1_enable")).unwrap(), "5");
+    }
+
+    #[test]
+    fn test_exit() {
+        let root = create_tree("exit");
+        let hwmon0 = root.join("hwmon0");
+
+        let mut bridge = HwmonBridge::from_root(&root).unwrap();
+        let control = bridge.hardware().controls[0].clone();
+
+        bridge.set_mode(&control, &Mode::Manual).unwrap();
+        bridge.set_value(&control, 100.0).unwrap();
+        bridge.set_on_exit(&control, OnExit::Fixed(40.0)).unwrap();
+
+        // like a panic with `panic = "abort"`
+        std::mem::forget(bridge);
+
+        // only restore the control of this test, the other tests run in parallel
+        let pwm_files = pending_exits().remove(&hwmon0.join("pwm1")).unwrap();
+        pwm_files.exit().unwrap();
+        assert_eq!(fs::read_to_string(hwmon0.join("pwm1")).unwrap(), "102");
+        assert_eq!(fs::read_to_string(hwmon0.join("pwm1_enable")).unwrap(), "1");
+    }
+}
@@ -1,28 +1,47 @@
 use derive_more::Display;
-use serde::Serialize;
-use std::{fmt::Debug, rc::Rc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Debug, rc::Rc, time::Instant};
 use thiserror::Error;
 
 #[macro_use]
 extern crate log;
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", feature = "libsensors"))]
 pub mod linux;
 
+#[cfg(target_os = "linux")]
+pub mod hwmon;
+
 #[cfg(target_os = "windows")]
 pub mod windows;
 
 #[cfg(feature = "fake_hardware")]
 pub mod fake_hardware;
 
+pub mod command_sensor;
+pub mod file_sensor;
+pub mod trace;
+
 #[derive(Error, Debug)]
 pub enum HardwareError {
-    #[cfg(target_os = "linux")]
+    #[cfg(all(target_os = "linux", feature = "libsensors"))]
     #[error(transparent)]
     Linux(#[from] linux::LinuxError),
+    #[cfg(target_os = "linux")]
+    #[error(transparent)]
+    Hwmon(#[from] hwmon::HwmonError),
     #[cfg(target_os = "windows")]
     #[error(transparent)]
     Windows(#[from] windows::WindowsError),
+    #[error(transparent)]
+    Trace(#[from] trace::TraceError),
+    #[error(transparent)]
+    FileSensor(#[from] file_sensor::FileSensorError),
+    #[error(transparent)]
+    CommandSensor(#[from] command_sensor::CommandSensorError),
+    #[cfg(feature = "fake_hardware")]
+    #[error(transparent)]
+    FakeHardware(#[from] fake_hardware::FakeHardwareError),
 }
 
 type Result<T> = std::result::Result<T, HardwareError>;
@@ -99,7 +118,7 @@ impl PartialEq for HSensor {
     }
 }
 
-#[derive(Serialize, Debug, Default)]
+#[derive(Serialize, Debug, Default, Clone)]
 pub struct Hardware {
     #[serde(default, rename = "Control")]
     pub controls: Vec<Rc<HControl>>,
@@ -107,15 +126,132 @@ pub struct Hardware {
     pub fans: Vec<Rc<HSensor>>,
     #[serde(default, rename = "Temp")]
     pub temps: Vec<Rc<HSensor>>,
+    #[serde(default, rename = "Power")]
+    pub powers: Vec<Rc<HSensor>>,
+    #[serde(default, rename = "Voltage")]
+    pub voltages: Vec<Rc<HSensor>>,
+    #[serde(default, rename = "Current")]
+    pub currents: Vec<Rc<HSensor>>,
+    #[serde(default, rename = "FanTarget")]
+    pub fan_targets: Vec<Rc<HSensor>>,
+    /// Ids generated by older versions, mapped to the current ones.
+    #[serde(skip)]
+    pub legacy_ids: HashMap<String, String>,
+}
+
+impl Hardware {
+    pub fn sensors(&self, kind: SensorKind) -> &Vec<Rc<HSensor>> {
+        match kind {
+            SensorKind::Power => &self.powers,
+            SensorKind::Voltage => &self.voltages,
+            SensorKind::Current => &self.currents,
+            SensorKind::FanTarget => &self.fan_targets,
+        }
+    }
+
+    pub fn sensors_mut(&mut self, kind: SensorKind) -> &mut Vec<Rc<HSensor>> {
+        match kind {
+            SensorKind::Power => &mut self.powers,
+            SensorKind::Voltage => &mut self.voltages,
+            SensorKind::Current => &mut self.currents,
+            SensorKind::FanTarget => &mut self.fan_targets,
+        }
+    }
+
+    /// When two items had the same legacy id, configs were resolved
+    /// to the first one, so keep it.
+    pub fn add_legacy_id(&mut self, legacy_id: String, id: &str) {
+        if legacy_id != id {
+            self.legacy_ids
+                .entry(legacy_id)
+                .or_insert_with(|| id.to_owned());
+        }
+    }
+
+    /// Current id of an item, from an id generated by an older version.
+    pub fn migrate_id(&self, id: &str) -> Option<&String> {
+        self.legacy_ids.get(id)
+    }
+
+    /// First internal index after all the sensors, for the bridges adding their own,
+    /// because sensors are compared by internal index.
+    pub(crate) fn next_sensor_index(&self) -> usize {
+        [
+            &self.fans,
+            &self.temps,
+            &self.powers,
+            &self.voltages,
+            &self.currents,
+            &self.fan_targets,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|sensor| sensor.internal_index + 1)
+        .max()
+        .unwrap_or_default()
+    }
+}
+
+/// Sensors that are neither fans nor temps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Display, Serialize, Deserialize)]
+pub enum SensorKind {
+    #[default]
+    Power,
+    Voltage,
+    Current,
+    #[display("Fan target")]
+    FanTarget,
 }
 
-pub type Value = i32;
+impl SensorKind {
+    pub const VALUES: [SensorKind; 4] = [
+        SensorKind::Power,
+        SensorKind::Voltage,
+        SensorKind::Current,
+        SensorKind::FanTarget,
+    ];
+
+    /// Unit of the values returned by the bridges.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            SensorKind::Power => "W",
+            SensorKind::Voltage => "mV",
+            SensorKind::Current => "mA",
+            SensorKind::FanTarget => "RPM",
+        }
+    }
+}
 
-#[derive(Debug, Clone, PartialEq, Eq, Display)]
+/// Degrees, RPM, percentages, and so on. Not rounded, the bridges
+/// keep the precision of the drivers.
+pub type Value = f64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Display, Serialize, Deserialize)]
 pub enum Mode {
     Auto,
     Manual,
-    Specific(Value),
+    /// Raw value of the enable setting of the driver
+    Specific(i32),
+}
+
+/// What happens to a control when the bridge is dropped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum OnExit {
+    /// Give the control back to the firmware
+    #[default]
+    Auto,
+    /// Leave the last value written
+    Keep,
+    /// %, written in manual mode
+    Fixed(Value),
+}
+
+/// Apply the exit policy of the controls of the bridges which can't be dropped:
+/// on a signal, or after a panic with `panic = "abort"`. Only needed on Linux,
+/// the other platforms give the control back to the firmware when the process stops.
+pub fn restore_pending_exits() {
+    #[cfg(target_os = "linux")]
+    hwmon::restore_pending_exits();
 }
 
 /// Try to construct a new hardware bridge
@@ -126,8 +262,19 @@ pub fn new() -> Result<impl HardwareBridge> {
     #[cfg(all(not(feature = "fake_hardware"), target_os = "windows"))]
     return windows::WindowsBridge::new();
 
-    #[cfg(all(not(feature = "fake_hardware"), target_os = "linux"))]
+    #[cfg(all(
+        not(feature = "fake_hardware"),
+        target_os = "linux",
+        feature = "libsensors"
+    ))]
     return linux::LinuxBridge::new();
+
+    #[cfg(all(
+        not(feature = "fake_hardware"),
+        target_os = "linux",
+        not(feature = "libsensors")
+    ))]
+    return hwmon::HwmonBridge::new();
 }
 
 pub trait HardwareBridge {
@@ -137,12 +284,35 @@ pub trait HardwareBridge {
 
     fn hardware(&self) -> &Hardware;
 
+    /// Give the hardware to a bridge which wraps this one.
+    /// The nodes count the references of an item to know if it is already used,
+    /// so the wrapper must be its only owner, instead of a clone.
+    fn take_hardware(&mut self) -> Hardware;
+
     fn get_sensor_value(&mut self, sensor: &HSensor) -> Result<Value>;
     fn get_control_value(&mut self, control: &HControl) -> Result<Value>;
 
     fn set_value(&mut self, control: &HControl, value: Value) -> Result<()>;
     fn set_mode(&mut self, control: &HControl, mode: &Mode) -> Result<()>;
 
+    /// Mode currently applied by the hardware, used to detect a firmware
+    /// which takes the control back. None when it can't be read.
+    fn get_mode(&mut self, _control: &HControl) -> Result<Option<Mode>> {
+        Ok(None)
+    }
+
+    /// What to do with the control when the bridge is dropped, or shut down.
+    /// Ignored by the bridges which always give the control back to the firmware.
+    fn set_on_exit(&mut self, _control: &HControl, _on_exit: OnExit) -> Result<()> {
+        Ok(())
+    }
+
+    /// Clock of the behaviors which depend on the elapsed time.
+    /// The simulated bridges return their own time.
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
     /// Used on Windows, because we update all sensors in one function, so
     /// we don't want to update at each call, instead, we call this function
     /// one time in each update iteration.
@@ -3,7 +3,10 @@ use std::{fmt::Debug, rc::Rc};
 use lm_sensors::{ChipRef, FeatureRef, LMSensors, SubFeatureRef, feature, value};
 use thiserror::Error;
 
-use crate::{HControl, HSensor, Hardware, HardwareBridge, HardwareError, Mode, Value};
+use crate::{
+    HControl, HSensor, Hardware, HardwareBridge, HardwareError, Mode, OnExit, SensorKind, Value,
+    hwmon::{self, ChipIds},
+};
 use ouroboros::self_referencing;
 
 // https://www.kernel.org/doc/Documentation/hwmon/sysfs-interface
@@ -37,8 +40,24 @@ struct LinuxBridgeSelfRef {
 
 impl Drop for PwmRefs<'_> {
     fn drop(&mut self) {
-        if let Err(e) = self.enable.set_raw_value(self.default_enable_cached) {
-            error!("can't set auto to a pwm sensor when quitting: {}", e)
+        let result = match self.on_exit {
+            OnExit::Auto => self.enable.set_raw_value(self.default_enable_cached),
+            OnExit::Keep => Ok(()),
+            OnExit::Fixed(value) => self
+                .enable
+                .set_raw_value(MANUAL_MODE)
+                .and_then(|_| self.io.set_raw_value((value * 2.55).round())),
+        };
+
+        if let Err(e) = result {
+            error!(
+                "can't apply the exit policy of a pwm sensor when quitting: {}",
+                e
+            )
+        }
+
+        if let Some(files) = &self.files {
+            hwmon::forget_exit(&files.io);
         }
     }
 }
@@ -47,14 +66,58 @@ struct PwmRefs<'a> {
     io: SubFeatureRef<'a>,
     enable: SubFeatureRef<'a>,
     default_enable_cached: f64,
+    on_exit: OnExit,
+    /// sysfs files, to restore the control when the bridge can't be dropped
+    files: Option<hwmon::PwmFiles>,
+}
+
+fn pwm_files(
+    chip_ref: &ChipRef,
+    io: &SubFeatureRef,
+    enable: &SubFeatureRef,
+    default_enable_cached: f64,
+) -> Option<hwmon::PwmFiles> {
+    let chip_path = chip_ref.path()?;
+
+    Some(hwmon::PwmFiles {
+        io: chip_path.join(io.name()?.ok()?),
+        enable: chip_path.join(enable.name()?.ok()?),
+        default_enable_cached: default_enable_cached as i32,
+        on_exit: OnExit::default(),
+    })
 }
 struct SensorRefs<'a> {
     io: SubFeatureRef<'a>,
+    /// libsensors unit -> user unit (volt -> millivolt)
+    scale: f64,
 }
 
 enum InternalSubFeatureRef<'a> {
     Pwm(PwmRefs<'a>),
     Sensor(SensorRefs<'a>),
+    /// read from sysfs, for what libsensors doesn't expose
+    File(hwmon::SensorFile),
+}
+
+/// libsensors doesn't expose the fan targets: find the one of a fan in sysfs,
+/// like the hwmon backend. Return its sub feature name, and its file.
+fn fan_target_file(
+    chip_ref: &ChipRef,
+    fan_input: &SubFeatureRef,
+) -> Option<(String, hwmon::SensorFile)> {
+    let chip_path = chip_ref.path()?;
+    let sub_feature_name = fan_input
+        .name()?
+        .ok()?
+        .strip_suffix("_input")
+        .map(|fan| format!("{}_target", fan))?;
+
+    let io = chip_path.join(&sub_feature_name);
+    if !io.exists() {
+        return None;
+    }
+
+    Some((sub_feature_name, hwmon::SensorFile { io, divisor: 1.0 }))
 }
 
 fn generate_hardware<'a>(
@@ -64,6 +127,7 @@ fn generate_hardware<'a>(
     struct HInfo {
         name: String,
         hardware_id: String,
+        legacy_id: String,
         info: String,
     }
 
@@ -77,6 +141,7 @@ fn generate_hardware<'a>(
 
     fn get_infos_from_refs(
         chip_ref: &ChipRef,
+        chip_id: &str,
         feature_ref: &FeatureRef,
         sub_feature_ref: &SubFeatureRef,
     ) -> std::result::Result<HInfo, GetInfoError> {
@@ -100,11 +165,13 @@ fn generate_hardware<'a>(
 
         Ok(HInfo {
             name: format!("{} {}", label, chip_name),
-            hardware_id: format!("{}-{}-{}", label, chip_name, sub_feature_name),
+            hardware_id: hwmon::hardware_id(chip_id, sub_feature_name),
+            legacy_id: hwmon::legacy_hardware_id(&label, &chip_name, sub_feature_name),
             info: format!(
-                "chip path: {}\nchip name: {}\nbus: {}\nlabel: {}\nfeature: {}",
+                "chip path: {}\nchip name: {}\nchip id: {}\nbus: {}\nlabel: {}\nfeature: {}",
                 chip_path.display(),
                 chip_name,
+                chip_id,
                 bus,
                 label,
                 sub_feature_name
@@ -113,8 +180,17 @@ fn generate_hardware<'a>(
     }
 
     let mut sensors = Vec::new();
+    let mut chip_ids = ChipIds::default();
 
     for chip_ref in lib.chip_iter(None) {
+        let chip_id = match (chip_ref.name(), chip_ref.path()) {
+            (Ok(chip_name), Some(chip_path)) => chip_ids.chip_id(&chip_name, chip_path),
+            _ => {
+                error!("can't generate the id of a chip");
+                continue;
+            }
+        };
+
         for feature_ref in chip_ref.feature_iter() {
             let next_internal_index = sensors.len();
 
@@ -127,18 +203,40 @@ fn generate_hardware<'a>(
                             continue;
                         };
 
-                        match get_infos_from_refs(&chip_ref, &feature_ref, &sub_feature_ref) {
+                        match get_infos_from_refs(
+                            &chip_ref,
+                            &chip_id,
+                            &feature_ref,
+                            &sub_feature_ref,
+                        ) {
                             Ok(h_info) => {
+                                hardware.add_legacy_id(h_info.legacy_id, &h_info.hardware_id);
+                                let target = fan_target_file(&chip_ref, &sub_feature_ref);
+
                                 let sensor = SensorRefs {
                                     io: sub_feature_ref,
+                                    scale: 1.0,
                                 };
                                 sensors.push(InternalSubFeatureRef::Sensor(sensor));
                                 hardware.fans.push(Rc::new(HSensor {
-                                    name: h_info.name,
+                                    name: h_info.name.clone(),
                                     hardware_id: h_info.hardware_id,
-                                    info: h_info.info,
+                                    info: h_info.info.clone(),
                                     internal_index: next_internal_index,
                                 }));
+
+                                if let Some((sub_feature_name, target)) = target {
+                                    hardware.fan_targets.push(Rc::new(HSensor {
+                                        name: h_info.name,
+                                        hardware_id: hwmon::hardware_id(
+                                            &chip_id,
+                                            &sub_feature_name,
+                                        ),
+                                        info: h_info.info,
+                                        internal_index: sensors.len(),
+                                    }));
+                                    sensors.push(InternalSubFeatureRef::File(target));
+                                }
                             }
                             Err(e) => {
                                 error!("can't generate hardware metadata for fan: {}", e);
@@ -152,10 +250,17 @@ fn generate_hardware<'a>(
                             continue;
                         };
 
-                        match get_infos_from_refs(&chip_ref, &feature_ref, &sub_feature_ref) {
+                        match get_infos_from_refs(
+                            &chip_ref,
+                            &chip_id,
+                            &feature_ref,
+                            &sub_feature_ref,
+                        ) {
                             Ok(h_info) => {
+                                hardware.add_legacy_id(h_info.legacy_id, &h_info.hardware_id);
                                 let sensor = SensorRefs {
                                     io: sub_feature_ref,
+                                    scale: 1.0,
                                 };
                                 sensors.push(InternalSubFeatureRef::Sensor(sensor));
                                 hardware.temps.push(Rc::new(HSensor {
@@ -196,12 +301,35 @@ fn generate_hardware<'a>(
                             }
                         };
 
-                        match get_infos_from_refs(&chip_ref, &feature_ref, &sub_feature_ref_io) {
+                        match get_infos_from_refs(
+                            &chip_ref,
+                            &chip_id,
+                            &feature_ref,
+                            &sub_feature_ref_io,
+                        ) {
                             Ok(h_info) => {
+                                hardware.add_legacy_id(h_info.legacy_id, &h_info.hardware_id);
+
+                                let files = pwm_files(
+                                    &chip_ref,
+                                    &sub_feature_ref_io,
+                                    &sub_feature_ref_enable,
+                                    enable_cached,
+                                );
+                                match &files {
+                                    Some(files) => hwmon::register_exit(files),
+                                    None => warn!(
+                                        "can't find the sysfs files of {}, it won't be restored after a crash",
+                                        h_info.name
+                                    ),
+                                }
+
                                 let sensor = InternalSubFeatureRef::Pwm(PwmRefs {
                                     io: sub_feature_ref_io,
                                     enable: sub_feature_ref_enable,
                                     default_enable_cached: enable_cached,
+                                    on_exit: OnExit::default(),
+                                    files,
                                 });
                                 sensors.push(sensor);
                                 hardware.controls.push(Rc::new(HControl {
@@ -216,6 +344,57 @@ fn generate_hardware<'a>(
                             }
                         }
                     }
+                    feature::Kind::Power | feature::Kind::Voltage | feature::Kind::Current => {
+                        let (sub_feature_kinds, sensor_kind, scale) = match feature_kind {
+                            feature::Kind::Power => (
+                                &[value::Kind::PowerInput, value::Kind::PowerAverage][..],
+                                SensorKind::Power,
+                                1.0,
+                            ),
+                            feature::Kind::Voltage => (
+                                &[value::Kind::VoltageInput][..],
+                                SensorKind::Voltage,
+                                1000.0,
+                            ),
+                            _ => (
+                                &[value::Kind::CurrentInput][..],
+                                SensorKind::Current,
+                                1000.0,
+                            ),
+                        };
+
+                        let Some(sub_feature_ref) = sub_feature_kinds
+                            .iter()
+                            .find_map(|kind| feature_ref.sub_feature_by_kind(*kind).ok())
+                        else {
+                            continue;
+                        };
+
+                        match get_infos_from_refs(
+                            &chip_ref,
+                            &chip_id,
+                            &feature_ref,
+                            &sub_feature_ref,
+                        ) {
+                            Ok(h_info) => {
+                                hardware.add_legacy_id(h_info.legacy_id, &h_info.hardware_id);
+                                let sensor = SensorRefs {
+                                    io: sub_feature_ref,
+                                    scale,
+                                };
+                                sensors.push(InternalSubFeatureRef::Sensor(sensor));
+                                hardware.sensors_mut(sensor_kind).push(Rc::new(HSensor {
+                                    name: h_info.name,
+                                    hardware_id: h_info.hardware_id,
+                                    info: h_info.info,
+                                    internal_index: next_internal_index,
+                                }));
+                            }
+                            Err(e) => {
+                                error!("can't generate hardware metadata for sensor: {}", e);
+                            }
+                        }
+                    }
                     _ => continue,
                 },
                 None => continue,
@@ -253,16 +432,23 @@ impl HardwareBridge for LinuxBridge {
         &self.hardware
     }
 
+    fn take_hardware(&mut self) -> Hardware {
+        std::mem::take(&mut self.hardware)
+    }
+
     fn get_sensor_value(&mut self, sensor: &HSensor) -> crate::Result<Value> {
         self.lm_sensor.with_sensors(|sensors| {
             match sensors.get(sensor.internal_index).expect("no sensor found") {
                 InternalSubFeatureRef::Sensor(sensor_refs) => match sensor_refs.io.raw_value() {
-                    Ok(value) => Ok(value as i32),
+                    Ok(value) => Ok(value * sensor_refs.scale),
                     Err(e) => Err(HardwareError::Linux(LinuxError::LmSensors(
                         "sensor".to_owned(),
                         e,
                     ))),
                 },
+                InternalSubFeatureRef::File(sensor_file) => {
+                    sensor_file.read().map_err(HardwareError::from)
+                }
                 _ => unreachable!(),
             }
         })
@@ -274,7 +460,7 @@ impl HardwareBridge for LinuxBridge {
                 .expect("no sensor found")
             {
                 InternalSubFeatureRef::Pwm(pwm_refs) => match pwm_refs.io.raw_value() {
-                    Ok(value) => Ok((value / 2.55) as i32),
+                    Ok(value) => Ok(value / 2.55),
                     Err(e) => Err(HardwareError::Linux(LinuxError::LmSensors(
                         "pwm".to_owned(),
                         e,
@@ -292,7 +478,7 @@ impl HardwareBridge for LinuxBridge {
                 .expect("no sensor found")
             {
                 InternalSubFeatureRef::Pwm(pwm_refs) => {
-                    let value = value as f64 * 2.55;
+                    let value = (value * 2.55).round();
                     if let Err(e) = pwm_refs.io.set_raw_value(value) {
                         let explication = format!("can't set value {} to a pwm", value);
                         let e = LinuxError::LmSensors(explication, e);
@@ -329,4 +515,43 @@ impl HardwareBridge for LinuxBridge {
             }
         })
     }
+
+    fn get_mode(&mut self, control: &HControl) -> crate::Result<Option<Mode>> {
+        self.lm_sensor.with_sensors(|sensors| {
+            match sensors
+                .get(control.internal_index)
+                .expect("no sensor found")
+            {
+                InternalSubFeatureRef::Pwm(pwm_refs) => match pwm_refs.enable.raw_value() {
+                    Ok(value) if value == MANUAL_MODE => Ok(Some(Mode::Manual)),
+                    Ok(value) if value == pwm_refs.default_enable_cached => Ok(Some(Mode::Auto)),
+                    Ok(value) => Ok(Some(Mode::Specific(value as i32))),
+                    Err(e) => {
+                        let e = LinuxError::LmSensors("can't read the mode of a pwm".into(), e);
+                        Err(HardwareError::Linux(e))
+                    }
+                },
+                _ => unreachable!(),
+            }
+        })
+    }
+
+    fn set_on_exit(&mut self, control: &HControl, on_exit: OnExit) -> crate::Result<()> {
+        self.lm_sensor.with_sensors_mut(|sensors| {
+            match sensors
+                .get_mut(control.internal_index)
+                .expect("no sensor found")
+            {
+                InternalSubFeatureRef::Pwm(pwm_refs) => {
+                    pwm_refs.on_exit = on_exit;
+                    if let Some(files) = &mut pwm_refs.files {
+                        files.on_exit = on_exit;
+                        hwmon::register_exit(files);
+                    }
+                    Ok(())
+                }
+                _ => unreachable!(),
+            }
+        })
+    }
 }
@@ -0,0 +1,711 @@
+//! Record every call made to a bridge in a trace file, and replay it later.
+//!
+//! The trace is a json line file: the first line describe the hardware,
+//! and each following line is a call, with the time elapsed since the
+//! beginning of the recording.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{HControl, HItem, HSensor, Hardware, HardwareBridge, Mode, OnExit, Value};
+
+#[derive(Error, Debug)]
+pub enum TraceError {
+    #[error("{0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("Invalid trace at line {0}: {1}")]
+    Parse(usize, serde_json::Error),
+    #[error("Can't serialize trace: {0}")]
+    Serialize(serde_json::Error),
+    #[error("The trace doesn't start with the hardware description")]
+    NoHardware,
+    #[error("No trace file was provided")]
+    NoTraceFile,
+    #[error("Replayed error: {0}")]
+    Replayed(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TraceItem {
+    pub name: String,
+    pub id: String,
+    pub info: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TraceHardware {
+    /// Unix time of the beginning of the recording, in seconds
+    pub start: u64,
+    pub controls: Vec<TraceItem>,
+    pub fans: Vec<TraceItem>,
+    pub temps: Vec<TraceItem>,
+    #[serde(default)]
+    pub powers: Vec<TraceItem>,
+    #[serde(default)]
+    pub voltages: Vec<TraceItem>,
+    #[serde(default)]
+    pub currents: Vec<TraceItem>,
+    #[serde(default)]
+    pub fan_targets: Vec<TraceItem>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "call")]
+pub enum TraceCall {
+    Update {
+        /// Time of the bridge after the update, since the beginning of the recording,
+        /// in milliseconds. Missing in older traces.
+        #[serde(default)]
+        now: Option<u64>,
+    },
+    GetSensorValue {
+        id: String,
+        result: std::result::Result<Value, String>,
+    },
+    GetControlValue {
+        id: String,
+        result: std::result::Result<Value, String>,
+    },
+    SetValue {
+        id: String,
+        value: Value,
+        result: std::result::Result<(), String>,
+    },
+    SetMode {
+        id: String,
+        mode: Mode,
+        result: std::result::Result<(), String>,
+    },
+    GetMode {
+        id: String,
+        result: std::result::Result<Option<Mode>, String>,
+    },
+    SetOnExit {
+        id: String,
+        on_exit: OnExit,
+        result: std::result::Result<(), String>,
+    },
+    Shutdown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TraceEntry {
+    /// Elapsed time since the beginning of the recording, in milliseconds
+    pub time: u64,
+    #[serde(flatten)]
+    pub call: TraceCall,
+}
+
+fn trace_items<H: HItem>(items: &[Rc<H>]) -> Vec<TraceItem> {
+    items
+        .iter()
+        .map(|h| TraceItem {
+            name: h.name().clone(),
+            id: h.id().clone(),
+            info: h.info().clone(),
+        })
+        .collect()
+}
+
+/// Wrap a bridge, and write all calls made to it in a trace file.
+pub struct RecordBridge<H: HardwareBridge> {
+    inner: H,
+    writer: BufWriter<File>,
+    start: Instant,
+    /// time of the inner bridge at the beginning of the recording
+    clock_start: Instant,
+}
+
+impl<H: HardwareBridge> RecordBridge<H> {
+    pub fn record(inner: H, path: &Path) -> crate::Result<Self> {
+        let file = File::create(path).map_err(|e| {
+            TraceError::Io(format!("can't create trace file {}", path.display()), e)
+        })?;
+
+        let hardware = inner.hardware();
+        let trace_hardware = TraceHardware {
+            start: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            controls: trace_items(&hardware.controls),
+            fans: trace_items(&hardware.fans),
+            temps: trace_items(&hardware.temps),
+            powers: trace_items(&hardware.powers),
+            voltages: trace_items(&hardware.voltages),
+            currents: trace_items(&hardware.currents),
+            fan_targets: trace_items(&hardware.fan_targets),
+        };
+
+        let mut writer = BufWriter::new(file);
+        write_line(&mut writer, &trace_hardware)?;
+
+        info!("Recording hardware calls in {}.", path.display());
+
+        Ok(Self {
+            clock_start: inner.now(),
+            inner,
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn into_inner(self) -> H {
+        self.inner
+    }
+
+    fn write(&mut self, call: TraceCall) {
+        let entry = TraceEntry {
+            time: self.start.elapsed().as_millis() as u64,
+            call,
+        };
+
+        if let Err(e) = write_line(&mut self.writer, &entry) {
+            error!("can't record hardware call: {}", e);
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            error!("can't flush trace file: {}", e);
+        }
+    }
+}
+
+fn write_line<T: Serialize>(writer: &mut impl Write, value: &T) -> Result<(), TraceError> {
+    serde_json::to_writer(&mut *writer, value).map_err(TraceError::Serialize)?;
+    writer
+        .write_all(b"\n")
+        .map_err(|e| TraceError::Io("can't write trace".into(), e))
+}
+
+fn recorded<T: Clone>(result: &crate::Result<T>) -> std::result::Result<T, String> {
+    match result {
+        Ok(value) => Ok(value.clone()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+impl<H: HardwareBridge> HardwareBridge for RecordBridge<H> {
+    /// Record in a timestamped file, in the current directory.
+    fn new() -> crate::Result<Self>
+    where
+        Self: Sized,
+    {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let path = PathBuf::from(format!("fan-control-trace-{}.jsonl", secs));
+        Self::record(H::new()?, &path)
+    }
+
+    fn hardware(&self) -> &Hardware {
+        self.inner.hardware()
+    }
+
+    fn take_hardware(&mut self) -> Hardware {
+        self.inner.take_hardware()
+    }
+
+    fn get_sensor_value(&mut self, sensor: &HSensor) -> crate::Result<Value> {
+        let result = self.inner.get_sensor_value(sensor);
+        self.write(TraceCall::GetSensorValue {
+            id: sensor.hardware_id.clone(),
+            result: recorded(&result),
+        });
+        result
+    }
+
+    fn get_control_value(&mut self, control: &HControl) -> crate::Result<Value> {
+        let result = self.inner.get_control_value(control);
+        self.write(TraceCall::GetControlValue {
+            id: control.hardware_id.clone(),
+            result: recorded(&result),
+        });
+        result
+    }
+
+    fn set_value(&mut self, control: &HControl, value: Value) -> crate::Result<()> {
+        let result = self.inner.set_value(control, value);
+        self.write(TraceCall::SetValue {
+            id: control.hardware_id.clone(),
+            value,
+            result: recorded(&result),
+        });
+        result
+    }
+
+    fn set_mode(&mut self, control: &HControl, mode: &Mode) -> crate::Result<()> {
+        let result = self.inner.set_mode(control, mode);
+        self.write(TraceCall::SetMode {
+            id: control.hardware_id.clone(),
+            mode: mode.clone(),
+            result: recorded(&result),
+        });
+        result
+    }
+
+    fn get_mode(&mut self, control: &HControl) -> crate::Result<Option<Mode>> {
+        let result = self.inner.get_mode(control);
+        self.write(TraceCall::GetMode {
+            id: control.hardware_id.clone(),
+            result: recorded(&result),
+        });
+        result
+    }
+
+    fn set_on_exit(&mut self, control: &HControl, on_exit: OnExit) -> crate::Result<()> {
+        let result = self.inner.set_on_exit(control, on_exit);
+        self.write(TraceCall::SetOnExit {
+            id: control.hardware_id.clone(),
+            on_exit,
+            result: recorded(&result),
+        });
+        result
+    }
+
+    fn now(&self) -> Instant {
+        self.inner.now()
+    }
+
+    fn update(&mut self) -> crate::Result<()> {
+        // flush the previous cycle, so we keep most of the trace on crash
+        self.flush();
+        let result = self.inner.update();
+        let now = self.inner.now().saturating_duration_since(self.clock_start);
+        self.write(TraceCall::Update {
+            now: Some(now.as_millis() as u64),
+        });
+        result
+    }
+
+    fn shutdown(&mut self) -> crate::Result<()> {
+        self.write(TraceCall::Shutdown);
+        self.flush();
+        self.inner.shutdown()
+    }
+}
+
+/// Serve a recorded trace.
+///
+/// Reads return the recorded values of each hardware, in order.
+/// When all values of a sensor have been served, the last one is repeated.
+/// Writes are compared to the recorded ones, and differences are kept
+/// in [`ReplayBridge::divergences`].
+/// Each update moves the clock of the bridge to the recorded time of the cycle.
+pub struct ReplayBridge {
+    hardware: Hardware,
+    sensor_values: HashMap<String, VecDeque<std::result::Result<Value, String>>>,
+    control_values: HashMap<String, VecDeque<std::result::Result<Value, String>>>,
+    modes: HashMap<String, VecDeque<std::result::Result<Option<Mode>, String>>>,
+    writes: HashMap<String, VecDeque<TraceCall>>,
+    last_values: HashMap<String, Value>,
+    divergences: Vec<String>,
+    /// recorded time of each update, since the beginning of the recording, in milliseconds
+    cycles: VecDeque<u64>,
+    start: Instant,
+    now: Instant,
+}
+
+impl ReplayBridge {
+    pub fn from_file(path: &Path) -> crate::Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| TraceError::Io(format!("can't open trace file {}", path.display()), e))?;
+        info!("Replaying hardware calls from {}.", path.display());
+        Self::from_reader(file)
+    }
+
+    pub fn from_reader(reader: impl Read) -> crate::Result<Self> {
+        let mut lines = BufReader::new(reader).lines().enumerate();
+
+        let trace_hardware = match lines.next() {
+            Some((_, Ok(line))) => {
+                serde_json::from_str::<TraceHardware>(&line).map_err(|e| TraceError::Parse(1, e))?
+            }
+            Some((_, Err(e))) => return Err(TraceError::Io("can't read trace".into(), e).into()),
+            None => return Err(TraceError::NoHardware.into()),
+        };
+
+        let mut hardware = Hardware::default();
+        let mut internal_index = 0;
+        let mut next_index = || {
+            internal_index += 1;
+            internal_index - 1
+        };
+
+        for item in trace_hardware.controls {
+            hardware.controls.push(Rc::new(HControl {
+                name: item.name,
+                hardware_id: item.id,
+                info: item.info,
+                internal_index: next_index(),
+            }));
+        }
+        for item in trace_hardware.fans {
+            hardware.fans.push(Rc::new(HSensor {
+                name: item.name,
+                hardware_id: item.id,
+                info: item.info,
+                internal_index: next_index(),
+            }));
+        }
+        let sensors = [
+            (&mut hardware.temps, trace_hardware.temps),
+            (&mut hardware.powers, trace_hardware.powers),
+            (&mut hardware.voltages, trace_hardware.voltages),
+            (&mut hardware.currents, trace_hardware.currents),
+            (&mut hardware.fan_targets, trace_hardware.fan_targets),
+        ];
+        for (h_sensors, items) in sensors {
+            for item in items {
+                h_sensors.push(Rc::new(HSensor {
+                    name: item.name,
+                    hardware_id: item.id,
+                    info: item.info,
+                    internal_index: next_index(),
+                }));
+            }
+        }
+
+        let start = Instant::now();
+        let mut bridge = Self {
+            hardware,
+            sensor_values: HashMap::new(),
+            control_values: HashMap::new(),
+            modes: HashMap::new(),
+            writes: HashMap::new(),
+            last_values: HashMap::new(),
+            divergences: Vec::new(),
+            cycles: VecDeque::new(),
+            start,
+            now: start,
+        };
+
+        for (index, line) in lines {
+            let line = line.map_err(|e| TraceError::Io("can't read trace".into(), e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str::<TraceEntry>(&line)
+                .map_err(|e| TraceError::Parse(index + 1, e))?;
+
+            match entry.call {
+                TraceCall::GetSensorValue { id, result } => bridge
+                    .sensor_values
+                    .entry(id)
+                    .or_default()
+                    .push_back(result),
+                TraceCall::GetControlValue { id, result } => bridge
+                    .control_values
+                    .entry(id)
+                    .or_default()
+                    .push_back(result),
+                TraceCall::GetMode { id, result } => {
+                    bridge.modes.entry(id).or_default().push_back(result)
+                }
+                TraceCall::SetValue { ref id, .. }
+                | TraceCall::SetMode { ref id, .. }
+                | TraceCall::SetOnExit { ref id, .. } => bridge
+                    .writes
+                    .entry(id.clone())
+                    .or_default()
+                    .push_back(entry.call),
+                TraceCall::Update { now } => bridge.cycles.push_back(now.unwrap_or(entry.time)),
+                TraceCall::Shutdown => {}
+            }
+        }
+
+        Ok(bridge)
+    }
+
+    /// Writes that did not match the recorded session.
+    pub fn divergences(&self) -> &[String] {
+        &self.divergences
+    }
+
+    fn next_value(
+        values: &mut HashMap<String, VecDeque<std::result::Result<Value, String>>>,
+        last_values: &mut HashMap<String, Value>,
+        id: &str,
+    ) -> crate::Result<Value> {
+        let next = values.get_mut(id).and_then(|v| v.pop_front());
+
+        match next {
+            Some(Ok(value)) => {
+                last_values.insert(id.to_owned(), value);
+                Ok(value)
+            }
+            Some(Err(e)) => Err(TraceError::Replayed(e).into()),
+            None => match last_values.get(id) {
+                Some(value) => Ok(*value),
+                None => Err(TraceError::Replayed(format!("no recorded value for {}", id)).into()),
+            },
+        }
+    }
+
+    fn check_write(&mut self, id: &str, call: TraceCall) -> crate::Result<()> {
+        let expected = self.writes.get_mut(id).and_then(|w| w.pop_front());
+
+        let result = match &expected {
+            Some(TraceCall::SetValue { result, .. })
+            | Some(TraceCall::SetMode { result, .. })
+            | Some(TraceCall::SetOnExit { result, .. }) => result.clone(),
+            _ => Ok(()),
+        };
+
+        let same = match (&expected, &call) {
+            (
+                Some(TraceCall::SetValue {
+                    value: expected, ..
+                }),
+                TraceCall::SetValue { value, .. },
+            ) => expected == value,
+            (
+                Some(TraceCall::SetOnExit {
+                    on_exit: expected, ..
+                }),
+                TraceCall::SetOnExit { on_exit, .. },
+            ) => expected == on_exit,
+            (Some(TraceCall::SetMode { mode: expected, .. }), TraceCall::SetMode { mode, .. }) => {
+                expected == mode
+            }
+            _ => false,
+        };
+
+        if !same {
+            let divergence = format!("{}: expected {:?}, got {:?}", id, expected, call);
+            warn!("Replay divergence: {}.", divergence);
+            self.divergences.push(divergence);
+        }
+
+        result.map_err(|e| TraceError::Replayed(e).into())
+    }
+}
+
+impl HardwareBridge for ReplayBridge {
+    fn new() -> crate::Result<Self>
+    where
+        Self: Sized,
+    {
+        Err(TraceError::NoTraceFile.into())
+    }
+
+    fn hardware(&self) -> &Hardware {
+        &self.hardware
+    }
+
+    fn take_hardware(&mut self) -> Hardware {
+        std::mem::take(&mut self.hardware)
+    }
+
+    fn get_sensor_value(&mut self, sensor: &HSensor) -> crate::Result<Value> {
+        Self::next_value(
+            &mut self.sensor_values,
+            &mut self.last_values,
+            &sensor.hardware_id,
+        )
+    }
+
+    fn get_control_value(&mut self, control: &HControl) -> crate::Result<Value> {
+        Self::next_value(
+            &mut self.control_values,
+            &mut self.last_values,
+            &control.hardware_id,
+        )
+    }
+
+    fn set_value(&mut self, control: &HControl, value: Value) -> crate::Result<()> {
+        self.check_write(
+            &control.hardware_id,
+            TraceCall::SetValue {
+                id: control.hardware_id.clone(),
+                value,
+                result: Ok(()),
+            },
+        )
+    }
+
+    fn set_mode(&mut self, control: &HControl, mode: &Mode) -> crate::Result<()> {
+        self.check_write(
+            &control.hardware_id,
+            TraceCall::SetMode {
+                id: control.hardware_id.clone(),
+                mode: mode.clone(),
+                result: Ok(()),
+            },
+        )
+    }
+
+    /// Unknown when all recorded modes have been served.
+    fn get_mode(&mut self, control: &HControl) -> crate::Result<Option<Mode>> {
+        match self
+            .modes
+            .get_mut(&control.hardware_id)
+            .and_then(|modes| modes.pop_front())
+        {
+            Some(result) => result.map_err(|e| TraceError::Replayed(e).into()),
+            None => Ok(None),
+        }
+    }
+
+    fn set_on_exit(&mut self, control: &HControl, on_exit: OnExit) -> crate::Result<()> {
+        self.check_write(
+            &control.hardware_id,
+            TraceCall::SetOnExit {
+                id: control.hardware_id.clone(),
+                on_exit,
+                result: Ok(()),
+            },
+        )
+    }
+
+    fn now(&self) -> Instant {
+        self.now
+    }
+
+    /// When all cycles have been served, the clock stays at the last one.
+    fn update(&mut self) -> crate::Result<()> {
+        if let Some(time) = self.cycles.pop_front() {
+            self.now = self.start + Duration::from_millis(time);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        rc::Rc,
+        time::{Duration, Instant},
+    };
+
+    use crate::{HControl, HSensor, Hardware, HardwareBridge, Mode, OnExit, Value};
+
+    use super::{RecordBridge, ReplayBridge};
+
+    /// Return an increasing temperature, and accept every write.
+    /// Each update is a second.
+    struct CounterBridge {
+        hardware: Hardware,
+        temp: Value,
+        now: Instant,
+    }
+
+    impl HardwareBridge for CounterBridge {
+        fn new() -> crate::Result<Self> {
+            let mut hardware = Hardware::default();
+            hardware.temps.push(Rc::new(HSensor {
+                name: "temp1".into(),
+                hardware_id: "temp1".into(),
+                info: String::new(),
+                internal_index: 0,
+            }));
+            hardware.controls.push(Rc::new(HControl {
+                name: "control1".into(),
+                hardware_id: "control1".into(),
+                info: String::new(),
+                internal_index: 1,
+            }));
+            Ok(Self {
+                hardware,
+                temp: 30.0,
+                now: Instant::now(),
+            })
+        }
+
+        fn hardware(&self) -> &Hardware {
+            &self.hardware
+        }
+
+        fn take_hardware(&mut self) -> Hardware {
+            std::mem::take(&mut self.hardware)
+        }
+
+        fn get_sensor_value(&mut self, _sensor: &HSensor) -> crate::Result<Value> {
+            self.temp += 1.0;
+            Ok(self.temp)
+        }
+
+        fn get_control_value(&mut self, _control: &HControl) -> crate::Result<Value> {
+            Ok(50.0)
+        }
+
+        fn set_value(&mut self, _control: &HControl, _value: Value) -> crate::Result<()> {
+            Ok(())
+        }
+
+        fn set_mode(&mut self, _control: &HControl, _mode: &Mode) -> crate::Result<()> {
+            Ok(())
+        }
+
+        fn now(&self) -> Instant {
+            self.now
+        }
+
+        fn update(&mut self) -> crate::Result<()> {
+            self.now += Duration::from_secs(1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_record_replay() {
+        let path =
+            std::env::temp_dir().join(format!("fan-control-trace-{}.jsonl", std::process::id()));
+
+        {
+            let mut bridge = RecordBridge::record(CounterBridge::new().unwrap(), &path).unwrap();
+            let temp = bridge.hardware().temps[0].clone();
+            let control = bridge.hardware().controls[0].clone();
+
+            bridge.set_on_exit(&control, OnExit::Auto).unwrap();
+            for _ in 0..3 {
+                bridge.update().unwrap();
+                let value = bridge.get_sensor_value(&temp).unwrap();
+                bridge.set_mode(&control, &Mode::Manual).unwrap();
+                bridge.set_value(&control, value).unwrap();
+            }
+            bridge.shutdown().unwrap();
+        }
+
+        let mut replay = ReplayBridge::from_file(&path).unwrap();
+        let temp = replay.hardware().temps[0].clone();
+        let control = replay.hardware().controls[0].clone();
+        assert_eq!(temp.hardware_id, "temp1");
+
+        let start = replay.now();
+        replay.set_on_exit(&control, OnExit::Auto).unwrap();
+        replay.update().unwrap();
+        assert_eq!(replay.now() - start, Duration::from_secs(1));
+        assert_eq!(replay.get_sensor_value(&temp).unwrap(), 31.0);
+        replay.set_mode(&control, &Mode::Manual).unwrap();
+        replay.set_value(&control, 31.0).unwrap();
+        replay.update().unwrap();
+        assert_eq!(replay.get_sensor_value(&temp).unwrap(), 32.0);
+        replay.set_mode(&control, &Mode::Manual).unwrap();
+        replay.set_value(&control, 40.0).unwrap();
+        replay.update().unwrap();
+        assert_eq!(replay.now() - start, Duration::from_secs(3));
+        assert_eq!(replay.get_sensor_value(&temp).unwrap(), 33.0);
+        // the last value is repeated
+        assert_eq!(replay.get_sensor_value(&temp).unwrap(), 33.0);
+
+        // the clock stays at the last cycle
+        replay.update().unwrap();
+        assert_eq!(replay.now() - start, Duration::from_secs(3));
+
+        assert_eq!(replay.divergences().len(), 1);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -314,12 +314,16 @@ impl HardwareBridge for WindowsBridge {
         &self.hardware
     }
 
+    fn take_hardware(&mut self) -> Hardware {
+        std::mem::take(&mut self.hardware)
+    }
+
     fn get_sensor_value(&mut self, sensor: &HSensor) -> crate::Result<Value> {
         self.send(Command::GetValue)?;
         self.send(I32::from(sensor.internal_index))?;
 
         let value = self.read::<I32>()?;
-        Ok(value.0)
+        Ok(value.0 as Value)
     }
 
     fn get_control_value(&mut self, control: &HControl) -> crate::Result<Value> {
@@ -327,13 +331,13 @@ impl HardwareBridge for WindowsBridge {
         self.send(I32::from(control.internal_index))?;
 
         let value = self.read::<I32>()?;
-        Ok(value.0)
+        Ok(value.0 as Value)
     }
 
     fn set_value(&mut self, control: &HControl, value: Value) -> crate::Result<()> {
         self.send(Command::SetValue)?;
         self.send(I32::from(control.internal_index))?;
-        self.send(I32(value))?;
+        self.send(I32(value.round() as i32))?;
         Ok(())
     }
 
@@ -2,15 +2,22 @@ none = Keine
 average = Durchschnitt
 max = Max
 min = Min
+exponential = Exponentieller Durchschnitt
+median = Median
 
 default_control = Steuerung
 default_fan = Lüfter
 default_temp = Temperatur
+default_sensor = Sensor
 default_custom_temp = Benutzerdefinierte Temperatur
 default_graph = Diagramm
 default_flat = Flach
 default_linear = Linear
 default_target = Ziel
+default_hysteresis = Hysterese
+default_smoothing = Glättung
+default_pid = PID
+default_rpm_target = Drehzahlziel
 
 system_theme = System
 dark_theme = Dunkel
@@ -19,4 +26,11 @@ light_theme = Hell
 value_celsius = { $value } °C
 value_percentage = { $value } %
 value_rpm = { $value } RPM
+value_unit = { $value } { $unit }
 no_value = Kein Wert
+
+stall_alarm = Der Lüfter { $fan } von { $control } steht: { $rpm } RPM bei { $duty } %
+stall_alarm_expected = Der Lüfter { $fan } von { $control } steht: { $rpm } RPM bei { $duty } %, { $expected } RPM erwartet
+
+failsafe_critical_temp = { $temp } hat seine kritische Temperatur erreicht: { $value } °C ≥ { $critical } °C
+failsafe_sensor_lost = { $temp } kann nicht mehr gelesen werden
@@ -15,12 +15,36 @@ idle_temp = Leerlauftemperatur
 idle_speed = Leerlaufdrehzahl
 load_temp = Lasttemperatur
 load_speed = Lastgeschwindigkeit
+up_delta = Delta nach oben
+down_delta = Delta nach unten
+min_interval = Mindestabstand
+samples = Messwerte
+time_constant = Zeitkonstante
+setpoint = Sollwert
+kp = Kp
+ki = Ki
+kd = Kd
+derivative_filter = Ableitungsfilter
+target_input = Ziel
+target_rpm = Zieldrehzahl
+max_rpm = max. Drehzahl
+gain = Verstärkung
+tolerance = Toleranz
+step = Schritt
+invert = Invertierte PWM
+zero_rpm = Aus unter
+kick = Anlaufimpuls
+ramp_up = Anstieg
+ramp_down = Abfall
+control_fan = Lüfter
+firmware_override = Die Firmware hat diese Steuerung { $count } Mal übernommen
 launch_graph_window = Koordinaten hinzufügen
 
 # Add item description
 add_item = Ein Element hinzufügen
 add_fan = Überwachung eines Lüftersensors
 add_temp = Überwachung eines Temperatursensors
+add_sensor = Überwachung eines Leistungs-, Spannungs-, Strom- oder Lüfterzielsensors
 add_custom_temp = Definiere die Logik zwischen Werten (Max, Durchschnitt, ...)
 add_control = Weist einer bestimmten Hardwarekomponente ein bestimmtes Verhalten zu
 add_flat = Gibt einen festen Wert zurück
@@ -37,6 +61,14 @@ add_target = Nimm 5 Variablen:
     - einen Sensorwert
     Wenn der Sensor > Auslösetemperatur ist, wird die Auslösegeschwindigkeit
     so lange gesetzt, bis dieser Sensor < Idealtemperatur ist.
+add_hysteresis = Behält den letzten Wert seines Eingangs, bis er um das Delta nach oben
+    steigt oder um das Delta nach unten fällt, höchstens eine Änderung pro Mindestabstand
+add_smoothing = Glättet die Messwerte einer Temperatur, damit eine einzelne Spitze
+    die Lüfter nicht hochdrehen lässt (Durchschnitt, exponentieller Durchschnitt oder Median)
+add_pid = Hält eine Temperatur auf einem Sollwert, indem die Geschwindigkeit
+    mit Proportional-, Integral- und Differentialanteil angepasst wird
+add_rpm_target = Hält einen Lüfter auf einer Drehzahl, indem der Wert seiner Steuerung
+    angepasst wird. Das Ziel ist fest, oder ein Prozentsatz der max. Drehzahl
 add_graph = Diagramm
 
 # Config
@@ -48,3 +80,26 @@ create_config = Konfiguration erstellen
 # Error
 already_used_error = Dieser Name wird bereits verwendet
 invalid_value_error = dieser Wert ist ungültig
+
+# Calibration
+calibrate = Kalibrieren
+calibration_title = Kalibrierung von { $name }
+calibration_fan = Von dieser Steuerung angetriebener Lüfter
+calibration_description = Die Steuerung durchläuft alle Tastgrade, um herauszufinden, wann der Lüfter stoppt und anläuft. Das dauert einige Minuten.
+calibration_running = Messung der Lüfterdrehzahl bei { $duty } %
+calibration_result = Stopp-PWM: { $stop }
+    Anlauf-PWM: { $start }
+    Maximale Drehzahl: { $max } RPM
+calibration_failed = Kalibrierung fehlgeschlagen: { $error }
+calibration_start = Starten
+calibration_cancel = Abbrechen
+calibration_close = Schließen
+
+# Remap
+remap_title = Hardware nicht gefunden
+remap_description = Einige von dieser Konfiguration verwendete Hardware wurde nicht gefunden, zum Beispiel nach einem BIOS- oder Kernel-Update. Wähle aus, was sie ersetzen soll.
+remap_missing = { $name }: { $id }
+remap_no_candidate = Kein Ersatz gefunden
+remap_skip = Überspringen
+remap_apply = Anwenden
+remap_ignore = Ignorieren
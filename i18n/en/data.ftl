@@ -2,15 +2,22 @@ none = None
 average = Average
 max = Max
 min = Min
+exponential = Exponential average
+median = Median
 
 default_control = Control
 default_fan = Fan
 default_temp = Temp
+default_sensor = Sensor
 default_custom_temp = Custom temp
 default_graph = Graph
 default_flat = Flat
 default_linear = Linear
 default_target = Target
+default_hysteresis = Hysteresis
+default_smoothing = Smoothing
+default_pid = PID
+default_rpm_target = RPM target
 
 system_theme = System
 dark_theme = Dark
@@ -21,4 +28,11 @@ hight_contrast_light_theme = HighContrastLight
 value_celsius = { $value } °C
 value_percentage = { $value } %
 value_rpm = { $value } RPM
-no_value = No value
\ No newline at end of file
+value_unit = { $value } { $unit }
+no_value = No value
+
+stall_alarm = The fan { $fan } of { $control } stalled: { $rpm } RPM at { $duty } %
+stall_alarm_expected = The fan { $fan } of { $control } stalled: { $rpm } RPM at { $duty } %, { $expected } RPM expected
+
+failsafe_critical_temp = { $temp } reached its critical temp: { $value } °C ≥ { $critical } °C
+failsafe_sensor_lost = { $temp } can't be read anymore
\ No newline at end of file
@@ -16,6 +16,29 @@ idle_temp = idle temp
 idle_speed = idle speed
 load_temp = load temp
 load_speed = load speed
+up_delta = up delta
+down_delta = down delta
+min_interval = min interval
+samples = samples
+time_constant = time constant
+setpoint = setpoint
+kp = Kp
+ki = Ki
+kd = Kd
+derivative_filter = derivative filter
+target_input = Target
+target_rpm = target RPM
+max_rpm = max RPM
+gain = gain
+tolerance = tolerance
+step = step
+invert = Inverted PWM
+zero_rpm = off below
+kick = kick
+ramp_up = ramp up
+ramp_down = ramp down
+control_fan = Fan
+firmware_override = The firmware took this control back { $count } times
 launch_graph_window = Add coordinates
 config_saved = Configuration successfully saved
 repository = Repository
@@ -26,6 +49,7 @@ issues_tracker = Report an Issue
 add_item = Add an item
 add_fan = Monitor a fan sensor
 add_temp = Monitor a temp sensor
+add_sensor = Monitor a power, voltage, current or fan target sensor
 add_custom_temp = Define logic between values (Max, Averrage, ...)
 add_control = Assigns a certain behavior to a certain hardware component
 add_flat = Returns a fixed value
@@ -42,6 +66,14 @@ add_target = Take 5 variables:
     - a sensor value
     If the sensor > trigger temperature, trigger speed is set
     until this sensor is < ideal temperature
+add_hysteresis = Keep the last value of its input until it rises by the up delta
+    or falls by the down delta, and at most one change per min interval
+add_smoothing = Smooth the readings of a temp, so a single spike
+    doesn't make the fans rev (average, exponential average or median)
+add_pid = Hold a temp at a setpoint, by adjusting the speed
+    with proportional, integral and derivative terms
+add_rpm_target = Keep a fan at a speed in RPM, by adjusting the value of its control
+    while reading the fan. The target is fixed, or a percentage of max RPM
 add_graph = Graph
 
 # Config
@@ -60,4 +92,27 @@ config_not_saved = Configuration not saved
 # Dialogs
 udev_rules_dialog_ok = I understand
 udev_rules_dialog_remind_later = Remind me Later
-udev_rules_dialog_copy_to_clipboard = Copy Commands to Clipboard
\ No newline at end of file
+udev_rules_dialog_copy_to_clipboard = Copy Commands to Clipboard
+
+# Calibration
+calibrate = Calibrate
+calibration_title = Calibration of { $name }
+calibration_fan = Fan driven by this control
+calibration_description = The control will go through all its duty cycles to find when the fan stops and starts. It takes a few minutes.
+calibration_running = Measuring the fan speed at { $duty } %
+calibration_result = Stop PWM: { $stop }
+    Start PWM: { $start }
+    Max speed: { $max } RPM
+calibration_failed = Calibration failed: { $error }
+calibration_start = Start
+calibration_cancel = Cancel
+calibration_close = Close
+
+# Remap
+remap_title = Hardware not found
+remap_description = Some hardware used by this configuration was not found, after a BIOS or kernel update for instance. Choose what should replace it.
+remap_missing = { $name }: { $id }
+remap_no_candidate = No replacement found
+remap_skip = Skip
+remap_apply = Apply
+remap_ignore = Ignore
@@ -2,15 +2,22 @@ none = Aucun
 average = Moyenne
 max = Max
 min = Min
+exponential = Moyenne exponentielle
+median = Médiane
 
 default_control = Control
 default_fan = Ventilateur
 default_temp = Temp
+default_sensor = Capteur
 default_custom_temp = Temp custom
 default_graph = Graph
 default_flat = Plat
 default_linear = Lineaire
 default_target = Cible
+default_hysteresis = Hystérésis
+default_smoothing = Lissage
+default_pid = PID
+default_rpm_target = Cible RPM
 
 system_theme = Système
 dark_theme = Sombre
@@ -19,4 +26,11 @@ light_theme = Clair
 value_celsius = { $value } °C
 value_percentage = { $value } %
 value_rpm = { $value } RPM
-no_value = Aucune valeur
\ No newline at end of file
+value_unit = { $value } { $unit }
+no_value = Aucune valeur
+
+stall_alarm = Le ventilateur { $fan } de { $control } est bloqué : { $rpm } RPM à { $duty } %
+stall_alarm_expected = Le ventilateur { $fan } de { $control } est bloqué : { $rpm } RPM à { $duty } %, { $expected } RPM attendus
+
+failsafe_critical_temp = { $temp } a atteint sa température critique : { $value } °C ≥ { $critical } °C
+failsafe_sensor_lost = { $temp } ne peut plus être lu
\ No newline at end of file
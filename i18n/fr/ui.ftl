@@ -15,6 +15,29 @@ idle_temp = idle temp
 idle_speed = idle speed
 load_temp = load temp
 load_speed = load speed
+up_delta = delta montée
+down_delta = delta descente
+min_interval = intervalle min
+samples = échantillons
+time_constant = constante de temps
+setpoint = consigne
+kp = Kp
+ki = Ki
+kd = Kd
+derivative_filter = filtre de la dérivée
+target_input = Cible
+target_rpm = RPM cible
+max_rpm = RPM max
+gain = gain
+tolerance = tolérance
+step = pas
+invert = PWM inversé
+zero_rpm = arrêt sous
+kick = impulsion
+ramp_up = montée
+ramp_down = descente
+control_fan = Ventilateur
+firmware_override = Le firmware a repris ce contrôle { $count } fois
 launch_graph_window = Ajout de coordonnées
 config_saved = Configuration enregistrée avec succès
 
@@ -22,6 +45,7 @@ config_saved = Configuration enregistrée avec succès
 add_item = Ajouter un item
 add_fan = Monitore un ventilateur
 add_temp = Monitore un capteur de temperature
+add_sensor = Monitore un capteur de puissance, tension, courant ou de vitesse cible
 add_custom_temp = Defini une logique entre des valeurs (Max, Moyenne, ...)
 add_control = Applique un certain comportement a un ventilateur
 add_flat = Retourne une valeur fixe
@@ -38,6 +62,14 @@ add_target = Prend 5 variables :
     - une valeur de capteur
     Si le capteur > température de déclenchement, la vitesse de déclenchement est définie
     jusqu'à ce que ce capteur < température idéale
+add_hysteresis = Garde la dernière valeur de son entrée jusqu'à ce qu'elle monte du delta montée
+    ou descende du delta descente, au plus un changement par intervalle min
+add_smoothing = Lisse les mesures d'une température, pour qu'un pic isolé
+    ne fasse pas accélérer les ventilateurs (moyenne, moyenne exponentielle ou médiane)
+add_pid = Maintient une température à une consigne, en ajustant la vitesse
+    avec des termes proportionnel, intégral et dérivé
+add_rpm_target = Maintient un ventilateur à une vitesse en RPM, en ajustant la valeur de son contrôle
+    selon la lecture du ventilateur. La cible est fixe, ou un pourcentage du RPM max
 add_graph = Graphe
 
 # Config
@@ -49,3 +81,26 @@ create_config = Créer une configuration
 # Error
 already_used_error = Ce nom est déjà utilisé
 invalid_value_error = Cette valeur est invalide
+
+# Calibration
+calibrate = Calibrer
+calibration_title = Calibrage de { $name }
+calibration_fan = Ventilateur piloté par ce contrôle
+calibration_description = Le contrôle va parcourir tous ses rapports cycliques pour trouver quand le ventilateur s'arrête et démarre. Cela prend quelques minutes.
+calibration_running = Mesure de la vitesse du ventilateur à { $duty } %
+calibration_result = PWM d'arrêt : { $stop }
+    PWM de démarrage : { $start }
+    Vitesse max : { $max } RPM
+calibration_failed = Échec du calibrage : { $error }
+calibration_start = Démarrer
+calibration_cancel = Annuler
+calibration_close = Fermer
+
+# Remap
+remap_title = Matériel introuvable
+remap_description = Du matériel utilisé par cette configuration est introuvable, par exemple après une mise à jour du BIOS ou du noyau. Choisissez par quoi le remplacer.
+remap_missing = { $name } : { $id }
+remap_no_candidate = Aucun remplacement trouvé
+remap_skip = Ignorer
+remap_apply = Appliquer
+remap_ignore = Ignorer
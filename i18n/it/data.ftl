@@ -2,15 +2,22 @@ none = Nessuno
 average = Media
 max = Max
 min = Min
+exponential = Media esponenziale
+median = Mediana
 
 default_control = Controllo
 default_fan = Ventola
 default_temp = Temp
+default_sensor = Sensore
 default_custom_temp = Temp. personalizzata
 default_graph = Grafico
 default_flat = Piatto
 default_linear = Lineare
 default_target = Target
+default_hysteresis = Isteresi
+default_smoothing = Smussamento
+default_pid = PID
+default_rpm_target = Obiettivo RPM
 
 system_theme = Sistema
 dark_theme = Scuro
@@ -19,4 +26,11 @@ light_theme = Chiaro
 value_celsius = { $value } °C
 value_percentage = { $value } %
 value_rpm = { $value } RPM
-no_value = Nessun valore
\ No newline at end of file
+value_unit = { $value } { $unit }
+no_value = Nessun valore
+
+stall_alarm = La ventola { $fan } di { $control } è bloccata: { $rpm } RPM al { $duty } %
+stall_alarm_expected = La ventola { $fan } di { $control } è bloccata: { $rpm } RPM al { $duty } %, { $expected } RPM attesi
+
+failsafe_critical_temp = { $temp } ha raggiunto la temperatura critica: { $value } °C ≥ { $critical } °C
+failsafe_sensor_lost = { $temp } non può più essere letto
\ No newline at end of file
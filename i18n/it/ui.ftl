@@ -15,12 +15,36 @@ idle_temp = idle temp
 idle_speed = idle speed
 load_temp = load temp
 load_speed = load speed
+up_delta = delta salita
+down_delta = delta discesa
+min_interval = intervallo min
+samples = campioni
+time_constant = costante di tempo
+setpoint = setpoint
+kp = Kp
+ki = Ki
+kd = Kd
+derivative_filter = filtro derivativo
+target_input = Obiettivo
+target_rpm = RPM obiettivo
+max_rpm = RPM max
+gain = guadagno
+tolerance = tolleranza
+step = passo
+invert = PWM invertito
+zero_rpm = spenta sotto
+kick = spinta
+ramp_up = salita
+ramp_down = discesa
+control_fan = Ventola
+firmware_override = Il firmware ha ripreso questo controllo { $count } volte
 launch_graph_window = Aggiungi coordinate
 
 # Add item description
 add_item = Aggiungi un elemento
 add_fan = Monitorare un sensore della ventola
 add_temp = Monitorare un sensore di temperatura
+add_sensor = Monitorare un sensore di potenza, tensione, corrente o velocità target
 add_custom_temp = Definire la logica tra i valori (Max, Media, ...)
 add_control = Assegna un determinato comportamento a un determinato componente hardware
 add_flat = Restituisce un valore fisso
@@ -37,6 +61,14 @@ add_target = Prendi 5 variabili:
     - un valore del sensore
     Se il sensore > temperatura di attivazione, viene impostata la velocità di attivazione
      fino a quando questo sensore < temperatura ideale
+add_hysteresis = Mantiene l'ultimo valore del suo ingresso finché non sale del delta salita
+    o scende del delta discesa, al massimo un cambio per intervallo min
+add_smoothing = Smussa le letture di una temperatura, così un singolo picco
+    non fa accelerare le ventole (media, media esponenziale o mediana)
+add_pid = Mantiene una temperatura a un setpoint, regolando la velocità
+    con termini proporzionale, integrale e derivativo
+add_rpm_target = Mantiene una ventola a una velocità in RPM, regolando il valore del suo controllo
+    in base alla lettura della ventola. L'obiettivo è fisso, o una percentuale degli RPM max
 add_graph = Grafico
 
 # Config
@@ -47,4 +79,27 @@ create_config = Crea configurazione
 
 # Error
 already_used_error =  Questo nome è già in uso
-invalid_value_error = questo valore non è valido
\ No newline at end of file
+invalid_value_error = questo valore non è valido
+
+# Calibration
+calibrate = Calibra
+calibration_title = Calibrazione di { $name }
+calibration_fan = Ventola pilotata da questo controllo
+calibration_description = Il controllo passerà per tutti i cicli di lavoro per trovare quando la ventola si ferma e riparte. Ci vogliono alcuni minuti.
+calibration_running = Misura della velocità della ventola al { $duty } %
+calibration_result = PWM di arresto: { $stop }
+    PWM di avvio: { $start }
+    Velocità massima: { $max } RPM
+calibration_failed = Calibrazione fallita: { $error }
+calibration_start = Avvia
+calibration_cancel = Annulla
+calibration_close = Chiudi
+
+# Remap
+remap_title = Hardware non trovato
+remap_description = Parte dell'hardware usato da questa configurazione non è stato trovato, per esempio dopo un aggiornamento del BIOS o del kernel. Scegli cosa deve sostituirlo.
+remap_missing = { $name }: { $id }
+remap_no_candidate = Nessuna sostituzione trovata
+remap_skip = Salta
+remap_apply = Applica
+remap_ignore = Ignora
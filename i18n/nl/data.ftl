@@ -2,15 +2,22 @@ none = Geen
 average = Gemiddeld
 max = Maximum
 min = Minimum
+exponential = Exponentieel gemiddelde
+median = Mediaan
 
 default_control = Controle
 default_fan = Ventilator
 default_temp = Temperatuur
+default_sensor = Sensor
 default_custom_temp = Aangepaste temperatuur
 default_graph = Grafiek
 default_flat = Vlak
 default_linear = Lineair
 default_target = Doel
+default_hysteresis = Hysterese
+default_smoothing = Afvlakking
+default_pid = PID
+default_rpm_target = RPM-doel
 
 system_theme = Systeemstandaard
 dark_theme = Donker
@@ -21,4 +28,11 @@ hight_contrast_light_theme = Hoog contrast licht
 value_celsius = { $value } °C
 value_percentage = { $value } %
 value_rpm = { $value } RPM
+value_unit = { $value } { $unit }
 no_value = Geen waarde
+
+stall_alarm = De ventilator { $fan } van { $control } staat stil: { $rpm } RPM bij { $duty } %
+stall_alarm_expected = De ventilator { $fan } van { $control } staat stil: { $rpm } RPM bij { $duty } %, { $expected } RPM verwacht
+
+failsafe_critical_temp = { $temp } heeft zijn kritieke temperatuur bereikt: { $value } °C ≥ { $critical } °C
+failsafe_sensor_lost = { $temp } kan niet meer worden gelezen
@@ -16,6 +16,29 @@ idle_temp = Temperatuur bij inactiviteit
 idle_speed = Snelheid bij inactiviteit
 load_temp = Temperatuur bij systeembelasting
 load_speed = Snelheid bij systeembelasting
+up_delta = delta omhoog
+down_delta = delta omlaag
+min_interval = min interval
+samples = metingen
+time_constant = tijdconstante
+setpoint = instelwaarde
+kp = Kp
+ki = Ki
+kd = Kd
+derivative_filter = afgeleidefilter
+target_input = Doel
+target_rpm = doel-RPM
+max_rpm = max RPM
+gain = versterking
+tolerance = tolerantie
+step = Stap
+invert = Omgekeerde PWM
+zero_rpm = Uit onder
+kick = Aanloopstoot
+ramp_up = stijging
+ramp_down = daling
+control_fan = Ventilator
+firmware_override = De firmware heeft deze regeling { $count } keer overgenomen
 launch_graph_window = Coördinaten toevoegen
 config_saved = Configuratie succesvol opgeslagen
 repository = Repository
@@ -26,6 +49,7 @@ issues_tracker = Meld een fout
 add_item = Item toevoegen
 add_fan = Ventilatorsensor toevoegen
 add_temp = Temperatuursensor toevoegen
+add_sensor = Vermogen-, spannings-, stroom- of doeltoerentalsensor toevoegen
 add_custom_temp = Defineer het logische verband tussen waarden (max, gemiddelde, ...)
 add_control = Een specifieke actie aan een specifieke hardwarecomponent toewijzen
 add_flat = Geeft een vaste waarde als resultaat
@@ -44,6 +68,14 @@ add_target = Gebruik 5 variabelen:
     als sensorwaarde > triggertemperatuur; dan wordt de triggersnelheid ingesteld
     totdat sensorwaarde < optimumtemperatuur
 
+add_hysteresis = Houdt de laatste waarde van de invoer vast tot die met de delta omhoog stijgt
+    of met de delta omlaag daalt, hoogstens één wijziging per min interval
+add_smoothing = Vlakt de metingen van een temperatuur af, zodat één piek
+    de ventilatoren niet laat optoeren (gemiddelde, exponentieel gemiddelde of mediaan)
+add_pid = Houdt een temperatuur op een instelwaarde, door de snelheid aan te passen
+    met proportionele, integrerende en differentiërende termen
+add_rpm_target = Houdt een ventilator op een snelheid in RPM, door de waarde van de regeling
+    aan te passen. Het doel is vast, of een percentage van de max RPM
 add_graph = Grafiek
 
 # Config
@@ -63,3 +95,26 @@ config_not_saved = De configuratie is niet opgeslagen
 udev_rules_dialog_ok = Ik begrijp het
 udev_rules_dialog_remind_later = Herinner me er later aan
 udev_rules_dialog_copy_to_clipboard = Commando's naar het klembord kopiëren
+
+# Calibration
+calibrate = Kalibreren
+calibration_title = Kalibratie van { $name }
+calibration_fan = Ventilator aangestuurd door deze besturing
+calibration_description = De besturing doorloopt alle duty cycles om te vinden wanneer de ventilator stopt en start. Dit duurt enkele minuten.
+calibration_running = Ventilatorsnelheid meten bij { $duty } %
+calibration_result = Stop-PWM: { $stop }
+    Start-PWM: { $start }
+    Maximale snelheid: { $max } RPM
+calibration_failed = Kalibratie mislukt: { $error }
+calibration_start = Starten
+calibration_cancel = Annuleren
+calibration_close = Sluiten
+
+# Remap
+remap_title = Hardware niet gevonden
+remap_description = Sommige hardware die deze configuratie gebruikt, is niet gevonden, bijvoorbeeld na een BIOS- of kernelupdate. Kies waardoor deze vervangen moet worden.
+remap_missing = { $name }: { $id }
+remap_no_candidate = Geen vervanging gevonden
+remap_skip = Overslaan
+remap_apply = Toepassen
+remap_ignore = Negeren
@@ -2,15 +2,22 @@ none = Nenhum
 average = Média
 max = Max
 min = Min
+exponential = Média exponencial
+median = Mediana
 
 default_control = Controle
 default_fan = Fan
 default_temp = Temp
+default_sensor = Sensor
 default_custom_temp = Temp. personalizada
 default_graph = Gráfico
 default_flat = Plano
 default_linear = Linear
 default_target = Alvo
+default_hysteresis = Histerese
+default_smoothing = Suavização
+default_pid = PID
+default_rpm_target = Alvo de RPM
 
 system_theme = Sistema
 dark_theme = Escuro
@@ -21,4 +28,11 @@ hight_contrast_light_theme = Alto Contraste Claro
 value_celsius = { $value } °C
 value_percentage = { $value } %
 value_rpm = { $value } RPM
+value_unit = { $value } { $unit }
 no_value = Nenhum valor
+
+stall_alarm = O ventilador { $fan } de { $control } travou: { $rpm } RPM a { $duty } %
+stall_alarm_expected = O ventilador { $fan } de { $control } travou: { $rpm } RPM a { $duty } %, { $expected } RPM esperados
+
+failsafe_critical_temp = { $temp } atingiu sua temperatura crítica: { $value } °C ≥ { $critical } °C
+failsafe_sensor_lost = { $temp } não pode mais ser lido
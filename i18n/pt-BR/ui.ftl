@@ -16,6 +16,29 @@ idle_temp = idle temp
 idle_speed = idle speed
 load_temp = load temp
 load_speed = load speed
+up_delta = delta de subida
+down_delta = delta de descida
+min_interval = intervalo mín
+samples = amostras
+time_constant = constante de tempo
+setpoint = setpoint
+kp = Kp
+ki = Ki
+kd = Kd
+derivative_filter = filtro da derivada
+target_input = Alvo
+target_rpm = RPM alvo
+max_rpm = RPM máx
+gain = ganho
+tolerance = tolerância
+step = passo
+invert = PWM invertido
+zero_rpm = desligar abaixo de
+kick = impulso
+ramp_up = subida
+ramp_down = descida
+control_fan = Ventoinha
+firmware_override = O firmware retomou este controle { $count } vezes
 launch_graph_window = Adicionar coordenadas
 config_saved = Configuração salva com sucesso
 repository = Repositório
@@ -26,6 +49,7 @@ issues_tracker = Relatar um problema
 add_item = Adicionar um item
 add_fan = Monitorar um sensor de ventilador
 add_temp = Monitorar um sensor de temperatura
+add_sensor = Monitorar um sensor de potência, tensão, corrente ou rotação alvo
 add_custom_temp = Definir uma lógica entre valores (Max, Média, ...)
 add_control = Atribui um determinado comportamento a um determinado componente de hardware
 add_flat = Retorna um valor fixo
@@ -42,6 +66,14 @@ add_target = Pegue 5 variáveis:
     - um sensor de valor
     Se o sensor > temp. de gatilho, a velocidade de gatilho é definida
     até que este sensor seja < temperatura ideal
+add_hysteresis = Mantém o último valor da entrada até ele subir o delta de subida
+    ou descer o delta de descida, no máximo uma mudança por intervalo mín
+add_smoothing = Suaviza as leituras de uma temperatura, para que um pico isolado
+    não acelere as ventoinhas (média, média exponencial ou mediana)
+add_pid = Mantém uma temperatura em um setpoint, ajustando a velocidade
+    com termos proporcional, integral e derivativo
+add_rpm_target = Mantém uma ventoinha em uma velocidade em RPM, ajustando o valor do seu controle
+    conforme a leitura da ventoinha. O alvo é fixo, ou uma porcentagem do RPM máx
 add_graph = Gráfico
 
 # Config
@@ -61,3 +93,26 @@ config_not_saved = Configuração não salva
 udev_rules_dialog_ok = Eu compreendo
 udev_rules_dialog_remind_later = Lembre-me depois
 udev_rules_dialog_copy_to_clipboard = Copiar comandos para a área de transferência
+
+# Calibration
+calibrate = Calibrar
+calibration_title = Calibração de { $name }
+calibration_fan = Ventoinha controlada por este controle
+calibration_description = O controle vai percorrer todos os ciclos de trabalho para descobrir quando a ventoinha para e volta a girar. Isso leva alguns minutos.
+calibration_running = Medindo a rotação da ventoinha em { $duty } %
+calibration_result = PWM de parada: { $stop }
+    PWM de partida: { $start }
+    Rotação máxima: { $max } RPM
+calibration_failed = Falha na calibração: { $error }
+calibration_start = Iniciar
+calibration_cancel = Cancelar
+calibration_close = Fechar
+
+# Remap
+remap_title = Hardware não encontrado
+remap_description = Parte do hardware usado por esta configuração não foi encontrado, por exemplo após uma atualização da BIOS ou do kernel. Escolha o que deve substituí-lo.
+remap_missing = { $name }: { $id }
+remap_no_candidate = Nenhuma substituição encontrada
+remap_skip = Pular
+remap_apply = Aplicar
+remap_ignore = Ignorar
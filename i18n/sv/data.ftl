@@ -2,15 +2,22 @@ none = Ingen
 average = Genomsnittlig
 max = Max
 min = Min
+exponential = Exponentiellt medelvärde
+median = Median
 
 default_control = Kontroll
 default_fan = Fläkt
 default_temp = Temperatur
+default_sensor = Sensor
 default_custom_temp = Anpassad temperatur
 default_graph = Graf
 default_flat = Plan
 default_linear = Linjär
 default_target = Mål
+default_hysteresis = Hysteres
+default_smoothing = Utjämning
+default_pid = PID
+default_rpm_target = Varvtalsmål
 
 system_theme = System
 dark_theme = Mörkt
@@ -21,4 +28,11 @@ hight_contrast_light_theme = Hög kontrast ljust
 value_celsius = { $value } °C
 value_percentage = { $value } %
 value_rpm = { $value } RPM
+value_unit = { $value } { $unit }
 no_value = Inget värde
+
+stall_alarm = Fläkten { $fan } för { $control } har stannat: { $rpm } RPM vid { $duty } %
+stall_alarm_expected = Fläkten { $fan } för { $control } har stannat: { $rpm } RPM vid { $duty } %, { $expected } RPM förväntat
+
+failsafe_critical_temp = { $temp } har nått sin kritiska temperatur: { $value } °C ≥ { $critical } °C
+failsafe_sensor_lost = { $temp } kan inte längre läsas
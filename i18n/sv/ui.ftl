@@ -15,6 +15,29 @@ idle_temp = overksam temperatur
 idle_speed = overksam hastighet
 load_temp = last temperatur
 load_speed = last hastighet
+up_delta = delta upp
+down_delta = delta ned
+min_interval = min intervall
+samples = mätvärden
+time_constant = tidskonstant
+setpoint = börvärde
+kp = Kp
+ki = Ki
+kd = Kd
+derivative_filter = derivatafilter
+target_input = Mål
+target_rpm = målvarvtal
+max_rpm = max varvtal
+gain = förstärkning
+tolerance = tolerans
+step = steg
+invert = Inverterad PWM
+zero_rpm = av under
+kick = startpuls
+ramp_up = ökning
+ramp_down = minskning
+control_fan = Fläkt
+firmware_override = Firmware tog tillbaka denna styrning { $count } gånger
 launch_graph_window = Lägg till koordinater
 config_saved = Konfigurationen har sparats
 
@@ -22,6 +45,7 @@ config_saved = Konfigurationen har sparats
 add_item = Lägg till ett objekt
 add_fan = Övervaka en fläktsensor
 add_temp = Övervaka en temperatursensor
+add_sensor = Övervaka en effekt-, spännings-, ström- eller målvarvtalssensor
 add_custom_temp = Definiera logik mellan värden (Max, Medel, ...)
 add_control = Tilldelar ett visst beteende till en viss hårdvarukomponent
 add_flat = Returnerar ett fast värde
@@ -38,6 +62,14 @@ add_target = Tar 5 variabler:
     - ett sensor värde
     Om sensorn > triggertemperatur är triggerhastigheten inställd
     tills denna sensor är < ideal temperatur
+add_hysteresis = Behåller det senaste värdet av indata tills det stiger med delta upp
+    eller sjunker med delta ned, högst en ändring per min intervall
+add_smoothing = Jämnar ut mätvärdena för en temperatur, så att en enstaka topp
+    inte får fläktarna att varva upp (medelvärde, exponentiellt medelvärde eller median)
+add_pid = Håller en temperatur vid ett börvärde, genom att justera hastigheten
+    med proportionell, integrerande och deriverande del
+add_rpm_target = Håller en fläkt vid ett varvtal, genom att justera värdet på dess styrning
+    utifrån fläktens avläsning. Målet är fast, eller en procentandel av max varvtal
 add_graph = Graf
 
 # Konfiguration
@@ -49,3 +81,26 @@ create_config = Skapa konfiguration
 # Fel
 already_used_error = Det här namnet används redan
 invalid_value_error = detta värde är ogiltigt
+
+# Calibration
+calibrate = Kalibrera
+calibration_title = Kalibrering av { $name }
+calibration_fan = Fläkt som styrs av denna kontroll
+calibration_description = Kontrollen går igenom alla arbetscykler för att hitta när fläkten stannar och startar. Det tar några minuter.
+calibration_running = Mäter fläktens hastighet vid { $duty } %
+calibration_result = Stopp-PWM: { $stop }
+    Start-PWM: { $start }
+    Maxhastighet: { $max } RPM
+calibration_failed = Kalibreringen misslyckades: { $error }
+calibration_start = Starta
+calibration_cancel = Avbryt
+calibration_close = Stäng
+
+# Remap
+remap_title = Hårdvara hittades inte
+remap_description = Viss hårdvara som används av denna konfiguration hittades inte, till exempel efter en BIOS- eller kärnuppdatering. Välj vad som ska ersätta den.
+remap_missing = { $name }: { $id }
+remap_no_candidate = Ingen ersättning hittades
+remap_skip = Hoppa över
+remap_apply = Verkställ
+remap_ignore = Ignorera
@@ -2,15 +2,22 @@ none = 空
 average = 平均值
 max = 最大值
 min = 最小值
+exponential = 指数平均
+median = 中位数
 
 default_control = 控制
 default_fan = 风扇
 default_temp = 温度
+default_sensor = 传感器
 default_custom_temp = 自定义温度
 default_graph = 图表
 default_flat = 水平
 default_linear = 线性
 default_target = 目标
+default_hysteresis = 滞后
+default_smoothing = 平滑
+default_pid = PID
+default_rpm_target = 转速目标
 
 system_theme = 系统
 dark_theme = 暗色
@@ -19,4 +26,11 @@ light_theme = 亮色
 value_celsius = { $value } °C
 value_percentage = { $value } %
 value_rpm = { $value } RPM
+value_unit = { $value } { $unit }
 no_value = 没有数据
+
+stall_alarm = { $control } 的风扇 { $fan } 已停转：{ $duty } % 时 { $rpm } RPM
+stall_alarm_expected = { $control } 的风扇 { $fan } 已停转：{ $duty } % 时 { $rpm } RPM，预期 { $expected } RPM
+
+failsafe_critical_temp = { $temp } 达到临界温度：{ $value } °C ≥ { $critical } °C
+failsafe_sensor_lost = 无法再读取 { $temp }
@@ -15,12 +15,36 @@ idle_temp = 怠速温度
 idle_speed = 怠速速度
 load_temp = 负载温度
 load_speed = 负载速度
+up_delta = 上升差值
+down_delta = 下降差值
+min_interval = 最小间隔
+samples = 样本数
+time_constant = 时间常数
+setpoint = 设定值
+kp = Kp
+ki = Ki
+kd = Kd
+derivative_filter = 微分滤波
+target_input = 目标
+target_rpm = 目标转速
+max_rpm = 最大转速
+gain = 增益
+tolerance = 容差
+step = 步长
+invert = 反向 PWM
+zero_rpm = 低于此值关闭
+kick = 启动脉冲
+ramp_up = 上升速率
+ramp_down = 下降速率
+control_fan = 风扇
+firmware_override = 固件已 { $count } 次接管此控制
 launch_graph_window = 添加坐标
 
 # Add item description
 add_item = 添加项目
 add_fan = 监控风扇传感器
 add_temp = 监控温度传感器
+add_sensor = 监控功率、电压、电流或目标转速传感器
 add_custom_temp = 定义值之间的逻辑（最大值、平均值、 ...）
 add_control = 将特定行为分配给特定硬件组件
 add_flat = 返回一个固定值
@@ -37,6 +61,14 @@ add_target = 取决于5个变量:
     - 一个传感器值
     如果传感器 > 触发温度，会设置触发速度
     直到这个传感器 < 理想温度
+add_hysteresis = 保持输入的上一个值，直到其上升超过上升差值
+    或下降超过下降差值，每个最小间隔内最多变化一次
+add_smoothing = 平滑温度读数，避免单次尖峰使风扇加速
+    （平均值、指数平均或中位数）
+add_pid = 通过比例、积分和微分项调整速度，
+    使温度保持在设定值
+add_rpm_target = 根据风扇读数调整其控制值，使风扇保持在指定转速。
+    目标为固定值，或最大转速的百分比
 add_graph = 图表
 
 # Config
@@ -48,3 +80,26 @@ create_config = 创建配置
 # Error
 already_used_error = 此名称已被使用
 invalid_value_error = 此值无效
+
+# Calibration
+calibrate = 校准
+calibration_title = 校准 { $name }
+calibration_fan = 由此控制驱动的风扇
+calibration_description = 控制将遍历所有占空比，以找出风扇停止和启动的位置。这需要几分钟。
+calibration_running = 正在测量 { $duty } % 时的风扇转速
+calibration_result = 停止 PWM：{ $stop }
+    启动 PWM：{ $start }
+    最大转速：{ $max } RPM
+calibration_failed = 校准失败：{ $error }
+calibration_start = 开始
+calibration_cancel = 取消
+calibration_close = 关闭
+
+# Remap
+remap_title = 未找到硬件
+remap_description = 此配置使用的部分硬件未找到，例如在 BIOS 或内核更新之后。请选择替换它的硬件。
+remap_missing = { $name }：{ $id }
+remap_no_candidate = 未找到替代硬件
+remap_skip = 跳过
+remap_apply = 应用
+remap_ignore = 忽略
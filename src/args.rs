@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::{Parser, ValueHint};
+use clap::{Parser, Subcommand, ValueHint};
 
 fn version() -> String {
     format!(
@@ -13,6 +13,9 @@ fn version() -> String {
 #[derive(Parser, Debug, Default)]
 #[clap(author = "wiiznokes", version = version(), about = "fan control app", long_about = None)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[arg(
         short = 'p',
         long = "path",
@@ -67,4 +70,49 @@ pub struct Args {
         help = "Write hardware file in the config dir"
     )]
     pub serialize_hardware: bool,
+
+    #[arg(
+        long = "record",
+        value_hint = ValueHint::FilePath,
+        value_names = ["PATH"],
+        conflicts_with = "replay",
+        help = "Record all hardware calls in a trace file, to reproduce a bug"
+    )]
+    pub record: Option<PathBuf>,
+
+    #[arg(
+        long = "replay",
+        value_hint = ValueHint::FilePath,
+        value_names = ["PATH"],
+        help = "Replay a trace file made with --record, instead of using the hardware"
+    )]
+    pub replay: Option<PathBuf>,
+
+    #[cfg(feature = "fake_hardware")]
+    #[arg(
+        long = "fake-hardware",
+        value_hint = ValueHint::FilePath,
+        value_names = ["PATH"],
+        help = "Inventory of the fake hardware, in the format of the hardware file"
+    )]
+    pub fake_hardware: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Measure the stop PWM, start PWM and max RPM of a fan, and save them in the config directory
+    Calibrate {
+        #[arg(help = "Id of the control, as written by --write-hardware")]
+        control: String,
+        #[arg(help = "Id of the fan driven by this control")]
+        fan: String,
+        #[arg(
+            long = "invert",
+            default_value_t = false,
+            help = "The control has an inverted PWM logic"
+        )]
+        invert: bool,
+    },
+    /// Give every control back to the firmware, for instance in the `ExecStopPost` of a service
+    Restore,
 }
@@ -2,16 +2,28 @@
 // no blocking read timeout for now
 
 use std::{
-    sync::mpsc::{self, RecvTimeoutError, Sender},
+    io::{self, BufRead, IsTerminal, Write},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
     thread::{self},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crossterm::event::{self, Event, KeyCode, KeyEvent};
-use data::{AppState, settings::Settings};
-use hardware::HardwareBridge;
+use data::{
+    AppState,
+    calibration::{CalibrationParams, Calibrator},
+    config::Config,
+    dir_manager::DirManager,
+    settings::Settings,
+};
+use hardware::{HItem, Hardware, HardwareBridge, Mode};
 
-pub fn run_cli<H: HardwareBridge>(mut app_state: AppState<H>) {
+/// `stop`: set on a signal, the loop ends and the controls are restored.
+pub fn run_cli<H: HardwareBridge>(mut app_state: AppState<H>, stop: Arc<AtomicBool>) {
     let current_config = match &app_state.dir_manager.settings().current_config {
         Some(current_config) => current_config,
         None => {
@@ -30,6 +42,11 @@ pub fn run_cli<H: HardwareBridge>(mut app_state: AppState<H>) {
     display_info(app_state.dir_manager.settings(), current_config);
 
     loop {
+        if stop.load(Ordering::Relaxed) {
+            println!("stop requested");
+            break;
+        }
+
         if let Err(e) = app_state.bridge.update() {
             error!("{}", e);
             break;
@@ -38,11 +55,20 @@ pub fn run_cli<H: HardwareBridge>(mut app_state: AppState<H>) {
         if let Err(e) = app_state.update.optimized(
             &mut app_state.app_graph.nodes,
             &app_state.app_graph.root_nodes,
+            &app_state.app_graph.failsafe,
             &mut app_state.bridge,
         ) {
             error!("{}", e);
         }
 
+        if let Some(reason) = app_state.update.failsafe.take_triggered() {
+            println!("Failsafe: {reason}");
+        }
+
+        for alarm in app_state.update.stall.take_new_alarms() {
+            println!("Alarm: {alarm}");
+        }
+
         let settings_update_delay =
             Duration::from_millis(app_state.dir_manager.settings().update_delay);
         let final_delay = std::cmp::max(settings_update_delay, Duration::from_millis(50));
@@ -66,11 +92,236 @@ pub fn run_cli<H: HardwareBridge>(mut app_state: AppState<H>) {
         }
     }
 
+    app_state
+        .update
+        .set_on_exit(&app_state.app_graph.nodes, &mut app_state.bridge);
+
     if let Err(e) = app_state.bridge.shutdown() {
         error!("shutdown hardware: {}", e);
     }
 }
 
+/// Give every control back to the firmware.
+pub fn restore<H: HardwareBridge>(mut bridge: H) {
+    for control in bridge.hardware().controls.clone() {
+        match bridge.set_mode(&control, &Mode::Auto) {
+            Ok(()) => println!("{}: restored", control.name()),
+            Err(e) => error!("Can't restore {}: {e}.", control.name()),
+        }
+    }
+
+    if let Err(e) = bridge.shutdown() {
+        error!("shutdown hardware: {}", e);
+    }
+}
+
+pub fn calibrate<H: HardwareBridge>(
+    mut dir_manager: DirManager,
+    mut bridge: H,
+    control_id: &str,
+    fan_id: &str,
+    invert: bool,
+) {
+    let hardware = bridge.hardware();
+
+    let Some(control) = hardware
+        .controls
+        .iter()
+        .find(|c| c.id() == control_id)
+        .cloned()
+    else {
+        println!("Control {control_id} not found. Available controls:");
+        for control in &hardware.controls {
+            println!("    {}: {}", control.id(), control.name());
+        }
+        return;
+    };
+
+    let Some(fan) = hardware.fans.iter().find(|f| f.id() == fan_id).cloned() else {
+        println!("Fan {fan_id} not found. Available fans:");
+        for fan in &hardware.fans {
+            println!("    {}: {}", fan.id(), fan.name());
+        }
+        return;
+    };
+
+    // restore the mode on ctrl-c
+    let interrupted = Arc::new(AtomicBool::new(false));
+    for signal in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+        if let Err(e) = signal_hook::flag::register(signal, interrupted.clone()) {
+            error!("can't register signal handler: {e}");
+        }
+    }
+
+    let (tx, rx) = mpsc::channel::<UserAction>();
+    start_listening(tx);
+
+    let params = CalibrationParams {
+        invert,
+        ..Default::default()
+    };
+    let sample_interval = params.sample_interval;
+
+    println!();
+    println!("Calibrating {} with {}.", control.name(), fan.name());
+    println!("This takes a few minutes, the fan will be driven through its whole range.");
+    println!("quit: q");
+    println!();
+
+    // restored at the end, the firmware had the control if it can't be read
+    let mode = match bridge.get_mode(&control) {
+        Ok(mode) => mode.unwrap_or(Mode::Auto),
+        Err(e) => {
+            warn!("Can't read the mode of {}: {e}.", control.name());
+            Mode::Auto
+        }
+    };
+
+    let mut calibrator =
+        match Calibrator::start(&mut bridge, control, fan, params, mode, Instant::now()) {
+            Ok(calibrator) => calibrator,
+            Err(e) => {
+                println!("Can't start the calibration: {e}");
+                return;
+            }
+        };
+
+    let mut last_progress = None;
+
+    loop {
+        if interrupted.load(Ordering::Relaxed) {
+            println!("calibration interrupted");
+            calibrator.cancel(&mut bridge);
+            break;
+        }
+
+        if let Err(e) = bridge.update() {
+            error!("{}", e);
+            calibrator.cancel(&mut bridge);
+            break;
+        }
+
+        match calibrator.poll(&mut bridge, Instant::now()) {
+            Ok(Some(calibration)) => {
+                let display = |pwm: Option<hardware::Value>| match pwm {
+                    Some(pwm) => format!("{pwm}%"),
+                    None => "never stops".to_owned(),
+                };
+
+                println!();
+                println!("Stop PWM: {}", display(calibration.stop_pwm));
+                println!("Start PWM: {}", display(calibration.start_pwm));
+                println!("Max RPM: {}", calibration.max_rpm);
+
+                dir_manager.save_calibration(calibration);
+                println!(
+                    "Calibration saved in {}",
+                    dir_manager.config_dir_path.display()
+                );
+                break;
+            }
+            Ok(None) => {
+                let progress = (calibrator.phase(), calibrator.duty());
+                if last_progress != Some(progress) {
+                    println!("Measuring at {}%", progress.1);
+                    last_progress = Some(progress);
+                }
+            }
+            Err(e) => {
+                println!("Calibration failed: {e}");
+                break;
+            }
+        }
+
+        match rx.recv_timeout(sample_interval) {
+            Ok(UserAction::Quit) => {
+                println!("calibration interrupted");
+                calibrator.cancel(&mut bridge);
+                break;
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                error!("can't listen action from user");
+                calibrator.cancel(&mut bridge);
+                break;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+    }
+
+    if let Err(e) = bridge.shutdown() {
+        error!("shutdown hardware: {}", e);
+    }
+}
+
+/// Ask the user to replace the hardware of the config which doesn't exist anymore.
+pub fn remap_hardware(dir_manager: &DirManager, config: &mut Config, hardware: &Hardware) {
+    let unresolved = config.unresolved_hardware_ids(hardware);
+
+    if unresolved.is_empty() {
+        return;
+    }
+
+    if !io::stdin().is_terminal() {
+        warn!(
+            "{} hardware ids of the config were not found, run fan-control --cli in a terminal to remap them.",
+            unresolved.len()
+        );
+        return;
+    }
+
+    let mut remappings = Vec::new();
+    let mut lines = io::stdin().lock().lines();
+
+    for unresolved in &unresolved {
+        println!();
+        println!(
+            "{}: hardware {} not found.",
+            unresolved.node_name, unresolved.hardware_id
+        );
+
+        if unresolved.candidates.is_empty() {
+            println!("No replacement found.");
+            continue;
+        }
+
+        for (index, candidate) in unresolved.candidates.iter().enumerate() {
+            println!(
+                "    {}: {} ({})",
+                index + 1,
+                candidate.name,
+                candidate.hardware_id
+            );
+        }
+        print!(
+            "Replace with [1-{}, enter to skip]: ",
+            unresolved.candidates.len()
+        );
+        if let Err(e) = io::stdout().flush() {
+            error!("{e}");
+        }
+
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+
+        match line
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|choice| unresolved.candidates.get(choice.checked_sub(1)?))
+        {
+            Some(candidate) => remappings.push(unresolved.remapping(candidate)),
+            None => println!("Skipped."),
+        }
+    }
+
+    if config.remap_hardware_ids(&remappings) {
+        dir_manager.remap_hardware_ids(&remappings);
+        println!("Configuration saved.");
+    }
+    println!();
+}
+
 enum UserAction {
     Quit,
 }
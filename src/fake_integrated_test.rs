@@ -1,58 +1,459 @@
 use std::path::PathBuf;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crate::args::Args;
 use crate::integrated_test::init_test_logging;
 use data::app_graph::AppGraph;
+use data::calibration::{CalibrationError, CalibrationParams, Calibrator};
 use data::dir_manager::DirManager;
+use data::node::NodeType;
 use data::{AppState, update::Update};
-use hardware::HardwareBridge;
+use hardware::Mode;
+use hardware::OnExit;
+use hardware::Value;
+use hardware::command_sensor::CommandSensorBridge;
+use hardware::fake_hardware::FakeHardwareBridge;
+use hardware::file_sensor::FileSensorBridge;
+use hardware::{HControl, HSensor, Hardware, HardwareBridge, HardwareError};
 
-#[test]
-fn test_config() {
+fn app_state(config_name: &str) -> AppState<FakeHardwareBridge> {
+    app_state_with(config_name, FakeHardwareBridge::new().unwrap())
+}
+
+fn app_state_with<H: HardwareBridge>(config_name: &str, bridge: H) -> AppState<H> {
     init_test_logging();
 
     let args = Args {
         config_dir_path: Some(PathBuf::from("./configs-examples")),
-        config_name: Some("fake".into()),
+        config_name: Some(config_name.into()),
         ..Default::default()
     };
 
     let dir_manager = DirManager::new(&args.config_dir_path, &args.config_name);
 
-    let bridge = hardware::fake_hardware::FakeHardwareBridge::new().unwrap();
-
     let config = dir_manager.get_config().unwrap();
 
     let app_graph = AppGraph::from_config(config, bridge.hardware());
 
-    let app_state = AppState {
+    AppState {
         dir_manager,
         app_graph,
         update: Update::new(),
         bridge,
-    };
-
-    run(app_state)
+    }
 }
 
-fn run<H: HardwareBridge>(mut app_state: AppState<H>) {
+#[test]
+fn test_config() {
+    let mut app_state = app_state("fake");
+
     for _ in 0..20 {
-        if let Err(e) = app_state.bridge.update() {
-            error!("{}", e);
-            break;
+        step(&mut app_state);
+        debug!("\n");
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn test_wrapped_bridge() {
+    let bridge = FileSensorBridge::with_sensors(FakeHardwareBridge::new().unwrap(), Vec::new());
+    let bridge = CommandSensorBridge::with_sensors(bridge, Vec::new());
+    let app_state = app_state_with("fake", bridge);
+
+    // the wrappers must not hold a second reference to the controls
+    let control = app_state
+        .app_graph
+        .nodes
+        .values()
+        .find_map(|node| match &node.node_type {
+            NodeType::Control(control) if control.name == "control1" => Some(control),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(control.hardware_id.as_deref(), Some("control1"));
+    assert!(control.control_h.is_some());
+}
+
+/// Run the simulation until it should have settled,
+/// and return the values of the node during the last steps.
+fn run_until_steady<H: HardwareBridge>(app_state: &mut AppState<H>, node_name: &str) -> Vec<Value> {
+    let mut values = Vec::new();
+
+    for i in 0..600 {
+        step(app_state);
+
+        if i >= 500 {
+            let node = app_state
+                .app_graph
+                .nodes
+                .values()
+                .find(|node| node.name() == node_name)
+                .unwrap();
+            values.push(node.value.unwrap());
         }
+    }
+
+    values
+}
 
-        app_state
-            .update
-            .optimized(
-                &mut app_state.app_graph.nodes,
-                &app_state.app_graph.root_nodes,
-                &mut app_state.bridge,
-            )
+fn step<H: HardwareBridge>(app_state: &mut AppState<H>) {
+    app_state.bridge.update().unwrap();
+
+    app_state
+        .update
+        .optimized(
+            &mut app_state.app_graph.nodes,
+            &app_state.app_graph.root_nodes,
+            &app_state.app_graph.failsafe,
+            &mut app_state.bridge,
+        )
+        .unwrap();
+}
+
+fn assert_steady(values: &[Value], expected: Value) {
+    let min = values.iter().copied().reduce(Value::min).unwrap();
+    let max = values.iter().copied().reduce(Value::max).unwrap();
+
+    assert!(max - min <= 1.0, "not steady: {values:?}");
+    assert!((min - expected).abs() <= 1.0, "{min} != {expected}");
+}
+
+#[test]
+fn test_graph_steady_state() {
+    let mut app_state = app_state("fake_graph");
+
+    // T = 25 + 60 / (1 + 2 * d), with d = 0.1 + 0.9 * (T - 30) / 30
+    assert_steady(&run_until_steady(&mut app_state, "temp1"), 50.0);
+    assert_steady(&run_until_steady(&mut app_state, "graph1"), 70.0);
+
+    let rpm = app_state.bridge.simulation.fans[0].rpm;
+    assert!((rpm - 1400.0).abs() < 20.0, "{rpm}");
+}
+
+#[test]
+fn test_target_steady_state() {
+    let mut app_state = app_state("fake_target");
+
+    // idle until 70°C, then full speed, which never go back under 40°C
+    assert_steady(&run_until_steady(&mut app_state, "temp1"), 45.0);
+    assert_steady(&run_until_steady(&mut app_state, "target1"), 100.0);
+}
+
+#[test]
+fn test_smoothing_steady_state() {
+    let mut app_state = app_state("fake_smoothing");
+
+    // same curve as the graph, the median of the temps doesn't change the steady state
+    assert_steady(&run_until_steady(&mut app_state, "smoothing1"), 50.0);
+    assert_steady(&run_until_steady(&mut app_state, "linear1"), 70.0);
+}
+
+#[test]
+fn test_pid() {
+    let mut app_state = app_state("fake_pid");
+
+    // the loop needs 60 / (50 - 25) = 1 + 2 * 0.7 to hold the setpoint
+    assert_steady(&run_until_steady(&mut app_state, "temp1"), 50.0);
+    assert_steady(&run_until_steady(&mut app_state, "pid1"), 70.0);
+}
+
+#[test]
+fn test_rpm_target() {
+    let mut app_state = app_state("fake_rpm_target");
+
+    // 2000 RPM at 100%
+    assert_steady(&run_until_steady(&mut app_state, "fan1"), 900.0);
+    assert_steady(&run_until_steady(&mut app_state, "rpm_target1"), 45.0);
+
+    // the same fan, worn out
+    app_state.bridge.simulation.fans[0].max_rpm = 1500.0;
+    assert_steady(&run_until_steady(&mut app_state, "fan1"), 900.0);
+    assert_steady(&run_until_steady(&mut app_state, "rpm_target1"), 60.0);
+}
+
+#[test]
+fn test_hysteresis() {
+    let mut app_state = app_state("fake_hysteresis");
+
+    // one is before the behavior, the other after
+    for name in ["hysteresis1", "hysteresis2"] {
+        let node = app_state
+            .app_graph
+            .nodes
+            .values()
+            .find(|node| node.name() == name)
             .unwrap();
-        debug!("\n");
-        thread::sleep(Duration::from_millis(50));
+        assert_eq!(node.inputs.len(), 1, "{name}");
+    }
+
+    // once settled, the held values don't move at all
+    for (name, expected) in [("hysteresis1", 50.0), ("hysteresis2", 67.0)] {
+        let values = run_until_steady(&mut app_state, name);
+        assert!(values.iter().all(|value| *value == values[0]), "{values:?}");
+        assert_steady(&values, expected);
+    }
+}
+
+#[test]
+fn test_calibration() {
+    init_test_logging();
+
+    let mut bridge = FakeHardwareBridge::new().unwrap();
+    let control = bridge.hardware().controls[0].clone();
+    let fan = bridge.hardware().fans[0].clone();
+
+    let params = CalibrationParams::default();
+    let time_step = bridge.simulation.time_step;
+    let mut now = Instant::now();
+
+    let mut calibrator =
+        Calibrator::start(&mut bridge, control, fan, params, Mode::Auto, now).unwrap();
+    assert!(bridge.simulation.controls[0].manual);
+
+    let calibration = loop {
+        bridge.update().unwrap();
+        now += time_step;
+
+        if let Some(calibration) = calibrator.poll(&mut bridge, now).unwrap() {
+            break calibration;
+        }
+        assert!(bridge.simulation.steps < 1000, "calibration never ends");
+    };
+
+    // the fan stops under 20%, and starts again from 30%
+    assert_eq!(calibration.stop_pwm, Some(15.0));
+    assert_eq!(calibration.start_pwm, Some(30.0));
+    assert!(
+        (calibration.max_rpm - 2000.0).abs() < 50.0,
+        "{calibration:?}"
+    );
+
+    assert!(!bridge.simulation.controls[0].manual);
+}
+
+#[test]
+fn test_calibration_no_start() {
+    let mut bridge = FakeHardwareBridge::new().unwrap();
+    let control = bridge.hardware().controls[0].clone();
+    let fan = bridge.hardware().fans[0].clone();
+
+    // already spinning, but once stopped, the fan never starts again
+    bridge.simulation.fans[0].rpm = 2000.0;
+    bridge.simulation.fans[0].start_duty = 101.0;
+
+    let params = CalibrationParams::default();
+    let time_step = bridge.simulation.time_step;
+    let mut now = Instant::now();
+
+    let mut calibrator =
+        Calibrator::start(&mut bridge, control, fan, params, Mode::Auto, now).unwrap();
+
+    let error = loop {
+        bridge.update().unwrap();
+        now += time_step;
+
+        match calibrator.poll(&mut bridge, now) {
+            Ok(None) => {}
+            Ok(Some(calibration)) => panic!("unexpected result: {calibration:?}"),
+            Err(e) => break e,
+        }
+        assert!(bridge.simulation.steps < 1000, "calibration never ends");
+    };
+
+    assert!(matches!(error, CalibrationError::NoStart(_)), "{error}");
+    assert!(!bridge.simulation.controls[0].manual);
+}
+
+#[test]
+fn test_firmware_override() {
+    let mut app_state = app_state("fake");
+
+    // a changing value is written again at each cycle anyway
+    run_until_steady(&mut app_state, "linear1");
+    assert!(app_state.bridge.simulation.controls[0].manual);
+
+    // the firmware takes the control back
+    app_state.bridge.simulation.controls[0].manual = false;
+    step(&mut app_state);
+
+    assert!(app_state.bridge.simulation.controls[0].manual);
+
+    // or overwrites the value, once it was read back
+    step(&mut app_state);
+    app_state.bridge.simulation.controls[0].manual_duty = 100.0;
+    step(&mut app_state);
+
+    assert_eq!(overrides(&app_state, "control1"), 2);
+}
+
+fn overrides<H: HardwareBridge>(app_state: &AppState<H>, control_name: &str) -> u32 {
+    app_state
+        .app_graph
+        .nodes
+        .values()
+        .find_map(|node| match &node.node_type {
+            NodeType::Control(control) if control.name == control_name => Some(control.overrides),
+            _ => None,
+        })
+        .unwrap()
+}
+
+/// Read back the controls by steps of 10%, like a coarse driver.
+struct RoundingBridge(FakeHardwareBridge);
+
+impl HardwareBridge for RoundingBridge {
+    fn new() -> Result<Self, HardwareError> {
+        Ok(Self(FakeHardwareBridge::new()?))
     }
+
+    fn hardware(&self) -> &Hardware {
+        self.0.hardware()
+    }
+
+    fn take_hardware(&mut self) -> Hardware {
+        self.0.take_hardware()
+    }
+
+    fn get_sensor_value(&mut self, sensor: &HSensor) -> Result<Value, HardwareError> {
+        self.0.get_sensor_value(sensor)
+    }
+
+    fn get_control_value(&mut self, control: &HControl) -> Result<Value, HardwareError> {
+        Ok((self.0.get_control_value(control)? / 10.0).round() * 10.0)
+    }
+
+    fn set_value(&mut self, control: &HControl, value: Value) -> Result<(), HardwareError> {
+        self.0.set_value(control, value)
+    }
+
+    fn set_mode(&mut self, control: &HControl, mode: &Mode) -> Result<(), HardwareError> {
+        self.0.set_mode(control, mode)
+    }
+
+    fn get_mode(&mut self, control: &HControl) -> Result<Option<Mode>, HardwareError> {
+        self.0.get_mode(control)
+    }
+
+    fn now(&self) -> Instant {
+        self.0.now()
+    }
+
+    fn update(&mut self) -> Result<(), HardwareError> {
+        self.0.update()
+    }
+}
+
+#[test]
+fn test_read_back_rounding() {
+    let mut app_state = app_state_with("fake", RoundingBridge::new().unwrap());
+
+    run_until_steady(&mut app_state, "linear1");
+    assert_eq!(overrides(&app_state, "control1"), 0);
+
+    app_state.bridge.0.simulation.controls[0].manual_duty = 100.0;
+    step(&mut app_state);
+
+    assert_eq!(overrides(&app_state, "control1"), 1);
+}
+
+#[test]
+fn test_stall() {
+    let mut app_state = app_state("fake");
+
+    for node in app_state.app_graph.nodes.values_mut() {
+        if let NodeType::Control(control) = &mut node.node_type {
+            if control.name == "control1" {
+                control.fan = Some("fan1".into());
+                control.min = Some(60);
+            }
+        }
+    }
+
+    for _ in 0..10 {
+        step(&mut app_state);
+    }
+    assert_eq!(app_state.update.stall.alarms().count(), 0);
+
+    // the rotor is blocked
+    let fan = app_state
+        .bridge
+        .simulation
+        .fans
+        .iter_mut()
+        .find(|fan| fan.control == 0)
+        .unwrap();
+    fan.script = Some(hardware::fake_hardware::Script::Constant { value: 0.0 });
+
+    for _ in 0..app_state.update.stall.settings.cycles {
+        step(&mut app_state);
+    }
+
+    let alarms = app_state.update.stall.take_new_alarms();
+    assert_eq!(alarms.len(), 1);
+    assert_eq!(alarms[0].fan, "fan1");
+    assert_eq!(alarms[0].rpm, 0.0);
+    assert!(alarms[0].expected.is_some());
+    assert_eq!(app_state.update.stall.alarms().count(), 1);
+}
+
+#[test]
+fn test_failsafe() {
+    let mut app_state = app_state("fake");
+
+    for _ in 0..3 {
+        step(&mut app_state);
+    }
+
+    let temp = app_state
+        .app_graph
+        .nodes
+        .values()
+        .find(|node| node.name() == "temp1")
+        .and_then(|node| node.value)
+        .unwrap();
+
+    app_state.app_graph.failsafe.critical_temp = Some(temp);
+    step(&mut app_state);
+
+    assert!(app_state.update.failsafe.take_triggered().is_some());
+    assert_eq!(app_state.bridge.simulation.controls[0].manual_duty, 100.0);
+
+    // still in the hysteresis
+    app_state.app_graph.failsafe.critical_temp = Some(temp + 2.0);
+    step(&mut app_state);
+    assert!(app_state.update.failsafe.reason().is_some());
+
+    app_state.app_graph.failsafe.critical_temp = Some(temp + 50.0);
+    step(&mut app_state);
+    assert!(app_state.update.failsafe.reason().is_none());
+}
+
+#[test]
+fn test_on_exit() {
+    let mut app_state = app_state("fake");
+
+    for node in app_state.app_graph.nodes.values_mut() {
+        if let NodeType::Control(control) = &mut node.node_type {
+            if control.name == "control1" {
+                control.on_exit = OnExit::Fixed(35.0);
+            }
+        }
+    }
+
+    for _ in 0..3 {
+        step(&mut app_state);
+    }
+
+    app_state
+        .update
+        .set_on_exit(&app_state.app_graph.nodes, &mut app_state.bridge);
+    app_state.bridge.shutdown().unwrap();
+
+    let control = &app_state.bridge.simulation.controls[0];
+    assert!(control.manual);
+    assert_eq!(control.manual_duty, 35.0);
+
+    // control2 is not active, the firmware keeps it
+    assert!(!app_state.bridge.simulation.controls[1].manual);
 }
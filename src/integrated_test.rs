@@ -20,3 +20,53 @@ fn test_init() {
 
     bridge.shutdown().unwrap();
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_restore_pending_exits() {
+    use std::fs;
+
+    use data::{
+        AppState,
+        app_graph::AppGraph,
+        config::{Config, control::Control},
+        dir_manager::DirManager,
+        update::Update,
+    };
+    use hardware::{OnExit, hwmon::HwmonBridge};
+
+    init_test_logging();
+
+    let root = std::env::temp_dir().join(format!("fan-control-exits-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let hwmon0 = root.join("class/hwmon0");
+    fs::create_dir_all(&hwmon0).unwrap();
+    fs::write(hwmon0.join("name"), "it8686").unwrap();
+    fs::write(hwmon0.join("pwm1"), "128").unwrap();
+    fs::write(hwmon0.join("pwm1_enable"), "2").unwrap();
+
+    let bridge = HwmonBridge::from_root(&root.join("class")).unwrap();
+    let config = Config {
+        controls: vec![Control {
+            name: "control1".into(),
+            hardware_id: Some(bridge.hardware().controls[0].hardware_id.clone()),
+            active: true,
+            on_exit: OnExit::Fixed(40.0),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let app_graph = AppGraph::from_config(config, bridge.hardware());
+    let dir_manager = DirManager::new(&Some(root.join("config")), &None);
+
+    let app_state = AppState::new(dir_manager, bridge, app_graph, Update::new());
+
+    // like a signal, the bridge is never dropped
+    std::mem::forget(app_state);
+    hardware::restore_pending_exits();
+
+    assert_eq!(fs::read_to_string(hwmon0.join("pwm1")).unwrap(), "102");
+    assert_eq!(fs::read_to_string(hwmon0.join("pwm1_enable")).unwrap(), "1");
+
+    let _ = fs::remove_dir_all(&root);
+}
@@ -2,12 +2,20 @@
 // because it blocks all logs, from C# AND Rust
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::{env, fs};
+use std::{
+    env, fs, panic,
+    sync::{Arc, atomic::AtomicBool},
+};
 
-use args::Args;
+use args::{Args, Command};
 use clap::Parser;
 use data::{AppState, app_graph::AppGraph, dir_manager::DirManager, update::Update};
-use hardware::{self, HardwareBridge};
+use hardware::{
+    self, HardwareBridge,
+    command_sensor::CommandSensorBridge,
+    file_sensor::{self, FileSensorBridge},
+    trace::{RecordBridge, ReplayBridge},
+};
 use log::LevelFilter;
 use thiserror::Error;
 
@@ -28,6 +36,8 @@ mod integrated_test;
 pub enum Error {
     #[error(transparent)]
     Hardware(#[from] hardware::HardwareError),
+    #[error(transparent)]
+    Config(#[from] data::dir_manager::ConfigError),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -94,7 +104,46 @@ fn try_run() -> Result<()> {
 
     let dir_manager = DirManager::new(&args.config_dir_path, &args.config_name);
 
-    let bridge = hardware::new()?;
+    if let Some(trace_path) = &args.replay {
+        let bridge = ReplayBridge::from_file(trace_path)?;
+        return run(args, dir_manager, bridge);
+    }
+
+    #[cfg(feature = "fake_hardware")]
+    if let Some(inventory_path) = &args.fake_hardware {
+        unsafe {
+            env::set_var(hardware::fake_hardware::FAKE_HARDWARE_ENV, inventory_path);
+        }
+    }
+
+    // even the drop of the bridge writes to the controls
+    let _lock = dir_manager.lock_instance()?;
+
+    let mut file_sensors = file_sensor::thermal_zones();
+    if let Some(config) = dir_manager
+        .get_config_cached()
+        .or_else(|| dir_manager.get_config())
+    {
+        file_sensors.extend(config.file_sensors);
+    }
+    let bridge = FileSensorBridge::with_sensors(hardware::new()?, file_sensors);
+    let bridge =
+        CommandSensorBridge::with_sensors(bridge, dir_manager.settings().command_sensors.clone());
+
+    match &args.record {
+        Some(trace_path) => {
+            let bridge = RecordBridge::record(bridge, trace_path)?;
+            run(args, dir_manager, bridge)
+        }
+        None => run(args, dir_manager, bridge),
+    }
+}
+
+fn run<H: HardwareBridge + 'static>(
+    args: Args,
+    mut dir_manager: DirManager,
+    bridge: H,
+) -> Result<()> {
     let hardware = bridge.hardware();
 
     debug!("sensors found: {:?}", hardware);
@@ -104,34 +153,94 @@ fn try_run() -> Result<()> {
         return Ok(());
     }
 
+    if let Some(Command::Restore) = &args.command {
+        cli::restore(bridge);
+        return Ok(());
+    }
+
+    dir_manager.migrate_hardware_ids(hardware);
+
+    if let Some(Command::Calibrate {
+        control,
+        fan,
+        invert,
+    }) = &args.command
+    {
+        cli::calibrate(dir_manager, bridge, control, fan, *invert);
+        return Ok(());
+    }
+
     let app_graph = match dir_manager
         .get_config_cached()
         .or_else(|| dir_manager.get_config())
     {
-        Some(config) => AppGraph::from_config(config, hardware),
+        Some(mut config) => {
+            #[cfg(feature = "ui")]
+            let cli = args.cli;
+            #[cfg(not(feature = "ui"))]
+            let cli = true;
+
+            // the ui has its own dialog
+            if cli {
+                cli::remap_hardware(&dir_manager, &mut config, hardware);
+            }
+            AppGraph::from_config(config, hardware)
+        }
         None => AppGraph::default(hardware),
     };
 
-    let app_state = AppState {
-        dir_manager,
-        bridge,
-        app_graph,
-        update: Update::new(),
-    };
+    let mut update = Update::new();
+    update.stall.settings = dir_manager.settings().stall.clone();
+
+    let app_state = AppState::new(dir_manager, bridge, app_graph, update);
+
+    let stop = restore_on_crash();
 
     #[cfg(not(feature = "ui"))]
-    cli::run_cli(app_state);
+    cli::run_cli(app_state, stop);
     #[cfg(feature = "ui")]
     {
         match args.cli {
-            true => cli::run_cli(app_state),
-            false => ui::run_ui(app_state),
+            true => cli::run_cli(app_state, stop),
+            false => ui::run_ui(app_state, stop),
         };
     }
 
     Ok(())
 }
 
+/// The bridge gives the controls back to the firmware when it's dropped,
+/// which doesn't happen after a panic with `panic = "abort"`.
+/// A signal only sets the returned flag: the main loop stops writing to the controls,
+/// and restores them itself.
+fn restore_on_crash() -> Arc<AtomicBool> {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        // whatever the thread, the controls are only restored once
+        hardware::restore_pending_exits();
+    }));
+
+    let stop = Arc::new(AtomicBool::new(false));
+
+    #[cfg(unix)]
+    {
+        use signal_hook::consts::{SIGHUP, SIGINT, SIGQUIT, SIGTERM};
+
+        for signal in [SIGINT, SIGTERM, SIGHUP, SIGQUIT] {
+            // a second signal stops the process at once, if the main loop is stuck
+            let result = signal_hook::flag::register_conditional_shutdown(signal, 1, stop.clone())
+                .and_then(|_| signal_hook::flag::register(signal, stop.clone()));
+
+            if let Err(e) = result {
+                error!("can't register the handler of the signal {signal}: {e}");
+            }
+        }
+    }
+
+    stop
+}
+
 fn main() {
     if let Err(e) = try_run() {
         error!("{}", e);
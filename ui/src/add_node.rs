@@ -14,9 +14,14 @@ pub fn add_node_button_view(expanded: bool) -> Element<'static, AppMsg> {
             .push(add_item(NodeTypeLight::Fan, fl!("add_fan")))
             .push(add_item(NodeTypeLight::Temp, fl!("add_temp")))
             .push(add_item(NodeTypeLight::CustomTemp, fl!("add_custom_temp")))
+            .push(add_item(NodeTypeLight::Smoothing, fl!("add_smoothing")))
+            .push(add_item(NodeTypeLight::Sensor, fl!("add_sensor")))
             .push(add_item(NodeTypeLight::Linear, fl!("add_linear")))
             .push(add_item(NodeTypeLight::Target, fl!("add_target")))
+            .push(add_item(NodeTypeLight::Pid, fl!("add_pid")))
+            .push(add_item(NodeTypeLight::RpmTarget, fl!("add_rpm_target")))
             .push(add_item(NodeTypeLight::Graph, fl!("add_graph")))
+            .push(add_item(NodeTypeLight::Hysteresis, fl!("add_hysteresis")))
             .push(add_item(NodeTypeLight::Flat, fl!("add_flat")))
             .push(icon_button!("close/40").on_press(AppMsg::Toggle(ToogleMsg::CreateButton(false))))
             .into(),
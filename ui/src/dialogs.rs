@@ -1,33 +1,72 @@
+use std::{rc::Rc, time::Instant};
+
 use cosmic::{
     Element, Task,
     iced::{clipboard, theme::Palette},
+    iced_core::Length,
+    iced_widget::PickList,
     widget::{
-        button, dialog,
+        Column, Text, button, dialog,
         markdown::{self, Url},
     },
 };
-use hardware::HardwareBridge;
+use data::{
+    calibration::{Calibration, CalibrationParams, Calibrator},
+    config::Config,
+    id::Id,
+    node::NodeType,
+    remap::{Candidate, Unresolved},
+};
+use hardware::{HControl, Hardware, HardwareBridge, Mode, Value};
 
-use crate::{Ui, message::AppMsg};
+use crate::{
+    Ui, message::AppMsg, node_cache::NodesC, pick_list_utils::hardware::HardwarePickListOption,
+};
 
 #[derive(Clone, Debug)]
 pub enum Dialog {
     Flatpak,
+    Calibration(CalibrationDialog),
+    Remap(RemapDialog),
 }
 
 #[derive(Clone, Debug)]
 pub enum DialogMsg {
     Flatpak(FlatpakDialogMsg),
+    Calibration(CalibrationDialogMsg),
+    Remap(RemapDialogMsg),
 }
 
 impl Dialog {
     pub fn view(&self) -> Element<AppMsg> {
         match self {
             Dialog::Flatpak => view_flatpak_dialog(),
+            Dialog::Calibration(calibration_dialog) => view_calibration_dialog(calibration_dialog),
+            Dialog::Remap(remap_dialog) => view_remap_dialog(remap_dialog),
         }
         .map(AppMsg::Dialog)
     }
 
+    /// Some if hardware of the config was not found.
+    pub fn remap(config: Config, hardware: &Hardware) -> Option<Dialog> {
+        let unresolved = config.unresolved_hardware_ids(hardware);
+
+        if unresolved.is_empty() {
+            return None;
+        }
+
+        let choices = unresolved
+            .iter()
+            .map(|unresolved| unresolved.candidates.first().cloned())
+            .collect();
+
+        Some(Dialog::Remap(RemapDialog {
+            config,
+            unresolved,
+            choices,
+        }))
+    }
+
     pub fn update<H: HardwareBridge>(app: &mut Ui<H>, message: DialogMsg) -> Task<AppMsg> {
         match message {
             DialogMsg::Flatpak(flatpak_dialog_msg) => match flatpak_dialog_msg {
@@ -47,6 +86,10 @@ impl Dialog {
                     }
                 }
             },
+            DialogMsg::Calibration(calibration_dialog_msg) => {
+                update_calibration_dialog(app, calibration_dialog_msg)
+            }
+            DialogMsg::Remap(remap_dialog_msg) => update_remap_dialog(app, remap_dialog_msg),
         }
 
         Task::none()
@@ -94,3 +137,366 @@ fn view_flatpak_dialog() -> Element<'static, DialogMsg> {
 
     dialog.map(DialogMsg::Flatpak)
 }
+
+#[derive(Clone, Debug)]
+pub struct CalibrationDialog {
+    pub node_id: Id,
+    pub control_name: String,
+    pub fans: Vec<HardwarePickListOption>,
+    pub fan: Option<HardwarePickListOption>,
+    pub status: CalibrationStatus,
+}
+
+#[derive(Clone, Debug)]
+pub enum CalibrationStatus {
+    Idle,
+    /// duty cycle currently measured
+    Running(Value),
+    Done(Calibration),
+    Failed(String),
+}
+
+#[derive(Clone, Debug)]
+pub enum CalibrationDialogMsg {
+    Open(Id),
+    Fan(HardwarePickListOption),
+    Start,
+    Cancel,
+    Close,
+}
+
+/// Calibration in progress, advanced at each tick.
+///
+/// The hardware ref of the control node is taken during the calibration,
+/// so the update of the graph doesn't fight with the calibration.
+pub struct RunningCalibration {
+    node_id: Id,
+    calibrator: Calibrator,
+    control_h: Rc<HControl>,
+}
+
+impl<H: HardwareBridge> Ui<H> {
+    pub fn update_calibration(&mut self) {
+        let Some(running) = &mut self.calibration else {
+            return;
+        };
+
+        let status = match running
+            .calibrator
+            .poll(&mut self.app_state.bridge, Instant::now())
+        {
+            Ok(None) => CalibrationStatus::Running(running.calibrator.duty()),
+            Ok(Some(calibration)) => {
+                self.app_state
+                    .dir_manager
+                    .save_calibration(calibration.clone());
+                self.stop_calibration();
+                CalibrationStatus::Done(calibration)
+            }
+            Err(e) => {
+                self.stop_calibration();
+                CalibrationStatus::Failed(e.to_string())
+            }
+        };
+
+        if let Some(Dialog::Calibration(calibration_dialog)) = &mut self.dialog {
+            calibration_dialog.status = status;
+        }
+    }
+
+    /// Restore the original mode, and give the control back to its node.
+    pub fn stop_calibration(&mut self) {
+        let Some(mut running) = self.calibration.take() else {
+            return;
+        };
+
+        running.calibrator.cancel(&mut self.app_state.bridge);
+
+        if let Some(node) = self.app_state.app_graph.nodes.get_mut(&running.node_id) {
+            if let NodeType::Control(control) = &mut node.node_type {
+                control.control_h = Some(running.control_h);
+            }
+        }
+    }
+}
+
+fn update_calibration_dialog<H: HardwareBridge>(app: &mut Ui<H>, message: CalibrationDialogMsg) {
+    match message {
+        CalibrationDialogMsg::Open(node_id) => {
+            app.nodes_c.get_mut(&node_id).context_menu_expanded = false;
+
+            let node = app.app_state.app_graph.get(&node_id);
+            let control = node.node_type.unwrap_control_ref();
+
+            let previous = control
+                .hardware_id
+                .as_ref()
+                .and_then(|id| app.app_state.dir_manager.calibrations().get(id));
+
+            let fans: Vec<HardwarePickListOption> = app
+                .app_state
+                .bridge
+                .hardware()
+                .fans
+                .iter()
+                .map(|fan| fan.into())
+                .collect();
+
+            let fan = previous
+                .and_then(|previous| fans.iter().find(|fan| fan.id == previous.fan).cloned());
+
+            app.dialog = Some(Dialog::Calibration(CalibrationDialog {
+                node_id,
+                control_name: control.name.clone(),
+                fans,
+                fan,
+                status: match previous {
+                    Some(previous) => CalibrationStatus::Done(previous.clone()),
+                    None => CalibrationStatus::Idle,
+                },
+            }));
+        }
+        CalibrationDialogMsg::Fan(fan) => {
+            if let Some(Dialog::Calibration(calibration_dialog)) = &mut app.dialog {
+                calibration_dialog.fan = Some(fan);
+            }
+        }
+        CalibrationDialogMsg::Start => {
+            let Some(Dialog::Calibration(calibration_dialog)) = &mut app.dialog else {
+                return;
+            };
+
+            let bridge = &mut app.app_state.bridge;
+
+            let Some(fan_h) = calibration_dialog.fan.as_ref().and_then(|fan| {
+                bridge
+                    .hardware()
+                    .fans
+                    .iter()
+                    .find(|fan_h| fan_h.hardware_id == fan.id)
+                    .cloned()
+            }) else {
+                return;
+            };
+
+            let node = app.app_state.app_graph.get_mut(&calibration_dialog.node_id);
+            let control = node.node_type.unwrap_control_mut();
+
+            let Some(control_h) = control.control_h.take() else {
+                return;
+            };
+
+            // the mode set by the node, or by the firmware
+            let mode = control.mode_set.clone().unwrap_or(Mode::Auto);
+
+            match Calibrator::start(
+                bridge,
+                control_h.clone(),
+                fan_h,
+                CalibrationParams {
+                    invert: control.invert,
+                    ..Default::default()
+                },
+                mode,
+                Instant::now(),
+            ) {
+                Ok(calibrator) => {
+                    calibration_dialog.status = CalibrationStatus::Running(calibrator.duty());
+                    app.calibration = Some(RunningCalibration {
+                        node_id: calibration_dialog.node_id,
+                        calibrator,
+                        control_h,
+                    });
+                }
+                Err(e) => {
+                    control.control_h = Some(control_h);
+                    calibration_dialog.status = CalibrationStatus::Failed(e.to_string());
+                }
+            }
+        }
+        CalibrationDialogMsg::Cancel => {
+            app.stop_calibration();
+
+            if let Some(Dialog::Calibration(calibration_dialog)) = &mut app.dialog {
+                calibration_dialog.status = CalibrationStatus::Idle;
+            }
+        }
+        CalibrationDialogMsg::Close => {
+            app.stop_calibration();
+            app.dialog = None;
+        }
+    }
+}
+
+fn view_calibration_dialog(calibration_dialog: &CalibrationDialog) -> Element<'_, DialogMsg> {
+    let is_running = matches!(calibration_dialog.status, CalibrationStatus::Running(_));
+
+    let fan: Element<_> = if is_running {
+        Text::new(
+            calibration_dialog
+                .fan
+                .as_ref()
+                .map(|fan| fan.name.clone())
+                .unwrap_or_default(),
+        )
+        .into()
+    } else {
+        PickList::new(
+            calibration_dialog.fans.clone(),
+            calibration_dialog.fan.clone(),
+            CalibrationDialogMsg::Fan,
+        )
+        .placeholder(fl!("calibration_fan"))
+        .width(Length::Fill)
+        .into()
+    };
+
+    let pwm_text = |pwm: Option<Value>| match pwm {
+        Some(pwm) => format!("{pwm} %"),
+        None => fl!("none"),
+    };
+
+    let status = match &calibration_dialog.status {
+        CalibrationStatus::Idle => fl!("calibration_description"),
+        CalibrationStatus::Running(duty) => fl!("calibration_running", duty = *duty),
+        CalibrationStatus::Done(calibration) => fl!(
+            "calibration_result",
+            stop = pwm_text(calibration.stop_pwm),
+            start = pwm_text(calibration.start_pwm),
+            max = calibration.max_rpm
+        ),
+        CalibrationStatus::Failed(error) => fl!("calibration_failed", error = error.clone()),
+    };
+
+    let primary_action = if is_running {
+        button::text(fl!("calibration_cancel")).on_press(CalibrationDialogMsg::Cancel)
+    } else {
+        let start = button::text(fl!("calibration_start"));
+        match calibration_dialog.fan {
+            Some(_) => start.on_press(CalibrationDialogMsg::Start),
+            None => start,
+        }
+    };
+
+    let dialog: Element<_> = dialog()
+        .title(fl!(
+            "calibration_title",
+            name = calibration_dialog.control_name.clone()
+        ))
+        .control(Column::new().push(fan).push(Text::new(status)).spacing(10))
+        .primary_action(primary_action)
+        .secondary_action(
+            button::text(fl!("calibration_close")).on_press(CalibrationDialogMsg::Close),
+        )
+        .into();
+
+    dialog.map(DialogMsg::Calibration)
+}
+
+#[derive(Clone, Debug)]
+pub struct RemapDialog {
+    /// The config, before the hardware ids were dropped by the app graph
+    config: Config,
+    unresolved: Vec<Unresolved>,
+    /// One per unresolved hardware id, None to skip it
+    choices: Vec<Option<Candidate>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum RemapDialogMsg {
+    Choose(usize, Candidate),
+    Apply,
+    Close,
+}
+
+fn update_remap_dialog<H: HardwareBridge>(app: &mut Ui<H>, message: RemapDialogMsg) {
+    match message {
+        RemapDialogMsg::Choose(index, candidate) => {
+            if let Some(Dialog::Remap(remap_dialog)) = &mut app.dialog {
+                remap_dialog.choices[index] = Some(candidate);
+            }
+        }
+        RemapDialogMsg::Apply => {
+            let Some(Dialog::Remap(mut remap_dialog)) = app.dialog.take() else {
+                return;
+            };
+
+            let remappings = remap_dialog
+                .unresolved
+                .iter()
+                .zip(&remap_dialog.choices)
+                .filter_map(|(unresolved, choice)| {
+                    choice
+                        .as_ref()
+                        .map(|candidate| unresolved.remapping(candidate))
+                })
+                .collect::<Vec<_>>();
+
+            if !remap_dialog.config.remap_hardware_ids(&remappings) {
+                return;
+            }
+
+            app.app_state.dir_manager.remap_hardware_ids(&remappings);
+
+            app.app_state.update.set_valid_root_nodes_to_auto(
+                &mut app.app_state.app_graph.nodes,
+                &app.app_state.app_graph.root_nodes,
+                &mut app.app_state.bridge,
+            );
+            app.app_state
+                .app_graph
+                .apply_config(remap_dialog.config, app.app_state.bridge.hardware());
+            app.app_state
+                .update
+                .set_on_exit(&app.app_state.app_graph.nodes, &mut app.app_state.bridge);
+            app.nodes_c = NodesC::new(app.app_state.app_graph.nodes.values());
+
+            app.update_hardware();
+        }
+        RemapDialogMsg::Close => {
+            app.dialog = None;
+        }
+    }
+}
+
+fn view_remap_dialog(remap_dialog: &RemapDialog) -> Element<'_, DialogMsg> {
+    let mut content = Column::new()
+        .push(Text::new(fl!("remap_description")))
+        .spacing(10);
+
+    for (index, (unresolved, choice)) in remap_dialog
+        .unresolved
+        .iter()
+        .zip(&remap_dialog.choices)
+        .enumerate()
+    {
+        content = content.push(Text::new(fl!(
+            "remap_missing",
+            name = unresolved.node_name.clone(),
+            id = unresolved.hardware_id.clone()
+        )));
+
+        let choice: Element<_> = if unresolved.candidates.is_empty() {
+            Text::new(fl!("remap_no_candidate")).into()
+        } else {
+            PickList::new(
+                unresolved.candidates.clone(),
+                choice.clone(),
+                move |candidate| RemapDialogMsg::Choose(index, candidate),
+            )
+            .placeholder(fl!("remap_skip"))
+            .width(Length::Fill)
+            .into()
+        };
+        content = content.push(choice);
+    }
+
+    let dialog: Element<_> = dialog()
+        .title(fl!("remap_title"))
+        .control(content)
+        .primary_action(button::text(fl!("remap_apply")).on_press(RemapDialogMsg::Apply))
+        .secondary_action(button::text(fl!("remap_ignore")).on_press(RemapDialogMsg::Close))
+        .into();
+
+    dialog.map(DialogMsg::Remap)
+}
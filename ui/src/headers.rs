@@ -182,9 +182,36 @@ fn config_choice_line<'a>(optional_name: Option<String>) -> Element<'a, AppMsg>
         .into()
 }
 
-pub fn header_end<'a>() -> Vec<Element<'a, AppMsg>> {
+pub fn header_end<'a, H: HardwareBridge>(app_state: &'a AppState<H>) -> Vec<Element<'a, AppMsg>> {
     let mut elems = vec![];
 
+    let alarms = app_state
+        .update
+        .failsafe
+        .reason()
+        .map(|reason| reason.to_string())
+        .into_iter()
+        .chain(
+            app_state
+                .update
+                .stall
+                .alarms()
+                .map(|alarm| alarm.to_string()),
+        )
+        .map(|alarm| Text::new(alarm).into())
+        .collect::<Vec<Element<_>>>();
+
+    if !alarms.is_empty() {
+        elems.push(
+            tooltip(
+                icon!("warning/24"),
+                Column::with_children(alarms),
+                tooltip::Position::Bottom,
+            )
+            .into(),
+        );
+    }
+
     let settings_button = icon_button!("settings/40")
         .on_press(AppMsg::Toggle(ToogleMsg::Settings))
         .height(ICON_LENGHT)
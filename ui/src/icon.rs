@@ -44,10 +44,15 @@ macro_rules! node_icon_handle {
             NodeTypeLight::Fan => icon_handle!("toys_fan/24"),
             NodeTypeLight::Temp => icon_handle!("thermometer/24"),
             NodeTypeLight::CustomTemp => icon_handle!("thermostat/24"),
+            NodeTypeLight::Sensor => icon_handle!("bolt/24"),
             NodeTypeLight::Graph => icon_handle!("psychology/24"),
             NodeTypeLight::Flat => icon_handle!("horizontal_rule/24"),
             NodeTypeLight::Linear => icon_handle!("linear/24"),
             NodeTypeLight::Target => icon_handle!("my_location/24"),
+            NodeTypeLight::Hysteresis => icon_handle!("hysteresis/24"),
+            NodeTypeLight::Smoothing => icon_handle!("smoothing/24"),
+            NodeTypeLight::Pid => icon_handle!("pid/24"),
+            NodeTypeLight::RpmTarget => icon_handle!("rpm_target/24"),
         }
     }};
 }
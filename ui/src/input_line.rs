@@ -6,6 +6,8 @@ use cosmic::{
     widget::{Column, Row, Space, Text, TextInput},
 };
 
+use hardware::Value;
+
 use crate::{ModifNodeMsg, icon_button};
 
 pub trait MyFrom<T> {
@@ -27,15 +29,47 @@ impl MyFrom<&str> for Option<u8> {
     }
 }
 
-#[derive(PartialEq, Eq)]
+impl MyFrom<i32> for u32 {
+    fn from(value: i32) -> Self {
+        value as u32
+    }
+}
+
+impl MyFrom<&str> for Option<u32> {
+    fn from(value: &str) -> Self {
+        match value.parse::<u32>() {
+            Ok(value) => Some(value),
+            Err(_) => None,
+        }
+    }
+}
+
+impl MyFrom<i32> for Value {
+    fn from(value: i32) -> Self {
+        value.into()
+    }
+}
+
+impl MyFrom<&str> for Option<Value> {
+    fn from(value: &str) -> Self {
+        match value.parse::<Value>() {
+            Ok(value) if value.is_finite() => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum InputLineUnit {
     Celcius,
     Porcentage,
+    Second,
+    Other(&'static str),
 }
 
 pub fn input_line<'a, V, F>(
     info: String,
-    value: &'a V,
+    value: V,
     cached_value: &'a str,
     unit: InputLineUnit,
     range: Range<V>,
@@ -45,12 +79,12 @@ where
     V: Add<V, Output = V>,
     V: Sub<V, Output = V>,
     V: MyFrom<i32>,
-    V: PartialOrd + Clone + ToString + PartialEq,
+    V: 'a + PartialOrd + Clone + ToString + PartialEq,
     Option<V>: for<'b> MyFrom<&'b str>,
     F: 'a + Fn(V, String) -> ModifNodeMsg,
 {
     // `map_value` is moved in `on_input` so we procuce buttons messages before
-    let plus_message = if range.end > *value {
+    let plus_message = if range.end > value {
         let new_value = value.clone() + MyFrom::from(1);
         let new_cached_value = new_value.to_string();
         Some(map_value(new_value, new_cached_value))
@@ -58,7 +92,7 @@ where
         None
     };
 
-    let sub_message = if range.start < *value {
+    let sub_message = if range.start < value {
         let new_value = value.clone() - MyFrom::from(1);
         let new_cached_value = new_value.to_string();
         Some(map_value(new_value, new_cached_value))
@@ -66,6 +100,11 @@ where
         None
     };
 
+    let is_error = match <Option<V> as MyFrom<_>>::from(cached_value) {
+        Some(value_from_string) => value != value_from_string,
+        None => true,
+    };
+
     let mut input = TextInput::new(fl!("value"), cached_value)
         .on_input(move |s| {
             let final_value = match <Option<V> as MyFrom<_>>::from(&s) {
@@ -80,18 +119,15 @@ where
         })
         .width(Length::Fixed(45.0));
 
-    let is_error = match <Option<V> as MyFrom<_>>::from(cached_value) {
-        Some(value_from_string) => value != &value_from_string,
-        None => true,
-    };
-
     if is_error {
         input = input.error("this value is invalid");
     }
 
     let unit_text = match unit {
-        InputLineUnit::Celcius => " °C",
-        InputLineUnit::Porcentage => " %",
+        InputLineUnit::Celcius => " °C".to_owned(),
+        InputLineUnit::Porcentage => " %".to_owned(),
+        InputLineUnit::Second => " s".to_owned(),
+        InputLineUnit::Other(unit) => format!(" {unit}"),
     };
 
     let icon_lenght = Length::Fixed(30.0);
@@ -8,7 +8,7 @@ use cosmic::{
         scrollable::{Direction, Scrollbar},
     },
     style, theme,
-    widget::{Column, Container, Row, Slider, Space, Text, TextInput},
+    widget::{Column, Container, Row, Slider, Space, Text, TextInput, tooltip},
 };
 use data::{
     app_graph::Nodes,
@@ -16,23 +16,33 @@ use data::{
         control::Control,
         custom_temp::{CustomTemp, CustomTempKind},
         flat::Flat,
+        hysteresis::Hysteresis,
         linear::Linear,
+        pid::Pid,
+        rpm_target::RpmTarget,
+        sensor::Sensor,
+        smoothing::{Smoothing, SmoothingKind},
         target::Target,
     },
     node::{Input, Node, NodeTypeLight, ValueKind},
 };
-use hardware::{HItem, Hardware};
+use hardware::{HItem, Hardware, SensorKind};
 
 use crate::{
+    dialogs::{CalibrationDialogMsg, DialogMsg},
     graph::graph_view,
+    icon,
     icon::icon_from_handle,
     icon_button,
     input_line::{InputLineUnit, input_line},
     message::{
-        AppMsg, ControlMsg, CustomTempMsg, FlatMsg, LinearMsg, ModifNodeMsg, TargetMsg, ToogleMsg,
+        AppMsg, ControlMsg, CustomTempMsg, FlatMsg, HysteresisMsg, LinearMsg, ModifNodeMsg, PidMsg,
+        RpmTargetMsg, SensorMsg, SmoothingMsg, TargetMsg, ToogleMsg,
     },
     my_widgets::{self, drop_down::DropDown, offset::Offset},
-    node_cache::{LinearC, NodeC, NodesC, TargetC},
+    node_cache::{
+        ControlC, HysteresisC, LinearC, NodeC, NodesC, PidC, RpmTargetC, SmoothingC, TargetC,
+    },
     node_icon_handle,
     pick_list_utils::{self, MyOption},
 };
@@ -55,12 +65,15 @@ pub fn items_view<'a>(
         match node.node_type.to_light() {
             NodeTypeLight::Control => controls.push(content),
             NodeTypeLight::Fan => fans.push(content),
-            NodeTypeLight::Temp => temps.push(content),
+            NodeTypeLight::Temp | NodeTypeLight::Sensor => temps.push(content),
             NodeTypeLight::Graph
             | NodeTypeLight::Flat
             | NodeTypeLight::Linear
-            | NodeTypeLight::Target => behaviors.push(content),
-            NodeTypeLight::CustomTemp => custom_temps.push(content),
+            | NodeTypeLight::Target
+            | NodeTypeLight::Pid
+            | NodeTypeLight::RpmTarget
+            | NodeTypeLight::Hysteresis => behaviors.push(content),
+            NodeTypeLight::CustomTemp | NodeTypeLight::Smoothing => custom_temps.push(content),
         }
     }
 
@@ -126,11 +139,21 @@ fn item_view<'a>(
             .into()
     }
 
-    let overlay = Container::new(Column::new().push(action_line(
+    let mut actions = Column::new().push(action_line(
         fl!("delete"),
         ModifNodeMsg::Delete.to_app(node.id),
-    )))
-    .class(theme::Container::Dropdown);
+    ));
+
+    if let data::node::NodeType::Control(control) = &node.node_type {
+        if control.control_h.is_some() {
+            actions = actions.push(action_line(
+                fl!("calibrate"),
+                AppMsg::Dialog(DialogMsg::Calibration(CalibrationDialogMsg::Open(node.id))),
+            ));
+        }
+    }
+
+    let overlay = Container::new(actions).class(theme::Container::Dropdown);
 
     let context_menu = DropDown::new(
         icon_button!("more_vert/24")
@@ -151,10 +174,17 @@ fn item_view<'a>(
         .align_y(Alignment::Center);
 
     let node_specific_content = match &node.node_type {
-        data::node::NodeType::Control(control) => control_view(node, control, nodes, hardware),
+        data::node::NodeType::Control(control) => control_view(
+            node,
+            control,
+            node_c.node_type_c.unwrap_control_ref(),
+            nodes,
+            hardware,
+        ),
         data::node::NodeType::Fan(_fan) => fan_view(node, hardware),
         data::node::NodeType::Temp(_temp) => temp_view(node, hardware),
         data::node::NodeType::CustomTemp(custom_temp) => custom_temp_view(node, custom_temp, nodes),
+        data::node::NodeType::Sensor(sensor) => sensor_view(node, sensor, hardware),
         data::node::NodeType::Graph(graph) => {
             graph_view(node, graph, node_c.node_type_c.unwrap_graph_ref(), nodes)
         }
@@ -165,6 +195,27 @@ fn item_view<'a>(
         data::node::NodeType::Target(target) => {
             target_view(node, target, node_c.node_type_c.unwrap_target_ref(), nodes)
         }
+        data::node::NodeType::Hysteresis(hysteresis) => hysteresis_view(
+            node,
+            hysteresis,
+            node_c.node_type_c.unwrap_hysteresis_ref(),
+            nodes,
+        ),
+        data::node::NodeType::Smoothing(smoothing) => smoothing_view(
+            node,
+            smoothing,
+            node_c.node_type_c.unwrap_smoothing_ref(),
+            nodes,
+        ),
+        data::node::NodeType::Pid(pid) => {
+            pid_view(node, pid, node_c.node_type_c.unwrap_pid_ref(), nodes)
+        }
+        data::node::NodeType::RpmTarget(rpm_target) => rpm_target_view(
+            node,
+            rpm_target,
+            node_c.node_type_c.unwrap_rpm_target_ref(),
+            nodes,
+        ),
     };
 
     let content = Column::new()
@@ -204,6 +255,7 @@ fn pick_hardware<'a, H: HItem>(
 fn control_view<'a>(
     node: &'a Node,
     control: &'a Control,
+    control_c: &'a ControlC,
     nodes: &'a Nodes,
     hardware: &'a Hardware,
 ) -> Element<'a, AppMsg> {
@@ -217,11 +269,45 @@ fn control_view<'a>(
     .width(Length::Fill)
     .into();
 
+    let mut fan_options = vec![MyOption::None];
+    fan_options.extend(nodes.values().filter_map(|n| match &n.node_type {
+        data::node::NodeType::Fan(fan) => Some(MyOption::Some(fan.name.clone())),
+        _ => None,
+    }));
+    let current_fan: MyOption<String> = match &control.fan {
+        Some(fan) => MyOption::Some(fan.clone()),
+        None => MyOption::None,
+    };
+
+    let pick_fan = Row::new()
+        .push(Text::new(fl!("control_fan")))
+        .push(Space::new(Length::Fixed(10.0), Length::Fixed(0.0)))
+        .push(
+            PickList::new(fan_options, Some(current_fan), |fan| {
+                ModifNodeMsg::Control(ControlMsg::Fan(fan.into())).to_app(node.id)
+            })
+            .width(Length::Fill),
+        )
+        .align_y(Alignment::Center)
+        .into();
+
+    let mut value_row = Row::new().push(Text::new(node.value_text(&ValueKind::Porcentage)));
+
+    // the firmware took the control back
+    if control.overrides > 0 {
+        value_row = value_row
+            .push(Space::new(Length::Fixed(5.0), Length::Fixed(0.0)))
+            .push(tooltip(
+                icon!("warning/24"),
+                Text::new(fl!("firmware_override", count = control.overrides)),
+                tooltip::Position::Bottom,
+            ));
+    }
+
     let content = vec![
         pick_hardware(node, &hardware.controls, true),
         pick_input,
-        Row::new()
-            .push(Text::new(node.value_text(&ValueKind::Porcentage)))
+        value_row
             .push(Space::new(Length::Fill, Length::Fixed(0.0)))
             .push(Toggler::new(control.active).on_toggle(|is_active| {
                 ModifNodeMsg::Control(ControlMsg::Active(is_active)).to_app(node.id)
@@ -229,6 +315,79 @@ fn control_view<'a>(
             .align_y(Alignment::Center)
             .width(Length::Fill)
             .into(),
+        input_line(
+            fl!("min_speed"),
+            control.min(),
+            &control_c.min,
+            InputLineUnit::Porcentage,
+            0..control.max(),
+            |val, cached_val| ModifNodeMsg::Control(ControlMsg::Min(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("max_speed"),
+            control.max(),
+            &control_c.max,
+            InputLineUnit::Porcentage,
+            control.min()..101,
+            |val, cached_val| ModifNodeMsg::Control(ControlMsg::Max(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("step"),
+            control.step(),
+            &control_c.step,
+            InputLineUnit::Porcentage,
+            1..101,
+            |val, cached_val| ModifNodeMsg::Control(ControlMsg::Step(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("zero_rpm"),
+            control.zero_rpm(),
+            &control_c.zero_rpm,
+            InputLineUnit::Porcentage,
+            0..101,
+            |val, cached_val| ModifNodeMsg::Control(ControlMsg::ZeroRpm(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("kick"),
+            control.kick(),
+            &control_c.kick,
+            InputLineUnit::Porcentage,
+            0..101,
+            |val, cached_val| ModifNodeMsg::Control(ControlMsg::Kick(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("ramp_up"),
+            control.ramp_up(),
+            &control_c.ramp_up,
+            InputLineUnit::Other("%/s"),
+            0.0..101.0,
+            |val, cached_val| ModifNodeMsg::Control(ControlMsg::RampUp(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("ramp_down"),
+            control.ramp_down(),
+            &control_c.ramp_down,
+            InputLineUnit::Other("%/s"),
+            0.0..101.0,
+            |val, cached_val| ModifNodeMsg::Control(ControlMsg::RampDown(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        pick_fan,
+        Row::new()
+            .push(Text::new(fl!("invert")))
+            .push(Space::new(Length::Fill, Length::Fixed(0.0)))
+            .push(Toggler::new(control.invert).on_toggle(|invert| {
+                ModifNodeMsg::Control(ControlMsg::Invert(invert)).to_app(node.id)
+            }))
+            .align_y(Alignment::Center)
+            .width(Length::Fill)
+            .into(),
     ];
 
     Column::with_children(content).into()
@@ -252,6 +411,32 @@ fn temp_view<'a>(node: &'a Node, hardware: &'a Hardware) -> Element<'a, AppMsg>
     Column::with_children(content).into()
 }
 
+fn sensor_view<'a>(
+    node: &'a Node,
+    sensor: &'a Sensor,
+    hardware: &'a Hardware,
+) -> Element<'a, AppMsg> {
+    let kind_options = SensorKind::VALUES
+        .iter()
+        .filter(|k| &sensor.kind != *k)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let pick_kind = PickList::new(kind_options, Some(sensor.kind), |k| {
+        ModifNodeMsg::Sensor(SensorMsg::Kind(k)).to_app(node.id)
+    })
+    .width(Length::Fill)
+    .into();
+
+    let content = vec![
+        pick_kind,
+        pick_hardware(node, hardware.sensors(sensor.kind), false),
+        Text::new(node.value_text(&ValueKind::Sensor(sensor.kind))).into(),
+    ];
+
+    Column::with_children(content).into()
+}
+
 fn custom_temp_view<'a>(
     node: &'a Node,
     custom_temp: &'a CustomTemp,
@@ -360,16 +545,16 @@ fn linear_view<'a>(
         Text::new(node.value_text(&ValueKind::Porcentage)).into(),
         input_line(
             fl!("min_temp"),
-            &linear.min_temp,
+            linear.min_temp,
             &linear_c.min_temp,
             InputLineUnit::Celcius,
-            0..linear.max_temp,
+            0.0..linear.max_temp,
             |val, cached_val| ModifNodeMsg::Linear(LinearMsg::MinTemp(val, cached_val)),
         )
         .map(|m| m.to_app(node.id)),
         input_line(
             fl!("min_speed"),
-            &linear.min_speed,
+            linear.min_speed,
             &linear_c.min_speed,
             InputLineUnit::Porcentage,
             0..linear.max_speed,
@@ -378,16 +563,16 @@ fn linear_view<'a>(
         .map(|m| m.to_app(node.id)),
         input_line(
             fl!("max_temp"),
-            &linear.max_temp,
+            linear.max_temp,
             &linear_c.max_temp,
             InputLineUnit::Celcius,
-            linear.min_temp..255,
+            linear.min_temp..255.0,
             |val, cached_val| ModifNodeMsg::Linear(LinearMsg::MaxTemp(val, cached_val)),
         )
         .map(|m| m.to_app(node.id)),
         input_line(
             fl!("max_speed"),
-            &linear.max_speed,
+            linear.max_speed,
             &linear_c.max_speed,
             InputLineUnit::Porcentage,
             linear.min_speed..101,
@@ -419,16 +604,16 @@ fn target_view<'a>(
         Text::new(node.value_text(&ValueKind::Porcentage)).into(),
         input_line(
             fl!("idle_temp"),
-            &target.idle_temp,
+            target.idle_temp,
             &target_c.idle_temp,
             InputLineUnit::Celcius,
-            0..255,
+            0.0..255.0,
             |val, cached_val| ModifNodeMsg::Target(TargetMsg::IdleTemp(val, cached_val)),
         )
         .map(|m| m.to_app(node.id)),
         input_line(
             fl!("idle_speed"),
-            &target.idle_speed,
+            target.idle_speed,
             &target_c.idle_speed,
             InputLineUnit::Porcentage,
             0..101,
@@ -437,16 +622,16 @@ fn target_view<'a>(
         .map(|m| m.to_app(node.id)),
         input_line(
             fl!("load_temp"),
-            &target.load_temp,
+            target.load_temp,
             &target_c.load_temp,
             InputLineUnit::Celcius,
-            0..255,
+            0.0..255.0,
             |val, cached_val| ModifNodeMsg::Target(TargetMsg::LoadTemp(val, cached_val)),
         )
         .map(|m| m.to_app(node.id)),
         input_line(
             fl!("load_speed"),
-            &target.load_speed,
+            target.load_speed,
             &target_c.load_speed,
             InputLineUnit::Porcentage,
             0..101,
@@ -457,3 +642,343 @@ fn target_view<'a>(
 
     Column::with_children(content).into()
 }
+
+fn hysteresis_view<'a>(
+    node: &'a Node,
+    hysteresis: &'a Hysteresis,
+    hysteresis_c: &'a HysteresisC,
+    nodes: &'a Nodes,
+) -> Element<'a, AppMsg> {
+    let input_options =
+        pick_list_utils::input::optional_availlable_inputs(nodes, node, hysteresis.input.is_some());
+    let current_input: MyOption<Input> = hysteresis.input.clone().into();
+    let pick_input = PickList::new(input_options, Some(current_input), |input| {
+        ModifNodeMsg::ReplaceInput(input.into()).to_app(node.id)
+    })
+    .width(Length::Fill)
+    .into();
+
+    // the unit of the deltas is the one of the input
+    let value_kind = input_value_kind(node, nodes);
+    let delta_unit = match &value_kind {
+        ValueKind::Celsius => InputLineUnit::Celcius,
+        ValueKind::Sensor(kind) => InputLineUnit::Other(kind.unit()),
+        _ => InputLineUnit::Porcentage,
+    };
+
+    let content = vec![
+        pick_input,
+        Text::new(node.value_text(&value_kind)).into(),
+        input_line(
+            fl!("up_delta"),
+            hysteresis.up_delta,
+            &hysteresis_c.up_delta,
+            delta_unit,
+            0.0..255.0,
+            |val, cached_val| ModifNodeMsg::Hysteresis(HysteresisMsg::UpDelta(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("down_delta"),
+            hysteresis.down_delta,
+            &hysteresis_c.down_delta,
+            delta_unit,
+            0.0..255.0,
+            |val, cached_val| ModifNodeMsg::Hysteresis(HysteresisMsg::DownDelta(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("min_interval"),
+            hysteresis.min_interval,
+            &hysteresis_c.min_interval,
+            InputLineUnit::Second,
+            0..3600,
+            |val, cached_val| ModifNodeMsg::Hysteresis(HysteresisMsg::MinInterval(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+    ];
+
+    Column::with_children(content).into()
+}
+
+fn smoothing_view<'a>(
+    node: &'a Node,
+    smoothing: &'a Smoothing,
+    smoothing_c: &'a SmoothingC,
+    nodes: &'a Nodes,
+) -> Element<'a, AppMsg> {
+    let kind_options = SmoothingKind::VALUES
+        .iter()
+        .filter(|k| &smoothing.kind != *k)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let pick_kind = PickList::new(kind_options, Some(smoothing.kind.clone()), |k| {
+        ModifNodeMsg::Smoothing(SmoothingMsg::Kind(k)).to_app(node.id)
+    })
+    .width(Length::Fill)
+    .into();
+
+    let input_options =
+        pick_list_utils::input::optional_availlable_inputs(nodes, node, smoothing.input.is_some());
+    let current_input: MyOption<Input> = smoothing.input.clone().into();
+    let pick_input = PickList::new(input_options, Some(current_input), |input| {
+        ModifNodeMsg::ReplaceInput(input.into()).to_app(node.id)
+    })
+    .width(Length::Fill)
+    .into();
+
+    let parameter = match smoothing.kind {
+        SmoothingKind::Average | SmoothingKind::Median => input_line(
+            fl!("samples"),
+            smoothing.samples,
+            &smoothing_c.samples,
+            InputLineUnit::Other(""),
+            1..101,
+            |val, cached_val| ModifNodeMsg::Smoothing(SmoothingMsg::Samples(val, cached_val)),
+        ),
+        SmoothingKind::Exponential => input_line(
+            fl!("time_constant"),
+            smoothing.time_constant,
+            &smoothing_c.time_constant,
+            InputLineUnit::Second,
+            0.0..3600.0,
+            |val, cached_val| ModifNodeMsg::Smoothing(SmoothingMsg::TimeConstant(val, cached_val)),
+        ),
+    };
+
+    let content = vec![
+        pick_kind,
+        pick_input,
+        Text::new(node.value_text(&input_value_kind(node, nodes))).into(),
+        parameter.map(|m| m.to_app(node.id)),
+    ];
+
+    Column::with_children(content).into()
+}
+
+fn pid_view<'a>(
+    node: &'a Node,
+    pid: &'a Pid,
+    pid_c: &'a PidC,
+    nodes: &'a Nodes,
+) -> Element<'a, AppMsg> {
+    let input_options =
+        pick_list_utils::input::optional_availlable_inputs(nodes, node, pid.input.is_some());
+    let current_input: MyOption<Input> = pid.input.clone().into();
+    let pick_input = PickList::new(input_options, Some(current_input), |input| {
+        ModifNodeMsg::ReplaceInput(input.into()).to_app(node.id)
+    })
+    .width(Length::Fill)
+    .into();
+
+    let content = vec![
+        pick_input,
+        Text::new(node.value_text(&ValueKind::Porcentage)).into(),
+        input_line(
+            fl!("setpoint"),
+            pid.setpoint,
+            &pid_c.setpoint,
+            InputLineUnit::Celcius,
+            0.0..255.0,
+            |val, cached_val| ModifNodeMsg::Pid(PidMsg::Setpoint(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("kp"),
+            pid.kp,
+            &pid_c.kp,
+            InputLineUnit::Other("%/°C"),
+            0.0..1000.0,
+            |val, cached_val| ModifNodeMsg::Pid(PidMsg::Kp(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("ki"),
+            pid.ki,
+            &pid_c.ki,
+            InputLineUnit::Other("%/°C/s"),
+            0.0..1000.0,
+            |val, cached_val| ModifNodeMsg::Pid(PidMsg::Ki(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("kd"),
+            pid.kd,
+            &pid_c.kd,
+            InputLineUnit::Other("%·s/°C"),
+            0.0..1000.0,
+            |val, cached_val| ModifNodeMsg::Pid(PidMsg::Kd(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("min_speed"),
+            pid.min_speed,
+            &pid_c.min_speed,
+            InputLineUnit::Porcentage,
+            0..pid.max_speed,
+            |val, cached_val| ModifNodeMsg::Pid(PidMsg::MinSpeed(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("max_speed"),
+            pid.max_speed,
+            &pid_c.max_speed,
+            InputLineUnit::Porcentage,
+            pid.min_speed..101,
+            |val, cached_val| ModifNodeMsg::Pid(PidMsg::MaxSpeed(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("derivative_filter"),
+            pid.derivative_filter,
+            &pid_c.derivative_filter,
+            InputLineUnit::Second,
+            0.0..3600.0,
+            |val, cached_val| ModifNodeMsg::Pid(PidMsg::DerivativeFilter(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+    ];
+
+    Column::with_children(content).into()
+}
+
+fn rpm_target_view<'a>(
+    node: &'a Node,
+    rpm_target: &'a RpmTarget,
+    rpm_target_c: &'a RpmTargetC,
+    nodes: &'a Nodes,
+) -> Element<'a, AppMsg> {
+    let is_fan = |input: &Input| {
+        nodes
+            .get(&input.id)
+            .is_some_and(|n| n.node_type.to_light() == NodeTypeLight::Fan)
+    };
+
+    let mut fan_options = vec![MyOption::None];
+    fan_options.extend(
+        pick_list_utils::input::availlable_inputs(nodes, node)
+            .filter(is_fan)
+            .map(MyOption::Some),
+    );
+    let current_fan: MyOption<Input> = rpm_target.fan.clone().into();
+
+    let pick_fan = Row::new()
+        .push(Text::new(fl!("control_fan")))
+        .push(Space::new(Length::Fixed(10.0), Length::Fixed(0.0)))
+        .push(
+            PickList::new(fan_options, Some(current_fan), |fan| {
+                ModifNodeMsg::RpmTarget(RpmTargetMsg::Fan(fan.into())).to_app(node.id)
+            })
+            .width(Length::Fill),
+        )
+        .align_y(Alignment::Center)
+        .into();
+
+    let mut target_options = vec![MyOption::None];
+    target_options.extend(
+        pick_list_utils::input::availlable_inputs(nodes, node)
+            .filter(|input| !is_fan(input))
+            .map(MyOption::Some),
+    );
+    let current_target: MyOption<Input> = rpm_target.target.clone().into();
+
+    let pick_target = Row::new()
+        .push(Text::new(fl!("target_input")))
+        .push(Space::new(Length::Fixed(10.0), Length::Fixed(0.0)))
+        .push(
+            PickList::new(target_options, Some(current_target), |target| {
+                ModifNodeMsg::RpmTarget(RpmTargetMsg::Target(target.into())).to_app(node.id)
+            })
+            .width(Length::Fill),
+        )
+        .align_y(Alignment::Center)
+        .into();
+
+    let mut content = vec![
+        pick_fan,
+        pick_target,
+        Text::new(node.value_text(&ValueKind::Porcentage)).into(),
+    ];
+
+    // with a target input, the target is a percentage of max RPM
+    if rpm_target.target.is_none() {
+        content.push(
+            input_line(
+                fl!("target_rpm"),
+                rpm_target.rpm,
+                &rpm_target_c.rpm,
+                InputLineUnit::Other("RPM"),
+                0..rpm_target.max_rpm,
+                |val, cached_val| ModifNodeMsg::RpmTarget(RpmTargetMsg::Rpm(val, cached_val)),
+            )
+            .map(|m| m.to_app(node.id)),
+        );
+    }
+
+    content.extend([
+        input_line(
+            fl!("max_rpm"),
+            rpm_target.max_rpm,
+            &rpm_target_c.max_rpm,
+            InputLineUnit::Other("RPM"),
+            1..100000,
+            |val, cached_val| ModifNodeMsg::RpmTarget(RpmTargetMsg::MaxRpm(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("gain"),
+            rpm_target.gain,
+            &rpm_target_c.gain,
+            InputLineUnit::Other("%/100 RPM/s"),
+            0.0..100.0,
+            |val, cached_val| ModifNodeMsg::RpmTarget(RpmTargetMsg::Gain(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("tolerance"),
+            rpm_target.tolerance,
+            &rpm_target_c.tolerance,
+            InputLineUnit::Other("RPM"),
+            0..1000,
+            |val, cached_val| ModifNodeMsg::RpmTarget(RpmTargetMsg::Tolerance(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("min_speed"),
+            rpm_target.min_speed,
+            &rpm_target_c.min_speed,
+            InputLineUnit::Porcentage,
+            0..rpm_target.max_speed,
+            |val, cached_val| ModifNodeMsg::RpmTarget(RpmTargetMsg::MinSpeed(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+        input_line(
+            fl!("max_speed"),
+            rpm_target.max_speed,
+            &rpm_target_c.max_speed,
+            InputLineUnit::Porcentage,
+            rpm_target.min_speed..101,
+            |val, cached_val| ModifNodeMsg::RpmTarget(RpmTargetMsg::MaxSpeed(val, cached_val)),
+        )
+        .map(|m| m.to_app(node.id)),
+    ]);
+
+    Column::with_children(content).into()
+}
+
+/// Kind of the values of a node which passes on the values of its input.
+fn input_value_kind(node: &Node, nodes: &Nodes) -> ValueKind {
+    let Some(input) = node.inputs.first().and_then(|input| nodes.get(&input.id)) else {
+        return ValueKind::Porcentage;
+    };
+
+    match &input.node_type {
+        data::node::NodeType::Temp(_) | data::node::NodeType::CustomTemp(_) => ValueKind::Celsius,
+        data::node::NodeType::Sensor(sensor) => ValueKind::Sensor(sensor.kind),
+        data::node::NodeType::Smoothing(_) | data::node::NodeType::Hysteresis(_) => {
+            input_value_kind(input, nodes)
+        }
+        _ => ValueKind::Porcentage,
+    }
+}
@@ -1,4 +1,10 @@
-use std::time::Duration;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 
 use data::{
     AppState,
@@ -7,10 +13,10 @@ use data::{
     settings::AppTheme,
     utils::RemoveElem,
 };
-use dialogs::Dialog;
+use dialogs::{Dialog, RunningCalibration};
 use drawer::{Drawer, about};
 use graph::GraphWindow;
-use hardware::{HardwareBridge, Mode};
+use hardware::{HardwareBridge, Mode, OnExit};
 use item::items_view;
 use message::{ConfigMsg, ModifNodeMsg, SettingsMsg, ToogleMsg};
 use node_cache::{NodeC, NodesC};
@@ -34,7 +40,10 @@ use cosmic::{
     },
 };
 
-use crate::message::{AppMsg, ControlMsg, CustomTempMsg, FlatMsg, LinearMsg, TargetMsg};
+use crate::message::{
+    AppMsg, ControlMsg, CustomTempMsg, FlatMsg, HysteresisMsg, LinearMsg, PidMsg, RpmTargetMsg,
+    SensorMsg, SmoothingMsg, TargetMsg,
+};
 
 use crate::add_node::add_node_button_view;
 
@@ -63,11 +72,12 @@ impl<H: HardwareBridge> CosmicFlags for Flags<H> {
     type Args = Vec<String>;
 }
 
-pub fn run_ui<H: HardwareBridge + 'static>(app_state: AppState<H>) {
+/// `stop`: set on a signal, the controls are restored at the next update.
+pub fn run_ui<H: HardwareBridge + 'static>(app_state: AppState<H>, stop: Arc<AtomicBool>) {
     let settings = cosmic::app::Settings::default()
         .theme(to_cosmic_theme(&app_state.dir_manager.settings().theme));
 
-    let flags = Flags { app_state };
+    let flags = Flags { app_state, stop };
 
     if let Err(e) = cosmic::app::run::<Ui<H>>(settings, flags) {
         error!("error while running ui: {}", e);
@@ -77,6 +87,7 @@ pub fn run_ui<H: HardwareBridge + 'static>(app_state: AppState<H>) {
 
 struct Flags<H: HardwareBridge> {
     app_state: AppState<H>,
+    stop: Arc<AtomicBool>,
 }
 
 struct Ui<H: HardwareBridge> {
@@ -90,7 +101,9 @@ struct Ui<H: HardwareBridge> {
     graph_window: Option<GraphWindow>,
     toasts: Toasts<AppMsg>,
     dialog: Option<Dialog>,
+    calibration: Option<RunningCalibration>,
     drawer: Option<Drawer>,
+    stop: Arc<AtomicBool>,
 }
 
 impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
@@ -117,12 +130,19 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
             .current_config_text()
             .to_owned();
 
-        let dialog = if cfg!(FAN_CONTROL_FORMAT = "flatpak")
-            && app_state.dir_manager.state().show_flatpak_dialog
+        let dialog = match app_state
+            .dir_manager
+            .get_config_cached()
+            .or_else(|| app_state.dir_manager.get_config())
+            .and_then(|config| Dialog::remap(config, app_state.bridge.hardware()))
         {
-            Some(Dialog::Flatpak)
-        } else {
-            None
+            Some(remap_dialog) => Some(remap_dialog),
+            None if cfg!(FAN_CONTROL_FORMAT = "flatpak")
+                && app_state.dir_manager.state().show_flatpak_dialog =>
+            {
+                Some(Dialog::Flatpak)
+            }
+            None => None,
         };
 
         let ui_state = Ui {
@@ -136,7 +156,9 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
             graph_window: None,
             toasts: Toasts::new(AppMsg::RemoveToast),
             dialog,
+            calibration: None,
             drawer: None,
+            stop: flags.stop,
         };
 
         let commands = Task::batch([cosmic::task::message(AppMsg::Tick)]);
@@ -149,7 +171,41 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
 
         match message {
             AppMsg::Tick => {
+                if self.stop.load(Ordering::Relaxed) {
+                    info!("Stop requested, restoring the controls.");
+                    self.on_app_exit();
+                    // the bridge is not dropped by process::exit
+                    hardware::restore_pending_exits();
+                    std::process::exit(0);
+                }
+
                 self.update_hardware();
+                self.update_calibration();
+
+                let messages = self
+                    .app_state
+                    .update
+                    .failsafe
+                    .take_triggered()
+                    .map(|reason| reason.to_string())
+                    .into_iter()
+                    .chain(
+                        self.app_state
+                            .update
+                            .stall
+                            .take_new_alarms()
+                            .into_iter()
+                            .map(|alarm| alarm.to_string()),
+                    )
+                    .collect::<Vec<_>>();
+
+                if !messages.is_empty() {
+                    return Task::batch(messages.into_iter().map(|message| {
+                        self.toasts
+                            .push(Toast::new(message))
+                            .map(cosmic::action::app)
+                    }));
+                }
             }
 
             AppMsg::ModifNode(id, modif_node_msg) => {
@@ -168,6 +224,14 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
                                     }
                                 }
 
+                                // the exit policy follows the control to its new hardware
+                                if let Some(control_h) = &i.control_h {
+                                    if let Err(e) = bridge.set_on_exit(control_h, OnExit::default())
+                                    {
+                                        error!("Can't reset the exit policy: {e}.");
+                                    }
+                                }
+
                                 i.hardware_id = hardware_id;
                                 i.control_h = match &i.hardware_id {
                                     Some(hardware_id) => bridge
@@ -178,6 +242,10 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
                                         .cloned(),
 
                                     None => None,
+                                };
+
+                                if let Err(e) = i.set_on_exit(bridge) {
+                                    error!("Can't set the exit policy of {}: {e}.", i.name);
                                 }
                             }
                             NodeType::Fan(i) => {
@@ -206,6 +274,19 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
                                     None => None,
                                 }
                             }
+                            NodeType::Sensor(i) => {
+                                i.hardware_id = hardware_id;
+                                i.sensor_h = match &i.hardware_id {
+                                    Some(hardware_id) => bridge
+                                        .hardware()
+                                        .sensors(i.kind)
+                                        .iter()
+                                        .find(|h| &h.hardware_id == hardware_id)
+                                        .cloned(),
+
+                                    None => None,
+                                }
+                            }
                             _ => panic!("node have no hardware id"),
                         }
                     }
@@ -225,6 +306,19 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
                             NodeType::Graph(i) => i.input = optional_name,
                             NodeType::Linear(i, ..) => i.input = optional_name,
                             NodeType::Target(i, ..) => i.input = optional_name,
+                            NodeType::Hysteresis(i) => {
+                                i.input = optional_name;
+                                // the last value came from the previous input
+                                i.reset();
+                            }
+                            NodeType::Smoothing(i) => {
+                                i.input = optional_name;
+                                i.reset();
+                            }
+                            NodeType::Pid(i) => {
+                                i.input = optional_name;
+                                i.reset();
+                            }
                             _ => panic!("node have not exactly one input"),
                         }
                     }
@@ -246,18 +340,66 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
                             _ => panic!("node have not multiple inputs"),
                         }
                     }
-                    ModifNodeMsg::Control(control_msg) => match control_msg {
-                        ControlMsg::Active(is_active) => {
-                            let control = node.node_type.unwrap_control_mut();
-                            control.active = is_active;
+                    ModifNodeMsg::Control(control_msg) => {
+                        let control = node.node_type.unwrap_control_mut();
+                        let control_c = self.nodes_c.get_mut(&id).node_type_c.unwrap_control_mut();
+
+                        match control_msg {
+                            ControlMsg::Active(is_active) => {
+                                control.active = is_active;
+                            }
+                            ControlMsg::Min(min, cached_value) => {
+                                control.min = Some(min);
+                                control_c.min = cached_value;
+                            }
+                            ControlMsg::Max(max, cached_value) => {
+                                control.max = Some(max);
+                                control_c.max = cached_value;
+                            }
+                            ControlMsg::Step(step, cached_value) => {
+                                control.step = Some(step);
+                                control_c.step = cached_value;
+                            }
+                            ControlMsg::Invert(invert) => {
+                                control.invert = invert;
+                            }
+                            ControlMsg::ZeroRpm(zero_rpm, cached_value) => {
+                                control.zero_rpm = Some(zero_rpm);
+                                control_c.zero_rpm = cached_value;
+                            }
+                            ControlMsg::Kick(kick, cached_value) => {
+                                control.kick = Some(kick);
+                                control_c.kick = cached_value;
+                            }
+                            ControlMsg::RampUp(ramp_up, cached_value) => {
+                                control.ramp_up = Some(ramp_up);
+                                control_c.ramp_up = cached_value;
+                            }
+                            ControlMsg::RampDown(ramp_down, cached_value) => {
+                                control.ramp_down = Some(ramp_down);
+                                control_c.ramp_down = cached_value;
+                            }
+                            ControlMsg::Fan(fan) => {
+                                control.fan = fan;
+                            }
                         }
-                    },
+                    }
                     ModifNodeMsg::CustomTemp(custom_temp_msg) => match custom_temp_msg {
                         CustomTempMsg::Kind(kind) => {
                             let custom_temp = node.node_type.unwrap_custom_temp_mut();
                             custom_temp.kind = kind;
                         }
                     },
+                    ModifNodeMsg::Sensor(sensor_msg) => match sensor_msg {
+                        SensorMsg::Kind(kind) => {
+                            let sensor = node.node_type.unwrap_sensor_mut();
+                            sensor.kind = kind;
+                            // ids are not shared between kinds
+                            sensor.hardware_id.take();
+                            sensor.sensor_h.take();
+                            node.value.take();
+                        }
+                    },
                     ModifNodeMsg::Flat(flat_msg) => match flat_msg {
                         FlatMsg::Value(value) => {
                             let flat = node.node_type.unwrap_flat_mut();
@@ -311,6 +453,140 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
                             }
                         }
                     }
+                    ModifNodeMsg::Hysteresis(hysteresis_msg) => {
+                        let hysteresis = node.node_type.unwrap_hysteresis_mut();
+                        let hysteresis_c = self
+                            .nodes_c
+                            .get_mut(&id)
+                            .node_type_c
+                            .unwrap_hysteresis_mut();
+
+                        match hysteresis_msg {
+                            HysteresisMsg::UpDelta(up_delta, cached_value) => {
+                                hysteresis.up_delta = up_delta;
+                                hysteresis_c.up_delta = cached_value;
+                            }
+                            HysteresisMsg::DownDelta(down_delta, cached_value) => {
+                                hysteresis.down_delta = down_delta;
+                                hysteresis_c.down_delta = cached_value;
+                            }
+                            HysteresisMsg::MinInterval(min_interval, cached_value) => {
+                                hysteresis.min_interval = min_interval;
+                                hysteresis_c.min_interval = cached_value;
+                            }
+                        }
+                    }
+                    ModifNodeMsg::Smoothing(smoothing_msg) => {
+                        let smoothing = node.node_type.unwrap_smoothing_mut();
+                        let smoothing_c =
+                            self.nodes_c.get_mut(&id).node_type_c.unwrap_smoothing_mut();
+
+                        match smoothing_msg {
+                            SmoothingMsg::Kind(kind) => {
+                                smoothing.kind = kind;
+                                smoothing.reset();
+                            }
+                            SmoothingMsg::Samples(samples, cached_value) => {
+                                smoothing.samples = samples;
+                                smoothing_c.samples = cached_value;
+                            }
+                            SmoothingMsg::TimeConstant(time_constant, cached_value) => {
+                                smoothing.time_constant = time_constant;
+                                smoothing_c.time_constant = cached_value;
+                            }
+                        }
+                    }
+                    ModifNodeMsg::Pid(pid_msg) => {
+                        let pid = node.node_type.unwrap_pid_mut();
+                        let pid_c = self.nodes_c.get_mut(&id).node_type_c.unwrap_pid_mut();
+
+                        match pid_msg {
+                            PidMsg::Setpoint(setpoint, cached_value) => {
+                                pid.setpoint = setpoint;
+                                pid_c.setpoint = cached_value;
+                            }
+                            PidMsg::Kp(kp, cached_value) => {
+                                pid.kp = kp;
+                                pid_c.kp = cached_value;
+                            }
+                            PidMsg::Ki(ki, cached_value) => {
+                                pid.ki = ki;
+                                pid_c.ki = cached_value;
+                            }
+                            PidMsg::Kd(kd, cached_value) => {
+                                pid.kd = kd;
+                                pid_c.kd = cached_value;
+                            }
+                            PidMsg::MinSpeed(min_speed, cached_value) => {
+                                pid.min_speed = min_speed;
+                                pid_c.min_speed = cached_value;
+                            }
+                            PidMsg::MaxSpeed(max_speed, cached_value) => {
+                                pid.max_speed = max_speed;
+                                pid_c.max_speed = cached_value;
+                            }
+                            PidMsg::DerivativeFilter(derivative_filter, cached_value) => {
+                                pid.derivative_filter = derivative_filter;
+                                pid_c.derivative_filter = cached_value;
+                            }
+                        }
+                    }
+                    ModifNodeMsg::RpmTarget(rpm_target_msg) => {
+                        let rpm_target = node.node_type.unwrap_rpm_target_mut();
+                        let rpm_target_c = self
+                            .nodes_c
+                            .get_mut(&id)
+                            .node_type_c
+                            .unwrap_rpm_target_mut();
+
+                        match rpm_target_msg {
+                            RpmTargetMsg::Fan(fan) => {
+                                // the fan is the first input
+                                if rpm_target.fan.is_some() {
+                                    node.inputs.remove(0);
+                                }
+                                if let Some(fan) = &fan {
+                                    node.inputs.insert(0, fan.clone());
+                                }
+                                rpm_target.fan = fan.map(|fan| fan.name);
+                                rpm_target.reset();
+                            }
+                            RpmTargetMsg::Target(target) => {
+                                // the target is the last input
+                                if rpm_target.target.is_some() {
+                                    node.inputs.pop();
+                                }
+                                if let Some(target) = &target {
+                                    node.inputs.push(target.clone());
+                                }
+                                rpm_target.target = target.map(|target| target.name);
+                            }
+                            RpmTargetMsg::Rpm(rpm, cached_value) => {
+                                rpm_target.rpm = rpm;
+                                rpm_target_c.rpm = cached_value;
+                            }
+                            RpmTargetMsg::MaxRpm(max_rpm, cached_value) => {
+                                rpm_target.max_rpm = max_rpm;
+                                rpm_target_c.max_rpm = cached_value;
+                            }
+                            RpmTargetMsg::Gain(gain, cached_value) => {
+                                rpm_target.gain = gain;
+                                rpm_target_c.gain = cached_value;
+                            }
+                            RpmTargetMsg::Tolerance(tolerance, cached_value) => {
+                                rpm_target.tolerance = tolerance;
+                                rpm_target_c.tolerance = cached_value;
+                            }
+                            RpmTargetMsg::MinSpeed(min_speed, cached_value) => {
+                                rpm_target.min_speed = min_speed;
+                                rpm_target_c.min_speed = cached_value;
+                            }
+                            RpmTargetMsg::MaxSpeed(max_speed, cached_value) => {
+                                rpm_target.max_speed = max_speed;
+                                rpm_target_c.max_speed = cached_value;
+                            }
+                        }
+                    }
                     ModifNodeMsg::Delete => {
                         match self.app_state.app_graph.remove_node(id) {
                             Some(mut node) => {
@@ -326,7 +602,11 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
                         }
 
                         self.nodes_c.remove(&id);
-                        self.app_state.app_graph.sanitize_inputs(false)
+                        self.app_state.app_graph.sanitize_inputs(false);
+                        self.app_state.update.set_on_exit(
+                            &self.app_state.app_graph.nodes,
+                            &mut self.app_state.bridge,
+                        );
                     }
                     ModifNodeMsg::Graph(graph_msg) => {
                         let graph = node.node_type.unwrap_graph_mut();
@@ -433,9 +713,18 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
                         Ok(config) => match config {
                             Some((config_name, config)) => {
                                 self.current_config_cached = config_name;
+                                if let Some(remap_dialog) =
+                                    Dialog::remap(config.clone(), self.app_state.bridge.hardware())
+                                {
+                                    self.dialog = Some(remap_dialog);
+                                }
                                 self.app_state
                                     .app_graph
                                     .apply_config(config, self.app_state.bridge.hardware());
+                                self.app_state.update.set_on_exit(
+                                    &self.app_state.app_graph.nodes,
+                                    &mut self.app_state.bridge,
+                                );
                                 self.nodes_c = NodesC::new(self.app_state.app_graph.nodes.values());
 
                                 self.update_hardware();
@@ -488,10 +777,22 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
                     node.node_type.set_name(name.clone());
 
                     let node_id = node.id;
+                    let is_temp = matches!(node.node_type, NodeType::Temp(_));
 
                     // find nodes that depend on node.id
                     // change the name in input and item.input
                     for n in self.app_state.app_graph.nodes.values_mut() {
+                        if let NodeType::Control(control) = &mut n.node_type {
+                            if control.fan.as_ref() == Some(&previous_name) {
+                                control.fan = Some(name.clone());
+                            }
+                            for fan in &mut control.fans {
+                                if fan == &previous_name {
+                                    fan.clone_from(&name);
+                                }
+                            }
+                        }
+
                         if let Some(node_input) = n
                             .inputs
                             .iter_mut()
@@ -513,6 +814,14 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
                             }
                         }
                     }
+
+                    if is_temp {
+                        for critical_temp in &mut self.app_state.app_graph.failsafe.temps {
+                            if critical_temp.name == previous_name {
+                                critical_temp.name.clone_from(&name);
+                            }
+                        }
+                    }
                 } else {
                     node_c.is_error_name = true;
                 }
@@ -606,7 +915,7 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
     }
 
     fn header_end(&self) -> Vec<Element<Self::Message>> {
-        headers::header_end()
+        headers::header_end(&self.app_state)
     }
 
     fn context_drawer(&self) -> Option<ContextDrawer<Self::Message>> {
@@ -632,6 +941,12 @@ impl<H: HardwareBridge + 'static> cosmic::Application for Ui<H> {
     }
 
     fn on_app_exit(&mut self) -> Option<Self::Message> {
+        self.stop_calibration();
+
+        self.app_state
+            .update
+            .set_on_exit(&self.app_state.app_graph.nodes, &mut self.app_state.bridge);
+
         if let Err(e) = self.app_state.bridge.shutdown() {
             error!("shutdown hardware: {}", e);
         }
@@ -724,6 +1039,7 @@ impl<H: HardwareBridge> Ui<H> {
         }
         if let Err(e) = self.app_state.update.all(
             &mut self.app_state.app_graph.nodes,
+            &self.app_state.app_graph.failsafe,
             &mut self.app_state.bridge,
         ) {
             error!("{}", e);
@@ -1,11 +1,13 @@
 use cosmic::widget::ToastId;
 use data::{
-    config::{custom_temp::CustomTempKind, graph::Coord},
+    config::{custom_temp::CustomTempKind, graph::Coord, smoothing::SmoothingKind},
     id::Id,
     node::{Input, NodeTypeLight},
     settings::AppTheme,
 };
 
+use hardware::{SensorKind, Value};
+
 use crate::{dialogs::DialogMsg, graph::GraphWindowMsg};
 
 #[derive(Debug, Clone)]
@@ -64,15 +66,29 @@ pub enum ModifNodeMsg {
 
     Control(ControlMsg),
     CustomTemp(CustomTempMsg),
+    Sensor(SensorMsg),
     Flat(FlatMsg),
     Linear(LinearMsg),
     Target(TargetMsg),
+    Hysteresis(HysteresisMsg),
+    Smoothing(SmoothingMsg),
+    Pid(PidMsg),
+    RpmTarget(RpmTargetMsg),
     Graph(GraphMsg),
 }
 
 #[derive(Debug, Clone)]
 pub enum ControlMsg {
     Active(bool),
+    Min(u8, String),
+    Max(u8, String),
+    Step(u8, String),
+    Invert(bool),
+    ZeroRpm(u8, String),
+    Kick(u8, String),
+    RampUp(Value, String),
+    RampDown(Value, String),
+    Fan(Option<String>),
 }
 
 #[derive(Debug, Clone)]
@@ -80,6 +96,11 @@ pub enum CustomTempMsg {
     Kind(CustomTempKind),
 }
 
+#[derive(Debug, Clone)]
+pub enum SensorMsg {
+    Kind(SensorKind),
+}
+
 #[derive(Debug, Clone)]
 pub enum FlatMsg {
     Value(u16),
@@ -87,20 +108,57 @@ pub enum FlatMsg {
 
 #[derive(Debug, Clone)]
 pub enum LinearMsg {
-    MinTemp(u8, String),
+    MinTemp(Value, String),
     MinSpeed(u8, String),
-    MaxTemp(u8, String),
+    MaxTemp(Value, String),
     MaxSpeed(u8, String),
 }
 
 #[derive(Debug, Clone)]
 pub enum TargetMsg {
-    IdleTemp(u8, String),
+    IdleTemp(Value, String),
     IdleSpeed(u8, String),
-    LoadTemp(u8, String),
+    LoadTemp(Value, String),
     LoadSpeed(u8, String),
 }
 
+#[derive(Debug, Clone)]
+pub enum HysteresisMsg {
+    UpDelta(Value, String),
+    DownDelta(Value, String),
+    MinInterval(u32, String),
+}
+
+#[derive(Debug, Clone)]
+pub enum PidMsg {
+    Setpoint(Value, String),
+    Kp(Value, String),
+    Ki(Value, String),
+    Kd(Value, String),
+    MinSpeed(u8, String),
+    MaxSpeed(u8, String),
+    DerivativeFilter(Value, String),
+}
+
+#[derive(Debug, Clone)]
+pub enum RpmTargetMsg {
+    Fan(Option<Input>),
+    Target(Option<Input>),
+    Rpm(u32, String),
+    MaxRpm(u32, String),
+    Gain(Value, String),
+    Tolerance(u32, String),
+    MinSpeed(u8, String),
+    MaxSpeed(u8, String),
+}
+
+#[derive(Debug, Clone)]
+pub enum SmoothingMsg {
+    Kind(SmoothingKind),
+    Samples(u8, String),
+    TimeConstant(Value, String),
+}
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone)]
 pub enum GraphMsg {
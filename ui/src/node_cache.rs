@@ -25,14 +25,27 @@ pub enum NodeTypeC {
     Fan(FanC),
     Temp(TempC),
     CustomTemp(CustomTempC),
+    Sensor(SensorC),
     Graph(GraphC),
     Flat(FlatC),
     Linear(LinearC),
     Target(TargetC),
+    Hysteresis(HysteresisC),
+    Smoothing(SmoothingC),
+    Pid(PidC),
+    RpmTarget(RpmTargetC),
 }
 
 #[derive(Debug, Clone)]
-pub struct ControlC {}
+pub struct ControlC {
+    pub min: String,
+    pub max: String,
+    pub step: String,
+    pub zero_rpm: String,
+    pub kick: String,
+    pub ramp_up: String,
+    pub ramp_down: String,
+}
 
 #[derive(Debug, Clone)]
 pub struct FanC {}
@@ -43,6 +56,9 @@ pub struct TempC {}
 #[derive(Debug, Clone)]
 pub struct CustomTempC {}
 
+#[derive(Debug, Clone)]
+pub struct SensorC {}
+
 #[derive(Debug, Clone)]
 pub struct GraphC {}
 
@@ -65,6 +81,40 @@ pub struct TargetC {
     pub load_speed: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct HysteresisC {
+    pub up_delta: String,
+    pub down_delta: String,
+    pub min_interval: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PidC {
+    pub setpoint: String,
+    pub kp: String,
+    pub ki: String,
+    pub kd: String,
+    pub min_speed: String,
+    pub max_speed: String,
+    pub derivative_filter: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RpmTargetC {
+    pub rpm: String,
+    pub max_rpm: String,
+    pub gain: String,
+    pub tolerance: String,
+    pub min_speed: String,
+    pub max_speed: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SmoothingC {
+    pub samples: String,
+    pub time_constant: String,
+}
+
 impl NodesC {
     pub fn new<'a>(nodes: impl Iterator<Item = &'a Node>) -> Self {
         let mut data = HashMap::new();
@@ -111,10 +161,19 @@ impl NodeC {
 impl NodeTypeC {
     pub fn new(node_type: &NodeType) -> Self {
         match node_type {
-            data::node::NodeType::Control(_) => NodeTypeC::Control(ControlC {}),
+            data::node::NodeType::Control(control) => NodeTypeC::Control(ControlC {
+                min: control.min().to_string(),
+                max: control.max().to_string(),
+                step: control.step().to_string(),
+                zero_rpm: control.zero_rpm().to_string(),
+                kick: control.kick().to_string(),
+                ramp_up: control.ramp_up().to_string(),
+                ramp_down: control.ramp_down().to_string(),
+            }),
             data::node::NodeType::Fan(_) => NodeTypeC::Fan(FanC {}),
             data::node::NodeType::Temp(_) => NodeTypeC::Temp(TempC {}),
             data::node::NodeType::CustomTemp(_) => NodeTypeC::CustomTemp(CustomTempC {}),
+            data::node::NodeType::Sensor(_) => NodeTypeC::Sensor(SensorC {}),
             data::node::NodeType::Graph(_) => NodeTypeC::Graph(GraphC {}),
             data::node::NodeType::Flat(_) => NodeTypeC::Flat(FlatC {}),
             data::node::NodeType::Linear(linear) => NodeTypeC::Linear(LinearC {
@@ -129,6 +188,32 @@ impl NodeTypeC {
                 load_temp: target.load_temp.to_string(),
                 load_speed: target.load_speed.to_string(),
             }),
+            data::node::NodeType::Hysteresis(hysteresis) => NodeTypeC::Hysteresis(HysteresisC {
+                up_delta: hysteresis.up_delta.to_string(),
+                down_delta: hysteresis.down_delta.to_string(),
+                min_interval: hysteresis.min_interval.to_string(),
+            }),
+            data::node::NodeType::Smoothing(smoothing) => NodeTypeC::Smoothing(SmoothingC {
+                samples: smoothing.samples.to_string(),
+                time_constant: smoothing.time_constant.to_string(),
+            }),
+            data::node::NodeType::Pid(pid) => NodeTypeC::Pid(PidC {
+                setpoint: pid.setpoint.to_string(),
+                kp: pid.kp.to_string(),
+                ki: pid.ki.to_string(),
+                kd: pid.kd.to_string(),
+                min_speed: pid.min_speed.to_string(),
+                max_speed: pid.max_speed.to_string(),
+                derivative_filter: pid.derivative_filter.to_string(),
+            }),
+            data::node::NodeType::RpmTarget(rpm_target) => NodeTypeC::RpmTarget(RpmTargetC {
+                rpm: rpm_target.rpm.to_string(),
+                max_rpm: rpm_target.max_rpm.to_string(),
+                gain: rpm_target.gain.to_string(),
+                tolerance: rpm_target.tolerance.to_string(),
+                min_speed: rpm_target.min_speed.to_string(),
+                max_speed: rpm_target.max_speed.to_string(),
+            }),
         }
     }
 }
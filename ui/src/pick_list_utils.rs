@@ -38,7 +38,7 @@ pub mod input {
     use data::{
         app_graph::Nodes,
         id::Id,
-        node::{Input, Node},
+        node::{self, Input, Node},
     };
 
     use super::MyOption;
@@ -72,15 +72,14 @@ pub mod input {
         nodes
             .values()
             .filter(|n| {
-                node.node_type
-                    .allowed_dep()
-                    .contains(&n.node_type.to_light())
+                node::is_allowed_input(nodes, node, n)
                     && !node
                         .inputs
                         .iter()
                         .map(|i| i.id)
                         .collect::<Vec<_>>()
                         .contains(&n.id)
+                    && !node::depends_on(nodes, &n.id, &node.id)
             })
             .map(|n| Input {
                 id: n.id,
@@ -102,15 +101,14 @@ pub mod input {
         let values = nodes
             .values()
             .filter(|n| {
-                node.node_type
-                    .allowed_dep()
-                    .contains(&n.node_type.to_light())
+                node::is_allowed_input(nodes, node, n)
                     && !node
                         .inputs
                         .iter()
                         .map(|i| i.id)
                         .collect::<Vec<_>>()
                         .contains(&n.id)
+                    && !node::depends_on(nodes, &n.id, &node.id)
             })
             .map(|n| {
                 MyOption::Some(Input {